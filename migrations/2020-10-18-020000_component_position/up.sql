@@ -0,0 +1,3 @@
+-- Your SQL goes here
+
+ALTER TABLE `component` ADD COLUMN position int not null default 1 comment '同一零件信息下的序号(从1开始)' AFTER maintain_interval;
@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE `component` DROP foreign key `fk_component_componentinfo`, DROP COLUMN component_info_id;
+
+ALTER TABLE `subsystem` DROP foreign key `fk_subsystem_subsysteminfo`, DROP COLUMN subsystem_info_id;
+
+ALTER TABLE `device` DROP foreign key `fk_device_deviceinfo`, DROP COLUMN device_info_id;
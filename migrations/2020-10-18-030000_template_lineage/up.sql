@@ -0,0 +1,13 @@
+-- Your SQL goes here
+
+ALTER TABLE `device`
+    ADD COLUMN device_info_id int comment '来源设备信息id' AFTER id,
+    ADD foreign key `fk_device_deviceinfo` (device_info_id) references `device_info` (id) on delete set null;
+
+ALTER TABLE `subsystem`
+    ADD COLUMN subsystem_info_id int comment '来源子系统信息id' AFTER device_id,
+    ADD foreign key `fk_subsystem_subsysteminfo` (subsystem_info_id) references `subsystem_info` (id) on delete set null;
+
+ALTER TABLE `component`
+    ADD COLUMN component_info_id int comment '来源零件信息id' AFTER subsystem_id,
+    ADD foreign key `fk_component_componentinfo` (component_info_id) references `component_info` (id) on delete set null;
@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE `component` DROP COLUMN last_maintain_at, DROP COLUMN last_maintain_duration;
+
+ALTER TABLE `subsystem` DROP COLUMN last_maintain_at, DROP COLUMN last_maintain_duration;
+
+ALTER TABLE `device` DROP COLUMN last_maintain_at, DROP COLUMN last_maintain_duration;
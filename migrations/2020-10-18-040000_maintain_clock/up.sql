@@ -0,0 +1,13 @@
+-- Your SQL goes here
+
+ALTER TABLE `device`
+    ADD COLUMN last_maintain_at datetime comment '最后维护时间',
+    ADD COLUMN last_maintain_duration int not null default 0 comment '最后维护时的积累运行时间(小时)';
+
+ALTER TABLE `subsystem`
+    ADD COLUMN last_maintain_at datetime comment '最后维护时间',
+    ADD COLUMN last_maintain_duration int not null default 0 comment '最后维护时的积累运行时间(小时)';
+
+ALTER TABLE `component`
+    ADD COLUMN last_maintain_at datetime comment '最后维护时间',
+    ADD COLUMN last_maintain_duration int not null default 0 comment '最后维护时的积累运行时间(小时)';
@@ -0,0 +1,22 @@
+-- Your SQL goes here
+
+CREATE TABLE IF NOT EXISTS `work_order` (
+    id int not null auto_increment comment 'id',
+    device_id int not null comment '设备id',
+    subsystem_id int comment '子系统id',
+    component_id int comment '零件id',
+    target varchar(32) not null comment '维护对象: Device-设备; Subsystem-子系统; Component-零件',
+    status varchar(32) not null default 'Planned' comment '状态: Planned-计划; InProgress-进行中; Done-完成; Cancelled-取消',
+    technician varchar(64) not null comment '维护人员',
+    notes text comment '备注',
+    planned_at datetime comment '计划时间',
+    started_at datetime comment '开始时间',
+    finished_at datetime comment '完成时间',
+    create_at timestamp not null default current_timestamp comment '创建时间',
+    update_at timestamp not null default current_timestamp on update current_timestamp comment '更新时间',
+    primary key (id),
+    key `idx_status` (status),
+    foreign key `fk_workorder_device` (device_id) references `device` (id) on delete cascade,
+    foreign key `fk_workorder_subsystem` (subsystem_id) references `subsystem` (id) on delete cascade,
+    foreign key `fk_workorder_component` (component_id) references `component` (id) on delete cascade
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin COMMENT '维护工单';
@@ -0,0 +1,13 @@
+-- Your SQL goes here
+
+CREATE TABLE IF NOT EXISTS `device_event` (
+    id int not null auto_increment comment 'id',
+    device_id int not null comment '设备id',
+    kind varchar(32) not null comment '事件: Start-开车; Stop-停车',
+    happen_at datetime not null comment '发生时间',
+    duration int not null default 0 comment '本次运行时间(小时), 仅停车事件',
+    create_at timestamp not null default current_timestamp comment '创建时间',
+    primary key (id),
+    key `idx_device_happen` (device_id, happen_at),
+    foreign key `fk_event_device` (device_id) references `device` (id) on delete cascade
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin COMMENT '设备开停车事件';
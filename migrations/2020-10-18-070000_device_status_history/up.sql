@@ -0,0 +1,15 @@
+-- Your SQL goes here
+
+CREATE TABLE IF NOT EXISTS `device_status_history` (
+    id int not null auto_increment comment 'id',
+    device_id int not null comment '设备id',
+    from_status varchar(32) not null comment '原状态',
+    to_status varchar(32) not null comment '新状态',
+    reason varchar(32) not null comment '原因: Scheduled-计划; Manual-手动; Maintenance-维护; Fault-故障; Repaired-修复',
+    description text comment '说明',
+    happen_at datetime not null comment '发生时间',
+    create_at timestamp not null default current_timestamp comment '创建时间',
+    primary key (id),
+    key `idx_device_happen` (device_id, happen_at),
+    foreign key `fk_history_device` (device_id) references `device` (id) on delete cascade
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin COMMENT '设备状态变更历史';
@@ -0,0 +1,20 @@
+-- Your SQL goes here
+
+CREATE TABLE IF NOT EXISTS `fault_ticket` (
+    id int not null auto_increment comment 'id',
+    device_id int not null comment '设备id',
+    subsystem_id int not null comment '故障子系统id',
+    component_id int comment '故障零件id',
+    failure_code varchar(64) not null comment '故障代码',
+    description text comment '故障描述',
+    reported_at datetime not null comment '报修时间',
+    repaired_at datetime comment '修复时间',
+    resolution text comment '处理结果',
+    create_at timestamp not null default current_timestamp comment '创建时间',
+    update_at timestamp not null default current_timestamp on update current_timestamp comment '更新时间',
+    primary key (id),
+    key `idx_device_reported` (device_id, reported_at),
+    foreign key `fk_fault_device` (device_id) references `device` (id) on delete cascade,
+    foreign key `fk_fault_subsystem` (subsystem_id) references `subsystem` (id) on delete cascade,
+    foreign key `fk_fault_component` (component_id) references `component` (id) on delete cascade
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin COMMENT '故障工单';
@@ -0,0 +1,9 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE IF EXISTS `reorder_point`;
+
+DROP TABLE IF EXISTS `stock_movement`;
+
+DROP TABLE IF EXISTS `stock`;
+
+DROP TABLE IF EXISTS `storage_location`;
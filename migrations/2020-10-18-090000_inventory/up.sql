@@ -0,0 +1,46 @@
+-- Your SQL goes here
+
+CREATE TABLE IF NOT EXISTS `storage_location` (
+    id int not null auto_increment comment 'id',
+    name varchar(64) not null comment '库位名称',
+    create_at timestamp not null default current_timestamp comment '创建时间',
+    update_at timestamp not null default current_timestamp on update current_timestamp comment '更新时间',
+    primary key (id),
+    unique key `uni_name` (name)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin COMMENT '库位';
+
+CREATE TABLE IF NOT EXISTS `stock` (
+    id int not null auto_increment comment 'id',
+    component_info_id int not null comment '零件信息id',
+    location_id int not null comment '库位id',
+    quantity int not null default 0 comment '库存数量',
+    update_at timestamp not null default current_timestamp on update current_timestamp comment '更新时间',
+    primary key (id),
+    unique key `uni_cominfo_location` (component_info_id, location_id),
+    foreign key `fk_stock_componentinfo` (component_info_id) references `component_info` (id) on delete cascade,
+    foreign key `fk_stock_location` (location_id) references `storage_location` (id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin COMMENT '备件库存';
+
+CREATE TABLE IF NOT EXISTS `stock_movement` (
+    id int not null auto_increment comment 'id',
+    component_info_id int not null comment '零件信息id',
+    kind varchar(32) not null comment '类型: Receive-入库; Issue-出库; Adjust-盘点调整; Transfer-移库',
+    from_location_id int comment '来源库位id',
+    to_location_id int comment '目标库位id',
+    quantity int not null comment '数量, 盘点调整时为增减量',
+    note text comment '备注',
+    happen_at datetime not null comment '发生时间',
+    create_at timestamp not null default current_timestamp comment '创建时间',
+    primary key (id),
+    key `idx_cominfo_happen` (component_info_id, happen_at),
+    foreign key `fk_movement_componentinfo` (component_info_id) references `component_info` (id),
+    foreign key `fk_movement_from` (from_location_id) references `storage_location` (id),
+    foreign key `fk_movement_to` (to_location_id) references `storage_location` (id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin COMMENT '备件出入库流水';
+
+CREATE TABLE IF NOT EXISTS `reorder_point` (
+    component_info_id int not null comment '零件信息id',
+    quantity int not null comment '再订货点',
+    primary key (component_info_id),
+    foreign key `fk_reorder_componentinfo` (component_info_id) references `component_info` (id) on delete cascade
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin COMMENT '备件再订货点';
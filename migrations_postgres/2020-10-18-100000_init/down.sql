@@ -0,0 +1,34 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS "reorder_point" CASCADE;
+
+DROP TABLE IF EXISTS "stock_movement" CASCADE;
+
+DROP TABLE IF EXISTS "stock" CASCADE;
+
+DROP TABLE IF EXISTS "storage_location" CASCADE;
+
+DROP TABLE IF EXISTS "fault_ticket" CASCADE;
+
+DROP TABLE IF EXISTS "device_status_history" CASCADE;
+
+DROP TABLE IF EXISTS "device_event" CASCADE;
+
+DROP TABLE IF EXISTS "work_order" CASCADE;
+
+DROP TABLE IF EXISTS "subsysteminfo_componentinfo" CASCADE;
+
+DROP TABLE IF EXISTS "deviceinfo_subsysteminfo" CASCADE;
+
+DROP TABLE IF EXISTS "component" CASCADE;
+
+DROP TABLE IF EXISTS "component_info" CASCADE;
+
+DROP TABLE IF EXISTS "subsystem" CASCADE;
+
+DROP TABLE IF EXISTS "subsystem_info" CASCADE;
+
+DROP TABLE IF EXISTS "device" CASCADE;
+
+DROP TABLE IF EXISTS "device_info" CASCADE;
+
+DROP FUNCTION IF EXISTS set_update_at();
@@ -0,0 +1,215 @@
+-- Your SQL goes here
+
+CREATE TABLE IF NOT EXISTS "device_info" (
+    id serial primary key, -- id
+    name varchar(64) not null, -- 名称
+    model varchar(255) not null, -- 型号
+    maintain_interval int not null default 0, -- 维护间隔
+    create_at timestamp not null default current_timestamp, -- 创建时间
+    update_at timestamp not null default current_timestamp, -- 更新时间
+    constraint "uni_deviceinfo_name_model" unique (name, model)
+);
+
+CREATE TABLE IF NOT EXISTS "device" (
+    id serial primary key, -- id
+    device_info_id int references "device_info" (id) on delete set null, -- 来源设备信息id
+    name varchar(64) not null, -- 名称
+    model varchar(255) not null, -- 型号
+    maintain_interval int not null default 0, -- 维护间隔(小时)
+    unicode varchar(255) not null, -- 唯一识别码
+    last_start_at timestamp, -- 最后的开车时间
+    last_stop_at timestamp, -- 最后停车时间
+    total_duration int not null default 0, -- 积累运行时间(小时)
+    status varchar(32) not null default 'Stopped', -- 状态: Running-运转; Stopped-停车; Breakdown-故障
+    create_at timestamp not null default current_timestamp, -- 创建时间
+    update_at timestamp not null default current_timestamp, -- 更新时间
+    last_maintain_at timestamp, -- 最后维护时间
+    last_maintain_duration int not null default 0 -- 最后维护时的积累运行时间(小时)
+);
+
+CREATE TABLE IF NOT EXISTS "subsystem_info" (
+    id serial primary key, -- id
+    name varchar(64) not null, -- 名称
+    maintain_interval int not null default 0, -- 维护间隔(小时)
+    create_at timestamp not null default current_timestamp, -- 创建时间
+    update_at timestamp not null default current_timestamp, -- 更新时间
+    constraint "uni_subsysteminfo_name" unique (name)
+);
+
+CREATE TABLE IF NOT EXISTS "subsystem" (
+    id serial primary key, -- id
+    device_id int not null references "device" (id), -- 所属设备id
+    subsystem_info_id int references "subsystem_info" (id) on delete set null, -- 来源子系统信息id
+    name varchar(64) not null, -- 名称
+    maintain_interval int not null default 0, -- 维护间隔(小时)
+    create_at timestamp not null default current_timestamp, -- 创建时间
+    update_at timestamp not null default current_timestamp, -- 更新时间
+    last_maintain_at timestamp, -- 最后维护时间
+    last_maintain_duration int not null default 0, -- 最后维护时的积累运行时间(小时)
+    constraint "uni_deviceid_name" unique (device_id, name)
+);
+
+CREATE TABLE IF NOT EXISTS "component_info" (
+    id serial primary key, -- id
+    name varchar(64) not null, -- 名称
+    model varchar(255) not null, -- 型号
+    maintain_interval int not null default 0, -- 维护时间间隔(小时)
+    create_at timestamp not null default current_timestamp, -- 创建时间
+    update_at timestamp not null default current_timestamp, -- 更新时间
+    constraint "uni_componentinfo_name_model" unique (name, model)
+);
+
+CREATE TABLE IF NOT EXISTS "component" (
+    id serial primary key, -- id
+    subsystem_id int not null references "subsystem" (id) on delete cascade, -- 子系统id
+    component_info_id int references "component_info" (id) on delete set null, -- 来源零件信息id
+    name varchar(64) not null, -- 名称
+    model varchar(255) not null, -- 型号
+    maintain_interval int not null default 0, -- 维护间隔(小时)
+    position int not null default 1, -- 同一零件信息下的序号(从1开始)
+    create_at timestamp not null default current_timestamp, -- 创建时间
+    update_at timestamp not null default current_timestamp, -- 更新时间
+    last_maintain_at timestamp, -- 最后维护时间
+    last_maintain_duration int not null default 0 -- 最后维护时的积累运行时间(小时)
+);
+
+CREATE TABLE IF NOT EXISTS "deviceinfo_subsysteminfo" (
+    id serial primary key, -- id
+    device_info_id int not null references "device_info" (id) on delete cascade, -- 设备信息id
+    subsystem_info_id int not null references "subsystem_info" (id) on delete cascade, -- 子系统信息id
+    constraint "uni_deviceinfo_subsysteminfo" unique (device_info_id, subsystem_info_id)
+);
+
+CREATE TABLE IF NOT EXISTS "subsysteminfo_componentinfo" (
+    id serial primary key, -- id
+    device_info_id int not null references "device_info" (id) on delete cascade, -- 设备信息id
+    subsystem_info_id int not null references "subsystem_info" (id) on delete cascade, -- 子系统信息id
+    component_info_id int not null references "component_info" (id) on delete cascade, -- 零部件信息id
+    quantity int not null default 1, -- 数量
+    constraint "uni_devinfo_subinfo_cominf" unique (device_info_id, subsystem_info_id, component_info_id)
+);
+
+CREATE TABLE IF NOT EXISTS "work_order" (
+    id serial primary key, -- id
+    device_id int not null references "device" (id) on delete cascade, -- 设备id
+    subsystem_id int references "subsystem" (id) on delete cascade, -- 子系统id
+    component_id int references "component" (id) on delete cascade, -- 零件id
+    target varchar(32) not null, -- 维护对象: Device-设备; Subsystem-子系统; Component-零件
+    status varchar(32) not null default 'Planned', -- 状态: Planned-计划; InProgress-进行中; Done-完成; Cancelled-取消
+    technician varchar(64) not null, -- 维护人员
+    notes text, -- 备注
+    planned_at timestamp, -- 计划时间
+    started_at timestamp, -- 开始时间
+    finished_at timestamp, -- 完成时间
+    create_at timestamp not null default current_timestamp, -- 创建时间
+    update_at timestamp not null default current_timestamp -- 更新时间
+);
+
+CREATE INDEX IF NOT EXISTS "idx_workorder_status" ON "work_order" (status);
+
+CREATE TABLE IF NOT EXISTS "device_event" (
+    id serial primary key, -- id
+    device_id int not null references "device" (id) on delete cascade, -- 设备id
+    kind varchar(32) not null, -- 事件: Start-开车; Stop-停车
+    happen_at timestamp not null, -- 发生时间
+    duration int not null default 0, -- 本次运行时间(小时), 仅停车事件
+    create_at timestamp not null default current_timestamp -- 创建时间
+);
+
+CREATE INDEX IF NOT EXISTS "idx_event_device_happen" ON "device_event" (device_id, happen_at);
+
+CREATE TABLE IF NOT EXISTS "device_status_history" (
+    id serial primary key, -- id
+    device_id int not null references "device" (id) on delete cascade, -- 设备id
+    from_status varchar(32) not null, -- 原状态
+    to_status varchar(32) not null, -- 新状态
+    reason varchar(32) not null, -- 原因: Scheduled-计划; Manual-手动; Maintenance-维护; Fault-故障; Repaired-修复
+    description text, -- 说明
+    happen_at timestamp not null, -- 发生时间
+    create_at timestamp not null default current_timestamp -- 创建时间
+);
+
+CREATE INDEX IF NOT EXISTS "idx_history_device_happen" ON "device_status_history" (device_id, happen_at);
+
+CREATE TABLE IF NOT EXISTS "fault_ticket" (
+    id serial primary key, -- id
+    device_id int not null references "device" (id) on delete cascade, -- 设备id
+    subsystem_id int not null references "subsystem" (id) on delete cascade, -- 故障子系统id
+    component_id int references "component" (id) on delete cascade, -- 故障零件id
+    failure_code varchar(64) not null, -- 故障代码
+    description text, -- 故障描述
+    reported_at timestamp not null, -- 报修时间
+    repaired_at timestamp, -- 修复时间
+    resolution text, -- 处理结果
+    create_at timestamp not null default current_timestamp, -- 创建时间
+    update_at timestamp not null default current_timestamp -- 更新时间
+);
+
+CREATE INDEX IF NOT EXISTS "idx_device_reported" ON "fault_ticket" (device_id, reported_at);
+
+CREATE TABLE IF NOT EXISTS "storage_location" (
+    id serial primary key, -- id
+    name varchar(64) not null, -- 库位名称
+    create_at timestamp not null default current_timestamp, -- 创建时间
+    update_at timestamp not null default current_timestamp, -- 更新时间
+    constraint "uni_location_name" unique (name)
+);
+
+CREATE TABLE IF NOT EXISTS "stock" (
+    id serial primary key, -- id
+    component_info_id int not null references "component_info" (id) on delete cascade, -- 零件信息id
+    location_id int not null references "storage_location" (id), -- 库位id
+    quantity int not null default 0, -- 库存数量
+    update_at timestamp not null default current_timestamp, -- 更新时间
+    constraint "uni_cominfo_location" unique (component_info_id, location_id)
+);
+
+CREATE TABLE IF NOT EXISTS "stock_movement" (
+    id serial primary key, -- id
+    component_info_id int not null references "component_info" (id), -- 零件信息id
+    kind varchar(32) not null, -- 类型: Receive-入库; Issue-出库; Adjust-盘点调整; Transfer-移库
+    from_location_id int references "storage_location" (id), -- 来源库位id
+    to_location_id int references "storage_location" (id), -- 目标库位id
+    quantity int not null, -- 数量, 盘点调整时为增减量
+    note text, -- 备注
+    happen_at timestamp not null, -- 发生时间
+    create_at timestamp not null default current_timestamp -- 创建时间
+);
+
+CREATE INDEX IF NOT EXISTS "idx_cominfo_happen" ON "stock_movement" (component_info_id, happen_at);
+
+CREATE TABLE IF NOT EXISTS "reorder_point" (
+    component_info_id int not null primary key references "component_info" (id) on delete cascade, -- 零件信息id
+    quantity int not null -- 再订货点
+);
+
+-- PostgreSQL has no "on update current_timestamp", the triggers keep update_at current instead.
+
+CREATE OR REPLACE FUNCTION set_update_at() RETURNS trigger AS $$
+BEGIN
+    IF NEW.update_at IS NOT DISTINCT FROM OLD.update_at THEN
+        NEW.update_at := current_timestamp;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER "device_info_update_at" BEFORE UPDATE ON "device_info" FOR EACH ROW EXECUTE PROCEDURE set_update_at();
+
+CREATE TRIGGER "device_update_at" BEFORE UPDATE ON "device" FOR EACH ROW EXECUTE PROCEDURE set_update_at();
+
+CREATE TRIGGER "subsystem_info_update_at" BEFORE UPDATE ON "subsystem_info" FOR EACH ROW EXECUTE PROCEDURE set_update_at();
+
+CREATE TRIGGER "subsystem_update_at" BEFORE UPDATE ON "subsystem" FOR EACH ROW EXECUTE PROCEDURE set_update_at();
+
+CREATE TRIGGER "component_info_update_at" BEFORE UPDATE ON "component_info" FOR EACH ROW EXECUTE PROCEDURE set_update_at();
+
+CREATE TRIGGER "component_update_at" BEFORE UPDATE ON "component" FOR EACH ROW EXECUTE PROCEDURE set_update_at();
+
+CREATE TRIGGER "work_order_update_at" BEFORE UPDATE ON "work_order" FOR EACH ROW EXECUTE PROCEDURE set_update_at();
+
+CREATE TRIGGER "fault_ticket_update_at" BEFORE UPDATE ON "fault_ticket" FOR EACH ROW EXECUTE PROCEDURE set_update_at();
+
+CREATE TRIGGER "storage_location_update_at" BEFORE UPDATE ON "storage_location" FOR EACH ROW EXECUTE PROCEDURE set_update_at();
+
+CREATE TRIGGER "stock_update_at" BEFORE UPDATE ON "stock" FOR EACH ROW EXECUTE PROCEDURE set_update_at();
@@ -0,0 +1,36 @@
+-- This file should undo anything in `up.sql`
+PRAGMA foreign_keys = OFF;
+
+DROP TABLE IF EXISTS `reorder_point`;
+
+DROP TABLE IF EXISTS `stock_movement`;
+
+DROP TABLE IF EXISTS `stock`;
+
+DROP TABLE IF EXISTS `storage_location`;
+
+DROP TABLE IF EXISTS `fault_ticket`;
+
+DROP TABLE IF EXISTS `device_status_history`;
+
+DROP TABLE IF EXISTS `device_event`;
+
+DROP TABLE IF EXISTS `work_order`;
+
+DROP TABLE IF EXISTS `subsysteminfo_componentinfo`;
+
+DROP TABLE IF EXISTS `deviceinfo_subsysteminfo`;
+
+DROP TABLE IF EXISTS `component`;
+
+DROP TABLE IF EXISTS `component_info`;
+
+DROP TABLE IF EXISTS `subsystem`;
+
+DROP TABLE IF EXISTS `subsystem_info`;
+
+DROP TABLE IF EXISTS `device`;
+
+DROP TABLE IF EXISTS `device_info`;
+
+PRAGMA foreign_keys = ON;
@@ -0,0 +1,206 @@
+use super::model::*;
+use super::service::{Server, Service};
+use super::storer::connection::Backend;
+#[cfg(test)]
+use super::storer::memstorer::Memory;
+use chrono::Local;
+#[cfg(feature = "mysql")]
+use diesel::dsl::sql;
+#[cfg(feature = "mysql")]
+use diesel::mysql::MysqlConnection;
+#[cfg(feature = "mysql")]
+use diesel::sql_types::BigInt;
+#[cfg(feature = "mysql")]
+use diesel::RunQueryDsl;
+use std::error::Error;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+// Seeds a fleet from one large template and times the tree loaders against it.
+// Usage: device-manage bench [devices] [subsystems] [components per subsystem] [quantity] [rounds]
+pub fn run(backend: &Backend, args: &[String]) -> Result<()> {
+    let arg = |i: usize, default: i32| -> Result<i32> { Ok(args.get(i).map(|v| v.parse()).transpose()?.unwrap_or(default)) };
+    let (devices, subsystems, components, quantity, rounds) = (arg(0, 200)?.max(1), arg(1, 50)?, arg(2, 4)?, arg(3, 1)?, arg(4, 20)?);
+    let probe = probe(backend)?;
+    let svc = &probe.svc;
+    let tag = format!("bench-{}", Local::now().timestamp());
+
+    println!("seeding {} devices of {} subsystems x {} components x {}", devices, subsystems, components, quantity);
+    let devinfo_id = svc.devinfo.insert(DeviceInfoInsert {
+        name: tag.clone(),
+        model: tag.clone(),
+        maintain_interval: 720,
+    })?;
+    let subinfo_ids = svc.subinfo.bulk_insert_ids(
+        &(0..subsystems)
+            .map(|i| SubsystemInfoInsert {
+                name: format!("{}-sub-{}", tag, i),
+                maintain_interval: 360,
+            })
+            .collect(),
+    )?;
+    let mut all_cominfo_ids = Vec::new();
+    for &subinfo_id in &subinfo_ids {
+        svc.rel.insert_deviceinfo_subsysteminfo(DevinfoSubinfoInsert {
+            device_info_id: devinfo_id,
+            subsystem_info_id: subinfo_id,
+        })?;
+        let cominfo_ids = svc.cominfo.bulk_insert_ids(
+            &(0..components)
+                .map(|i| ComponentInfoInsert {
+                    name: format!("{}-com-{}-{}", tag, subinfo_id, i),
+                    model: tag.clone(),
+                    maintain_interval: 180,
+                })
+                .collect(),
+        )?;
+        for &cominfo_id in &cominfo_ids {
+            svc.rel.insert_subsysteminfo_componentinfo(SubinfoCominfoInsert {
+                device_info_id: devinfo_id,
+                subsystem_info_id: subinfo_id,
+                component_info_id: cominfo_id,
+                quantity: quantity,
+            })?;
+        }
+        all_cominfo_ids.extend(cominfo_ids);
+    }
+
+    let mut device_ids = Vec::with_capacity(devices as usize);
+    let measured = measure_all(&probe, &tag, devinfo_id, devices, rounds, &mut device_ids);
+    println!("removing the seeded rows");
+    cleanup(svc, &device_ids, devinfo_id, &subinfo_ids, &all_cominfo_ids)?;
+    let stats = measured?;
+
+    println!("{:<20} {:>6} {:>12} {:>12} {:>10}", "operation", "runs", "avg ms", "max ms", "queries");
+    for s in &stats {
+        let queries = s.queries.map(|q| format!("{:.1}", q as f64 / s.runs as f64)).unwrap_or_else(|| "-".to_owned());
+        println!("{:<20} {:>6} {:>12.3} {:>12.3} {:>10}", s.name, s.runs, millis(s.total) / s.runs as f64, millis(s.max), queries);
+    }
+    if stats.iter().any(|s| s.queries.is_none()) {
+        println!("queries are counted from the MySQL session status only, this backend reports latency alone");
+    }
+    Ok(())
+}
+
+fn measure_all(probe: &Probe, tag: &str, devinfo_id: i32, devices: i32, rounds: i32, device_ids: &mut Vec<i32>) -> Result<Vec<Stat>> {
+    let svc = &probe.svc;
+    let mut stats = vec![probe.measure("create_device", devices, |i| {
+        device_ids.push(svc.create_device(devinfo_id, format!("{}-{}", tag, i))?);
+        Ok(())
+    })?];
+    stats.push(probe.measure("device_info detail", rounds, |_| {
+        svc.devinfo.detail(devinfo_id)?;
+        Ok(())
+    })?);
+    stats.push(probe.measure("get_device", rounds, |i| {
+        svc.get_device(device_ids[i as usize % device_ids.len()])?;
+        Ok(())
+    })?);
+    let pages = (devices as i64 + 19) / 20;
+    stats.push(probe.measure("query_device by 20", rounds, |i| {
+        svc.query_device(&DeviceQuery {
+            device_info_id: Some(devinfo_id),
+            page: Some(i as i64 % pages + 1),
+            size: Some(20),
+            ..Default::default()
+        })?;
+        Ok(())
+    })?);
+    Ok(stats)
+}
+
+// Devices only lose their template link when the device info goes, so every seeded row is removed explicitly.
+fn cleanup(svc: &Service, device_ids: &[i32], devinfo_id: i32, subinfo_ids: &[i32], cominfo_ids: &[i32]) -> Result<()> {
+    for &id in device_ids {
+        svc.delete_device(id)?;
+    }
+    svc.devinfo.delete(devinfo_id)?;
+    for &id in subinfo_ids {
+        svc.subinfo.delete(id)?;
+    }
+    for &id in cominfo_ids {
+        svc.cominfo.delete(id)?;
+    }
+    Ok(())
+}
+
+struct Stat {
+    name: &'static str,
+    runs: i32,
+    total: Duration,
+    max: Duration,
+    queries: Option<i64>,
+}
+
+// A service bound to a single connection, so that the statements it sends can be counted where the backend keeps a counter.
+struct Probe {
+    svc: Service,
+    questions: Box<dyn Fn() -> Option<i64>>,
+}
+
+impl Probe {
+    fn measure<F>(&self, name: &'static str, runs: i32, mut f: F) -> Result<Stat>
+    where
+        F: FnMut(i32) -> Result<()>,
+    {
+        let mut stat = Stat {
+            name: name,
+            runs: runs.max(1),
+            total: Duration::default(),
+            max: Duration::default(),
+            queries: None,
+        };
+        let before = (self.questions)();
+        for i in 0..stat.runs {
+            let start = Instant::now();
+            f(i)?;
+            let elapsed = start.elapsed();
+            stat.total += elapsed;
+            stat.max = stat.max.max(elapsed);
+        }
+        // The second reading counts itself.
+        stat.queries = before.and_then(|b| (self.questions)().map(|a| a - b - 1));
+        Ok(stat)
+    }
+}
+
+fn probe(backend: &Backend) -> Result<Probe> {
+    match backend {
+        #[cfg(feature = "mysql")]
+        Backend::Mysql(pool) => {
+            let conn = Rc::new(pool.get()?);
+            let counter = conn.clone();
+            Ok(Probe {
+                svc: Service::mysql(conn),
+                questions: Box::new(move || questions(&counter).ok()),
+            })
+        }
+        #[cfg(feature = "postgres")]
+        Backend::Postgres(pool) => Ok(Probe {
+            svc: Service::postgres(Rc::new(pool.get()?)),
+            questions: Box::new(|| None),
+        }),
+        #[cfg(feature = "sqlite")]
+        Backend::Sqlite(pool) => Ok(Probe {
+            svc: Service::sqlite(Rc::new(pool.get()?)),
+            questions: Box::new(|| None),
+        }),
+        #[cfg(test)]
+        Backend::Memory => Ok(Probe {
+            svc: Service::memory(Memory::local()),
+            questions: Box::new(|| None),
+        }),
+    }
+}
+
+// Statements the server has received on this session so far.
+#[cfg(feature = "mysql")]
+fn questions(conn: &MysqlConnection) -> diesel::QueryResult<i64> {
+    sql::<BigInt>("SELECT CAST(VARIABLE_VALUE AS SIGNED) FROM performance_schema.session_status WHERE VARIABLE_NAME = 'Questions'").get_result(conn)
+}
+
+fn millis(d: Duration) -> f64 {
+    d.as_secs() as f64 * 1000.0 + d.subsec_nanos() as f64 / 1_000_000.0
+}
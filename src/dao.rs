@@ -1,14 +1,40 @@
 use super::model::*;
 use std::error;
 
+// Storer and service failures by kind, the kind decides the status code a handler answers with.
 #[derive(Debug)]
-pub struct Error(pub String);
+pub enum Error {
+    NotFound(String),
+    Conflict(String),
+    ForeignKey(String),
+    Validation(String),
+    Invalid(Vec<FieldError>),
+    Internal(String),
+}
+
+impl Error {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::NotFound(_) => "NOT_FOUND",
+            Error::Conflict(_) => "CONFLICT",
+            Error::ForeignKey(_) => "FOREIGN_KEY",
+            Error::Validation(_) | Error::Invalid(_) => "VALIDATION",
+            Error::Internal(_) => "INTERNAL",
+        }
+    }
+}
 
 use std::fmt::{self, Display, Formatter};
 
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        write!(f, "{}", &self)
+        match self {
+            Error::NotFound(m) | Error::Conflict(m) | Error::ForeignKey(m) | Error::Validation(m) | Error::Internal(m) => write!(f, "{}", m),
+            Error::Invalid(fields) => {
+                let fields: Vec<String> = fields.iter().map(|e| format!("{}: {}", e.field, e.message)).collect();
+                write!(f, "{}", fields.join("; "))
+            }
+        }
     }
 }
 
@@ -21,15 +47,17 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub trait DeviceInfoStorer {
     fn insert(&self, info: DeviceInfoInsert) -> Result<i32>;
     fn bulk_insert(&self, infos: &Vec<DeviceInfoInsert>) -> Result<usize>;
+    fn bulk_insert_ids(&self, infos: &Vec<DeviceInfoInsert>) -> Result<Vec<i32>>;
     fn delete(&self, id: i32) -> Result<usize>;
     fn bulk_delete(&self, query: DeviceInfoQuery) -> Result<usize>;
     fn update(&self, id: i32, upd: DeviceInfoUpdate) -> Result<usize>;
     fn get(&self, id: i32) -> Result<DeviceInfo>;
+    fn find(&self, name: &str, model: &str) -> Result<Option<DeviceInfo>>;
     fn query(&self, query: &DeviceInfoQuery) -> Result<(Vec<DeviceInfo>, i64)>;
     fn query_by_subsystem_info(&self, subinfoid: i32, query: DeviceInfoQuery) -> Result<(Vec<DeviceInfo>, i64)>;
     fn count(&self, query: DeviceInfoQuery) -> Result<i64>;
     fn is_exist(&self, id: i32) -> Result<bool>;
-    fn detail(&self, id: i32) -> Result<(DeviceInfo, Vec<(SubsystemInfo, Vec<ComponentInfo>)>)>;
+    fn detail(&self, id: i32) -> Result<(DeviceInfo, Vec<(SubsystemInfo, Vec<(ComponentInfo, i32)>)>)>;
 }
 
 // ===================================================subsystem_info======================================================
@@ -37,9 +65,11 @@ pub trait DeviceInfoStorer {
 pub trait SubsystemInfoStorer {
     fn insert(&self, info: SubsystemInfoInsert) -> Result<i32>;
     fn bulk_insert(&self, infos: &Vec<SubsystemInfoInsert>) -> Result<usize>;
+    fn bulk_insert_ids(&self, infos: &Vec<SubsystemInfoInsert>) -> Result<Vec<i32>>;
     fn delete(&self, id: i32) -> Result<usize>;
     fn update(&self, id: i32, upd: SubsystemInfoUpdate) -> Result<usize>;
     fn get(&self, id: i32) -> Result<SubsystemInfo>;
+    fn find(&self, name: &str) -> Result<Option<SubsystemInfo>>;
     fn query(&self, query: &SubsystemInfoQuery) -> Result<(Vec<SubsystemInfo>, i64)>;
     fn query_by_device_info(&self, devinfoid: i32, query: SubsystemInfoQuery) -> Result<(Vec<SubsystemInfo>, i64)>;
     fn query_by_component_info(&self, comid: i32, query: SubsystemInfoQuery) -> Result<(Vec<SubsystemInfo>, i64)>;
@@ -52,9 +82,11 @@ pub trait SubsystemInfoStorer {
 pub trait ComponentInfoStorer {
     fn insert(&self, info: ComponentInfoInsert) -> Result<i32>;
     fn bulk_insert(&self, infos: &Vec<ComponentInfoInsert>) -> Result<usize>;
+    fn bulk_insert_ids(&self, infos: &Vec<ComponentInfoInsert>) -> Result<Vec<i32>>;
     fn delete(&self, id: i32) -> Result<usize>;
     fn update(&self, id: i32, upd: ComponentInfoUpdate) -> Result<usize>;
     fn get(&self, id: i32) -> Result<ComponentInfo>;
+    fn find(&self, name: &str, model: &str) -> Result<Option<ComponentInfo>>;
     fn query(&self, query: &ComponentInfoQuery) -> Result<(Vec<ComponentInfo>, i64)>;
     fn query_by_subsystem_info(&self, subinfoid: i32, query: ComponentInfoQuery) -> Result<(Vec<ComponentInfo>, i64)>;
     fn count(&self, query: ComponentInfoQuery) -> Result<i64>;
@@ -66,6 +98,7 @@ pub trait ComponentInfoStorer {
 pub trait DeviceStorer {
     fn insert(&self, dev: DeviceInsert) -> Result<i32>;
     fn bulk_insert(&self, devs: &Vec<DeviceInsert>) -> Result<usize>;
+    fn bulk_insert_ids(&self, devs: &Vec<DeviceInsert>) -> Result<Vec<i32>>;
     fn delete(&self, id: i32) -> Result<usize>;
     fn update(&self, id: i32, upd: DeviceUpdate) -> Result<usize>;
     fn get(&self, id: i32) -> Result<(Device, Vec<(Subsystem, Vec<Component>)>)>;
@@ -77,10 +110,11 @@ pub trait DeviceStorer {
 pub trait SubsystemStorer {
     fn insert(&self, sub: SubsystemInsert) -> Result<i32>;
     fn bulk_insert(&self, subs: &Vec<SubsystemInsert>) -> Result<usize>;
+    fn bulk_insert_ids(&self, subs: &Vec<SubsystemInsert>) -> Result<Vec<i32>>;
     fn delete(&self, id: i32) -> Result<usize>;
     fn udpate(&self, id: i32, upd: SubsystemUpdate) -> Result<usize>;
     fn get(&self, id: i32) -> Result<(Device, Subsystem, Vec<Component>)>;
-    fn query(&self, query: &SubsystemQuery) -> Result<(Vec<Subsystem>, i64)>;
+    fn query(&self, query: &SubsystemQuery) -> Result<(Vec<(Device, Subsystem)>, i64)>;
 }
 
 // =================================================component=========================================================
@@ -88,10 +122,11 @@ pub trait SubsystemStorer {
 pub trait ComponentStorer {
     fn insert(&self, com: ComponentInsert) -> Result<i32>;
     fn bulk_insert(&self, coms: &Vec<ComponentInsert>) -> Result<usize>;
+    fn bulk_insert_ids(&self, coms: &Vec<ComponentInsert>) -> Result<Vec<i32>>;
     fn delete(&self, id: i32) -> Result<usize>;
     fn update(&self, id: i32, upd: ComponentUpdate) -> Result<usize>;
     fn get(&self, id: i32) -> Result<(Device, Subsystem, Component)>;
-    fn query(&self, query: &ComponentQuery) -> Result<(Vec<Component>, i64)>;
+    fn query(&self, query: &ComponentQuery) -> Result<(Vec<(Device, Subsystem, Component)>, i64)>;
 }
 
 // ==============================================================relations================================================
@@ -100,7 +135,70 @@ pub trait RelationStorer {
     fn insert_deviceinfo_subsysteminfo(&self, rel: DevinfoSubinfoInsert) -> Result<usize>;
     fn delete_deviceinfo_subsysteminfo(&self, devinfo_id: i32, subinfo_id: i32) -> Result<usize>;
     fn bulk_delete_deviceinfo_subsysteminfo(&self, devinfo_id: i32) -> Result<usize>;
+    fn has_deviceinfo_subsysteminfo(&self, devinfo_id: i32, subinfo_id: i32) -> Result<bool>;
     fn insert_subsysteminfo_componentinfo(&self, rel: SubinfoCominfoInsert) -> Result<usize>;
     fn delete_subsysteminfo_componentinfo(&self, devinfo_id: i32, subinfo_id: i32, cominfo_id: i32) -> Result<usize>;
+    fn update_subsysteminfo_componentinfo(&self, devinfo_id: i32, subinfo_id: i32, cominfo_id: i32, quantity: i32) -> Result<usize>;
+    fn subsysteminfo_componentinfo_quantity(&self, devinfo_id: i32, subinfo_id: i32, cominfo_id: i32) -> Result<Option<i32>>;
     fn bulk_delete_subsysteminfo_componentinfo(&self, devinfo_id: i32, subinfo_id: i32) -> Result<usize>;
 }
+
+// ==============================================================device_event================================================
+
+pub trait DeviceEventStorer {
+    fn insert(&self, ev: DeviceEventInsert) -> Result<i32>;
+    fn query(&self, query: &DeviceEventQuery) -> Result<(Vec<DeviceEvent>, i64)>;
+}
+
+// ==============================================================status_history================================================
+
+pub trait StatusHistoryStorer {
+    fn insert(&self, his: StatusHistoryInsert) -> Result<i32>;
+    fn query(&self, query: &StatusHistoryQuery) -> Result<(Vec<StatusHistory>, i64)>;
+}
+
+// ==============================================================work_order================================================
+
+pub trait WorkOrderStorer {
+    fn insert(&self, order: WorkOrderInsert) -> Result<i32>;
+    fn delete(&self, id: i32) -> Result<usize>;
+    fn update(&self, id: i32, upd: WorkOrderUpdate) -> Result<usize>;
+    fn transition(&self, id: i32, trans: WorkOrderTransition) -> Result<usize>;
+    fn get(&self, id: i32) -> Result<WorkOrder>;
+    fn query(&self, query: &WorkOrderQuery) -> Result<(Vec<WorkOrder>, i64)>;
+}
+
+// ==============================================================fault_ticket================================================
+
+pub trait FaultStorer {
+    fn insert(&self, ticket: FaultTicketInsert) -> Result<i32>;
+    fn delete(&self, id: i32) -> Result<usize>;
+    fn update(&self, id: i32, upd: FaultTicketUpdate) -> Result<usize>;
+    fn get(&self, id: i32) -> Result<FaultTicket>;
+    fn query(&self, query: &FaultTicketQuery) -> Result<(Vec<FaultTicket>, i64)>;
+}
+
+// ==============================================================inventory================================================
+
+pub trait InventoryStorer {
+    fn insert_location(&self, loc: StorageLocationInsert) -> Result<i32>;
+    fn delete_location(&self, id: i32) -> Result<usize>;
+    fn locations(&self) -> Result<Vec<StorageLocation>>;
+    fn stock_quantity(&self, cominfo_id: i32, location_id: i32) -> Result<i32>;
+    fn change_stock(&self, cominfo_id: i32, location_id: i32, delta: i32) -> Result<usize>;
+    // Takes the quantity out only while that much is on hand, in a single statement so concurrent issues cannot overdraw, 0 rows means too little stock.
+    fn take_stock(&self, cominfo_id: i32, location_id: i32, quantity: i32) -> Result<usize>;
+    fn query_stock(&self, query: &StockQuery) -> Result<(Vec<Stock>, i64)>;
+    fn insert_movement(&self, mov: StockMovementInsert) -> Result<i32>;
+    fn query_movement(&self, query: &StockMovementQuery) -> Result<(Vec<StockMovement>, i64)>;
+    fn set_reorder_point(&self, point: ReorderPoint) -> Result<usize>;
+    fn low_stock(&self) -> Result<Vec<(ComponentInfo, i64, i32)>>;
+}
+
+// ==============================================================transaction================================================
+
+pub trait Transactor {
+    fn begin(&self) -> Result<()>;
+    fn commit(&self) -> Result<()>;
+    fn rollback(&self) -> Result<()>;
+}
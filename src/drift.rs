@@ -0,0 +1,106 @@
+use super::model::*;
+use std::collections::HashSet;
+
+fn interval_drift(template: i32, actual: i32) -> Option<IntervalDrift> {
+    if template == actual {
+        None
+    } else {
+        Some(IntervalDrift { template, actual })
+    }
+}
+
+fn diff_components(cominfos: &[(ComponentInfo, i32)], coms: &[Component]) -> Vec<ComponentDrift> {
+    let mut drifts = Vec::new();
+    for (cominfo, quantity) in cominfos {
+        let mut instances: Vec<&Component> = coms.iter().filter(|c| c.component_info_id == Some(cominfo.id)).collect();
+        instances.sort_by_key(|c| c.position);
+        let maintain_interval = instances
+            .iter()
+            .find(|c| c.maintain_interval != cominfo.maintain_interval)
+            .and_then(|c| interval_drift(cominfo.maintain_interval, c.maintain_interval));
+        if instances.len() as i32 != *quantity || maintain_interval.is_some() {
+            drifts.push(ComponentDrift {
+                component_info_id: Some(cominfo.id),
+                name: cominfo.name.clone(),
+                model: cominfo.model.clone(),
+                template_quantity: *quantity,
+                actual_quantity: instances.len() as i32,
+                maintain_interval,
+                component_ids: instances.iter().map(|c| c.id).collect(),
+            });
+        }
+    }
+    let mut extras: Vec<ComponentDrift> = Vec::new();
+    for com in coms.iter().filter(|c| !cominfos.iter().any(|(i, _)| Some(i.id) == c.component_info_id)) {
+        match extras
+            .iter_mut()
+            .find(|d| d.component_info_id == com.component_info_id && d.name == com.name && d.model == com.model)
+        {
+            Some(d) => {
+                d.actual_quantity += 1;
+                d.component_ids.push(com.id);
+            }
+            None => extras.push(ComponentDrift {
+                component_info_id: com.component_info_id,
+                name: com.name.clone(),
+                model: com.model.clone(),
+                template_quantity: 0,
+                actual_quantity: 1,
+                maintain_interval: None,
+                component_ids: vec![com.id],
+            }),
+        }
+    }
+    drifts.extend(extras);
+    drifts
+}
+
+// Compares a device tree with the current BOM of its template, only the differing parts are reported.
+// Conflicts depend on the work order and fault history, the service fills them in.
+pub fn diff(template: &(DeviceInfo, Vec<(SubsystemInfo, Vec<(ComponentInfo, i32)>)>), device: &(Device, Vec<(Subsystem, Vec<Component>)>)) -> DeviceDrift {
+    let (devinfo, subinfos) = template;
+    let (dev, subs) = device;
+    let mut subsystems = Vec::new();
+    let mut matched = HashSet::new();
+    for (subinfo, cominfos) in subinfos {
+        match subs.iter().find(|(s, _)| s.subsystem_info_id == Some(subinfo.id)) {
+            Some((sub, coms)) => {
+                matched.insert(sub.id);
+                let maintain_interval = interval_drift(subinfo.maintain_interval, sub.maintain_interval);
+                let components = diff_components(cominfos, coms);
+                if maintain_interval.is_some() || !components.is_empty() {
+                    subsystems.push(SubsystemDrift {
+                        subsystem_info_id: Some(subinfo.id),
+                        subsystem_id: Some(sub.id),
+                        name: sub.name.clone(),
+                        maintain_interval,
+                        components,
+                    });
+                }
+            }
+            None => subsystems.push(SubsystemDrift {
+                subsystem_info_id: Some(subinfo.id),
+                subsystem_id: None,
+                name: subinfo.name.clone(),
+                maintain_interval: None,
+                components: diff_components(cominfos, &[]),
+            }),
+        }
+    }
+    for (sub, coms) in subs.iter().filter(|(s, _)| !matched.contains(&s.id)) {
+        subsystems.push(SubsystemDrift {
+            subsystem_info_id: sub.subsystem_info_id,
+            subsystem_id: Some(sub.id),
+            name: sub.name.clone(),
+            maintain_interval: None,
+            components: diff_components(&[], coms),
+        });
+    }
+    DeviceDrift {
+        device_id: dev.id,
+        device_info_id: devinfo.id,
+        maintain_interval: interval_drift(devinfo.maintain_interval, dev.maintain_interval),
+        subsystems,
+        conflicts: Vec::new(),
+    }
+}
@@ -0,0 +1,257 @@
+use super::dao;
+use super::import;
+use super::model::*;
+use super::service::Service;
+use super::validate::Validate;
+use csv;
+use rust_xlsxwriter::Workbook;
+use std::error::Error;
+use std::io::{self, Cursor, Read};
+
+// Devices are loaded this many at a time while an export is streamed.
+const PAGE_SIZE: i64 = 100;
+
+pub const DEVICE_COLUMNS: [&str; 15] = [
+    "device_id",
+    "device_name",
+    "device_model",
+    "unicode",
+    "status",
+    "device_maintain_interval",
+    "total_duration",
+    "subsystem_id",
+    "subsystem_name",
+    "subsystem_maintain_interval",
+    "component_id",
+    "component_name",
+    "component_model",
+    "component_maintain_interval",
+    "position",
+];
+
+// One row per component with the device and subsystem columns repeated, empty levels still get a row of their own.
+fn device_rows((dev, subs): &(Device, Vec<(Subsystem, Vec<Component>)>)) -> Vec<Vec<String>> {
+    let device = vec![
+        dev.id.to_string(),
+        dev.name.clone(),
+        dev.model.clone(),
+        dev.unicode.clone(),
+        format!("{:?}", dev.status),
+        dev.maintain_interval.to_string(),
+        dev.total_duration.to_string(),
+    ];
+    let mut rows = Vec::new();
+    for (sub, coms) in subs {
+        let mut subsystem = device.clone();
+        subsystem.extend(vec![sub.id.to_string(), sub.name.clone(), sub.maintain_interval.to_string()]);
+        for com in coms {
+            let mut row = subsystem.clone();
+            row.extend(vec![
+                com.id.to_string(),
+                com.name.clone(),
+                com.model.clone(),
+                com.maintain_interval.to_string(),
+                com.position.to_string(),
+            ]);
+            rows.push(row);
+        }
+        if coms.is_empty() {
+            subsystem.resize(DEVICE_COLUMNS.len(), String::new());
+            rows.push(subsystem);
+        }
+    }
+    if subs.is_empty() {
+        let mut row = device;
+        row.resize(DEVICE_COLUMNS.len(), String::new());
+        rows.push(row);
+    }
+    rows
+}
+
+// Template rows use the import columns so an exported BOM can be imported again unchanged.
+fn template_rows((devinfo, subinfos): &(DeviceInfo, Vec<(SubsystemInfo, Vec<(ComponentInfo, i32)>)>)) -> Vec<Vec<String>> {
+    let device = vec![devinfo.name.clone(), devinfo.model.clone(), devinfo.maintain_interval.to_string()];
+    let mut rows = Vec::new();
+    for (subinfo, cominfos) in subinfos {
+        let mut subsystem = device.clone();
+        subsystem.extend(vec![subinfo.name.clone(), subinfo.maintain_interval.to_string()]);
+        for (cominfo, quantity) in cominfos {
+            let mut row = subsystem.clone();
+            row.extend(vec![cominfo.name.clone(), cominfo.model.clone(), cominfo.maintain_interval.to_string(), quantity.to_string()]);
+            rows.push(row);
+        }
+        if cominfos.is_empty() {
+            subsystem.resize(import::COLUMNS.len(), String::new());
+            rows.push(subsystem);
+        }
+    }
+    if subinfos.is_empty() {
+        let mut row = device;
+        row.resize(import::COLUMNS.len(), String::new());
+        rows.push(row);
+    }
+    rows
+}
+
+fn csv_bytes(rows: &[Vec<String>]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut w = csv::Writer::from_writer(Vec::new());
+    for row in rows {
+        w.write_record(row)?;
+    }
+    Ok(w.into_inner()?)
+}
+
+fn header(columns: &[&str]) -> Vec<String> {
+    columns.iter().map(|c| (*c).to_owned()).collect()
+}
+
+fn xlsx_bytes<F>(columns: &[&str], mut next: F) -> Result<Vec<u8>, Box<dyn Error>>
+where
+    F: FnMut() -> Result<Option<Vec<Vec<String>>>, Box<dyn Error>>,
+{
+    let mut wb = Workbook::new();
+    let sheet = wb.add_worksheet_with_constant_memory();
+    for (c, v) in columns.iter().enumerate() {
+        sheet.write_string(0, c as u16, *v)?;
+    }
+    let mut r = 1;
+    while let Some(rows) = next()? {
+        for row in rows {
+            for (c, v) in row.iter().enumerate() {
+                sheet.write_string(r, c as u16, v)?;
+            }
+            r += 1;
+        }
+    }
+    Ok(wb.save_to_buffer()?)
+}
+
+// Reads through a source one chunk at a time, a chunk is only produced once the previous one has been consumed.
+struct Chunked<F> {
+    next: F,
+    buf: Vec<u8>,
+    pos: usize,
+    tail: Option<Vec<u8>>,
+}
+
+impl<F> Read for Chunked<F>
+where
+    F: FnMut() -> Result<Option<Vec<u8>>, Box<dyn Error>>,
+{
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.buf.len() {
+            let chunk = match (self.next)().map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{}", e)))? {
+                Some(chunk) => chunk,
+                None => match self.tail.take() {
+                    Some(tail) => tail,
+                    None => return Ok(0),
+                },
+            };
+            self.buf = chunk;
+            self.pos = 0;
+        }
+        let n = out.len().min(self.buf.len() - self.pos);
+        out[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+// Returns the next page of device trees, or None once the result set is exhausted. A query that asks for a page of its own is exported as that single page.
+fn pages(svc: Service, mut query: DeviceQuery) -> impl FnMut() -> Result<Option<Vec<(Device, Vec<(Subsystem, Vec<Component>)>)>>, Box<dyn Error>> {
+    let single = query.page.is_some() && query.size.is_some();
+    if !single {
+        query.page = Some(0);
+        query.size = Some(PAGE_SIZE);
+    }
+    let mut done = false;
+    move || {
+        if done {
+            return Ok(None);
+        }
+        if single {
+            done = true;
+        } else {
+            query.page = query.page.map(|p| p + 1);
+        }
+        let (trees, _) = svc.dev.query(&query)?;
+        if (trees.len() as i64) < query.size.unwrap_or(PAGE_SIZE) {
+            done = true;
+        }
+        if trees.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(trees))
+    }
+}
+
+fn internal(e: Box<dyn Error>) -> dao::Error {
+    dao::Error::Internal(format!("{}", e))
+}
+
+pub fn devices(svc: Service, query: DeviceQuery, format: FileFormat) -> dao::Result<Box<dyn Read>> {
+    query.validate()?;
+    let mut next = pages(svc, query);
+    match format {
+        FileFormat::Csv => Ok(Box::new(Chunked {
+            buf: csv_bytes(&[header(&DEVICE_COLUMNS)]).map_err(internal)?,
+            pos: 0,
+            tail: None,
+            next: move || match next()? {
+                Some(trees) => Ok(Some(csv_bytes(&trees.iter().flat_map(device_rows).collect::<Vec<_>>())?)),
+                None => Ok(None),
+            },
+        })),
+        FileFormat::Json => {
+            let mut first = true;
+            Ok(Box::new(Chunked {
+                buf: b"[".to_vec(),
+                pos: 0,
+                tail: Some(b"]".to_vec()),
+                next: move || match next()? {
+                    Some(trees) => {
+                        let mut chunk = Vec::new();
+                        for tree in trees {
+                            if !first {
+                                chunk.push(b',');
+                            }
+                            first = false;
+                            serde_json::to_writer(&mut chunk, &DeviceTree::from(tree))?;
+                        }
+                        Ok(Some(chunk))
+                    }
+                    None => Ok(None),
+                },
+            }))
+        }
+        // The xlsx container is a zip archive that cannot be written incrementally, the sheet itself is kept out of memory while rows are added.
+        FileFormat::Xlsx => Ok(Box::new(Cursor::new(
+            xlsx_bytes(&DEVICE_COLUMNS, || Ok(next()?.map(|trees| trees.iter().flat_map(device_rows).collect()))).map_err(internal)?,
+        ))),
+    }
+}
+
+pub fn template(svc: &Service, devinfo_id: i32, format: FileFormat) -> dao::Result<Box<dyn Read>> {
+    let detail = svc.devinfo.detail(devinfo_id)?;
+    let bytes: Result<Vec<u8>, Box<dyn Error>> = match format {
+        FileFormat::Csv => {
+            let mut rows = vec![header(&import::COLUMNS)];
+            rows.extend(template_rows(&detail));
+            csv_bytes(&rows)
+        }
+        FileFormat::Json => serde_json::to_vec(&TemplateTree::from(detail)).map_err(|e| e.into()),
+        FileFormat::Xlsx => {
+            let mut rows = Some(template_rows(&detail));
+            xlsx_bytes(&import::COLUMNS, || Ok(rows.take()))
+        }
+    };
+    Ok(Box::new(Cursor::new(bytes.map_err(internal)?)))
+}
+
+pub fn content_type(format: FileFormat) -> (&'static str, &'static str) {
+    match format {
+        FileFormat::Csv => ("text", "csv"),
+        FileFormat::Json => ("application", "json"),
+        FileFormat::Xlsx => ("application", "vnd.openxmlformats-officedocument.spreadsheetml.sheet"),
+    }
+}
@@ -1,218 +1,582 @@
-use super::dao;
+use super::export;
 use super::model::*;
-use super::result::Result;
-use super::service;
-use super::storer::mysqlstorer;
-use super::MysqlConn;
+use super::result::{Error, Result};
+use super::service::{Server, Service};
+use chrono::Local;
+use rocket::http::{ContentType, Status};
 use rocket::request::Form;
+use rocket::response::content::Content;
+use rocket::response::Stream;
+use rocket::Data;
+use std::io::Read;
 use rocket_contrib::json::Json;
 
 // ===============================================device_info=================================================
 
-// #[post("/device_info", format = "application/json", data = "<info>")]
-// pub fn add_device_info(conn: MysqlConn, info: Json<DeviceInfoInsert>) -> Result<usize> {
-//     Ok(Json(dao::insert_device_info(&*conn, info.0)?))
-// }
-
-// #[post("/device_infos", format="application/json", data="<infos>", rank = 1)]
-// pub fn bulk_add_device_info(conn: MysqlConn, infos: Json<Vec<DeviceInfoInsert>>) -> Result<usize> {
-//     Ok(Json(dao::bulk_insert_device_info(&*conn, &infos.0)?))
-// }
-
-// #[delete("/device_info/<id>")]
-// pub fn delete_device_info(conn: MysqlConn, id: i32) -> Result<usize> {
-//     Ok(Json(dao::delete_device_info(&*conn, id)?))
-// }
-
-// #[put("/device_info/<id>", format="application/json", data="<req>")]
-// pub fn update_device_info(conn: MysqlConn, id: i32, req: Json<DeviceInfoUpdate>) -> Result<usize> {
-//     Ok(Json(dao::update_device_info(&*conn, id, req.0)?))
-// }
-
-// #[get("/device_info/<id>")]
-// pub fn get_device_info(conn: MysqlConn, id: i32) -> Result<DeviceInfo> {
-//     Ok(Json(dao::get_device_info(&*conn, id)?))
-// }
-
-// #[get("/device_infos?<query..>")]
-// pub fn query_device_info(conn: MysqlConn, query: Form<DeviceInfoQuery>) -> Result<(Vec<DeviceInfo>, i64)> {
-//     Ok(Json(dao::query_device_infos(&*conn, query.0)?))
-// }
-
-// #[get("/subsystem_info/<subinfoid>/device_infos?<query..>")]
-// pub fn query_device_info_by_subsystem_info(conn: MysqlConn, subinfoid: i32, query: Form<DeviceInfoQuery>) -> Result<(Vec<DeviceInfo>, i64)> {
-//     Ok(Json(dao::query_device_infos_by_subsystem_info(&*conn, subinfoid, query.0)?))
-// }
-
-// //==============================================device===========================================================
-
-// #[post("/device", format = "application/json", data = "<dev>")]
-// pub fn add_device(conn: MysqlConn, dev: Json<DeviceInsert>) -> Result<usize> {
-//     Ok(Json(dao::insert_device(&*conn, dev.0)?))
-// }
-
-// #[post("/devices", format="application/json", data="<devs>")]
-// pub fn bulk_add_device(conn: MysqlConn, devs: Json<Vec<DeviceInsert>>) -> Result<usize> {
-//     Ok(Json(dao::bulk_insert_device(&*conn, &devs.0)?))
-// }
-
-// #[delete("/device/<id>")]
-// pub fn delete_device(conn: MysqlConn, id: i32) -> Result<usize> {
-//     Ok(Json(dao::delete_device(&*conn, id)?))
-// }
-
-// #[put("/device/<id>", format="application/json", data="<req>")]
-// pub fn update_device(conn: MysqlConn, id: i32, req: Json<DeviceUpdate>) -> Result<usize> {
-//     Ok(Json(dao::update_device(&*conn, id, req.0)?))
-// }
-
-// #[get("/device/<id>")]
-// pub fn get_device(conn: MysqlConn, id: i32) -> Result<(Device, Vec<(Subsystem, Vec<Component>)>)> {
-//     Ok(Json(dao::get_device(&*conn, id)?))
-// }
-
-// #[get("/devices?<query..>")]
-// pub fn query_device(conn: MysqlConn, query: Form<DeviceQuery>) -> Result<Vec<(Device, Vec<(Subsystem, Vec<Component>)>)>> {
-//     Ok(Json(dao::query_device(&*conn, query.0)?))
-// }
-
-// //==================================================subsystem_info========================================================
-
-// #[post("/subsystem_info", format="application/json", data="<info>")]
-// pub fn add_subsystem_info(conn: MysqlConn, info: Json<SubsystemInfoInsert>) -> Result<usize> {
-//     Ok(Json(dao::insert_subsystem_info(&*conn, info.0)?))
-// }
-
-// #[post("/subsystem_infos", format="application/json", data="<infos>")]
-// pub fn bulk_add_subsystem_info(conn: MysqlConn, infos: Json<Vec<SubsystemInfoInsert>>) -> Result<usize> {
-//     Ok(Json(dao::bulk_insert_subsystem_info(&*conn, &infos.0)?))
-// }
-
-// #[delete("/subsystem_info/<id>")]
-// pub fn delete_subsystem_info(conn: MysqlConn, id: i32) -> Result<usize> {
-//     Ok(Json(dao::delete_subsystem_info(&*conn, id)?))
-// }
-
-// #[put("/subsystem_info/<id>", format="application/json", data="<upd>")]
-// pub fn update_subsystem_info(conn: MysqlConn, id: i32, upd: Json<SubsystemInfoUpdate>) -> Result<usize> {
-//     Ok(Json(dao::update_subsystem_info(&*conn, id, upd.0)?))
-// }
-
-// #[get("/subsysetem_info/<id>")]
-// pub fn get_subsystem_info(conn: MysqlConn, id: i32) -> Result<(SubsystemInfo, Vec<DeviceInfo>, Vec<ComponentInfo>)> {
-//     Ok(Json(dao::get_subsystem_info(&*conn, id)?))
-// }
-
-// #[get("/subsystem_infos?<query..>")]
-// pub fn query_subsystem_info(conn: MysqlConn, query: Form<SubsystemInfoQuery>) -> Result<(Vec<SubsystemInfo>, i64)> {
-//     Ok(Json(dao::query_subsystem_infos(&*conn, query.0)?))
-// }
-
-// #[get("/device_info/<devinfoid>/subsystem_infos?<query..>")]
-// pub fn query_subsystem_info_by_device_info(conn: MysqlConn, devinfoid: i32, query: Form<SubsystemInfoQuery>) -> Result<(Vec<SubsystemInfo>, i64)> {
-//     Ok(Json(dao::query_subsystem_infos_by_device_info(&*conn, devinfoid, query.0)?))
-// }
-
-// #[get("/component_info/<cominfoid>/subsystem_infos?<query..>")]
-// pub fn query_subsystem_info_by_component_info(conn: MysqlConn, cominfoid: i32, query: Form<SubsystemInfoQuery>) -> Result<(Vec<SubsystemInfo>, i64)> {
-//     Ok(Json(dao::query_subsystem_infos_by_component_info(&*conn, cominfoid, query.0)?))
-// }
-
-// // =============================================================subsystem=============================================================
-
-// #[post("/subsystem", format="application/json", data="<sys>")]
-// pub fn add_subsystem(conn: MysqlConn, sys: Json<SubsystemInsert>) -> Result<usize> {
-//     Ok(Json(dao::insert_subsystem(&*conn, sys.0)?))
-// }
-
-// #[post("/subsystems", format="application/json", data="<ss>")]
-// pub fn bulk_add_subsystem(conn: MysqlConn, ss: Json<Vec<SubsystemInsert>>) -> Result<usize> {
-//     Ok(Json(dao::bulk_insert_subsystem(&*conn, &ss.0)?))
-// }
-
-// #[delete("/subsystem/<id>")]
-// pub fn delete_subsystem(conn: MysqlConn, id: i32) -> Result<usize> {
-//     Ok(Json(dao::delete_subsystem(&*conn, id)?))
-// }
-
-// #[put("/subsystem/<id>", format="application/json", data="<upd>")]
-// pub fn update_subsystem(conn: MysqlConn, id: i32, upd: Json<SubsystemUpdate>) -> Result<usize> {
-//     Ok(Json(dao::udpate_subsystem(&*conn, id, upd.0)?))
-// }
-
-// #[get("/subsystem/<id>")]
-// pub fn get_subsystem(conn: MysqlConn, id: i32) -> Result<(Device, Subsystem, Vec<Component>)> {
-//     Ok(Json(dao::get_subsystem(&*conn, id)?))
-// }
-
-// #[get("/subsystems?<query..>")]
-// pub fn query_subsystem(conn: MysqlConn, query: Form<SubsystemQuery>) -> Result<Vec<(Device, Subsystem, Vec<Component>)>> {
-//     Ok(Json(dao::query_subsystem(&*conn, query.0)?))
-// }
-
-// // ===================================================component_info=======================================================
-
-// #[post("/component_info", format="application/json", data="<info>")]
-// pub fn add_component_info(conn: MysqlConn, info: Json<ComponentInfoInsert>) -> Result<usize> {
-//     Ok(Json(dao::insert_component_info(&*conn, info.0)?))
-// }
-
-// #[post("/component_infos", format="application/json", data="<infos>")]
-// pub fn bulk_add_component_info(conn: MysqlConn, infos: Json<Vec<ComponentInfoInsert>>) -> Result<usize> {
-//     Ok(Json(dao::bulk_insert_component_info(&*conn, &infos.0)?))
-// }
-
-// #[delete("/component_info/<id>")]
-// pub fn delete_component_info(conn: MysqlConn, id: i32) -> Result<usize> {
-//     Ok(Json(dao::delete_component_info(&*conn, id)?))
-// }
-
-// #[put("/component_info/<id>", format="application/json", data="<upd>")]
-// pub fn update_component_info(conn: MysqlConn, id: i32, upd: Json<ComponentInfoUpdate>) -> Result<usize> {
-//     Ok(Json(dao::update_component_info(&*conn, id, upd.0)?))
-// }
-
-// #[get("/component_info/<id>")]
-// pub fn get_component_info(conn: MysqlConn, id: i32) -> Result<(DeviceInfo, SubsystemInfo, ComponentInfo)> {
-//     Ok(Json(dao::get_component_info(&*conn, id)?))
-// }
-
-// #[get("/component_info?<query..>")]
-// pub fn query_component_info(conn: MysqlConn, query: Form<ComponentInfoQuery>) -> Result<(Vec<ComponentInfo>, i64)> {
-//     Ok(Json(dao::query_component_infos(&*conn, query.0)?))
-// }
-
-// #[get("/subsystem_info/<subinfoid>/component_infos?<query..>")]
-// pub fn query_component_info_by_subsystem_info(conn: MysqlConn, subinfoid: i32, query: Form<ComponentInfoQuery>) -> Result<(Vec<ComponentInfo>, i64)> {
-//     Ok(Json(dao::query_component_infos_by_subsystem_info(&*conn, subinfoid, query.0)?))
-// }
-
-// #[post("/component", format="application/json", data="<com>")]
-// pub fn add_component(conn: MysqlConn, com: Json<ComponentInsert>) -> Result<usize> {
-//     Ok(Json(dao::insert_component(&*conn, com.0)?))
-// }
-
-// #[post("/components", format="application/json", data="<coms>")]
-// pub fn bulk_add_component(conn: MysqlConn, coms: Json<Vec<ComponentInsert>>) -> Result<usize> {
-//     Ok(Json(dao::bulk_insert_component(&*conn, &coms.0)?))
-// }
-
-// #[delete("/component/<id>")]
-// pub fn delete_component(conn: MysqlConn, id: i32) -> Result<usize> {
-//     Ok(Json(dao::delete_component(&*conn, id)?))
-// }
-
-// #[put("/component/<id>", format="application/json", data="<upd>")]
-// pub fn update_component(conn: MysqlConn, id: i32, upd: Json<ComponentUpdate>) -> Result<usize> {
-//     Ok(Json(dao::update_component(&*conn, id, upd.0)?))
-// }
-
-// #[get("/component/<id>")]
-// pub fn get_component(conn: MysqlConn, id: i32) -> Result<(Device, Subsystem, Component)> {
-//     Ok(Json(dao::get_component(&*conn, id)?))
-// }
-
-// #[get("/components?<query..>")]
-// pub fn query_component(conn: MysqlConn, query: Form<ComponentQuery>) -> Result<Vec<(Device, Subsystem, Component)>> {
-//     Ok(Json(dao::query_component(&*conn, query.0)?))
-// }
+#[post("/device_info", format = "application/json", data = "<info>")]
+pub fn add_device_info(svc: Service, info: Json<DeviceInfoInsert>) -> Result<i32> {
+    let info = info.0;
+    Ok(Json(svc.add_device_info(info.name, info.model, info.maintain_interval)?))
+}
+
+#[delete("/device_info/<id>")]
+pub fn delete_device_info(svc: Service, id: i32) -> Result<usize> {
+    Ok(Json(svc.delete_device_info(id)?))
+}
+
+#[put("/device_info/<id>", format = "application/json", data = "<upd>")]
+pub fn update_device_info(svc: Service, id: i32, upd: Json<DeviceInfoUpdate>) -> Result<usize> {
+    Ok(Json(svc.update_device_info(id, upd.0)?))
+}
+
+#[get("/device_info/<id>")]
+pub fn get_device_info(svc: Service, id: i32) -> Result<DeviceInfo> {
+    Ok(Json(svc.get_device_info(id)?))
+}
+
+#[get("/device_infos?<query..>")]
+pub fn query_device_info(svc: Service, query: Form<DeviceInfoQuery>) -> Result<Page<DeviceInfo>> {
+    Ok(Json(svc.query_device_info(&query.0)?.into()))
+}
+
+#[get("/subsystem_info/<subinfoid>/device_infos?<query..>")]
+pub fn query_device_info_by_subsystem_info(svc: Service, subinfoid: i32, query: Form<DeviceInfoQuery>) -> Result<Page<DeviceInfo>> {
+    Ok(Json(svc.query_device_info_by_subsystem_info(subinfoid, query.0)?.into()))
+}
+
+//==================================================subsystem_info========================================================
+
+#[post("/subsystem_info", format = "application/json", data = "<info>")]
+pub fn add_subsystem_info(svc: Service, info: Json<SubsystemInfoInsert>) -> Result<i32> {
+    let info = info.0;
+    Ok(Json(svc.add_subsystem_info(info.name, info.maintain_interval)?))
+}
+
+#[delete("/subsystem_info/<id>")]
+pub fn delete_subsystem_info(svc: Service, id: i32) -> Result<usize> {
+    Ok(Json(svc.delete_subsystem_info(id)?))
+}
+
+#[put("/subsystem_info/<id>", format = "application/json", data = "<upd>")]
+pub fn update_subsystem_info(svc: Service, id: i32, upd: Json<SubsystemInfoUpdate>) -> Result<usize> {
+    Ok(Json(svc.update_subsystem_info(id, upd.0)?))
+}
+
+#[get("/subsystem_info/<id>")]
+pub fn get_subsystem_info(svc: Service, id: i32) -> Result<SubsystemInfo> {
+    Ok(Json(svc.get_subsystem_info(id)?))
+}
+
+#[get("/subsystem_infos?<query..>")]
+pub fn query_subsystem_info(svc: Service, query: Form<SubsystemInfoQuery>) -> Result<Page<SubsystemInfo>> {
+    Ok(Json(svc.query_subsystem_info(&query.0)?.into()))
+}
+
+#[get("/device_info/<devinfoid>/subsystem_infos?<query..>")]
+pub fn query_subsystem_info_by_device_info(svc: Service, devinfoid: i32, query: Form<SubsystemInfoQuery>) -> Result<Page<SubsystemInfo>> {
+    Ok(Json(svc.query_subsystem_info_by_device_info(devinfoid, query.0)?.into()))
+}
+
+#[get("/component_info/<cominfoid>/subsystem_infos?<query..>")]
+pub fn query_subsystem_info_by_component_info(svc: Service, cominfoid: i32, query: Form<SubsystemInfoQuery>) -> Result<Page<SubsystemInfo>> {
+    Ok(Json(svc.query_subsystem_info_by_component_info(cominfoid, query.0)?.into()))
+}
+
+// ===================================================component_info=======================================================
+
+#[post("/component_info", format = "application/json", data = "<info>")]
+pub fn add_component_info(svc: Service, info: Json<ComponentInfoInsert>) -> Result<i32> {
+    let info = info.0;
+    Ok(Json(svc.add_component_info(info.name, info.model, info.maintain_interval)?))
+}
+
+#[delete("/component_info/<id>")]
+pub fn delete_component_info(svc: Service, id: i32) -> Result<usize> {
+    Ok(Json(svc.delete_component_info(id)?))
+}
+
+#[put("/component_info/<id>", format = "application/json", data = "<upd>")]
+pub fn update_component_info(svc: Service, id: i32, upd: Json<ComponentInfoUpdate>) -> Result<usize> {
+    Ok(Json(svc.update_component_info(id, upd.0)?))
+}
+
+#[get("/component_info/<id>")]
+pub fn get_component_info(svc: Service, id: i32) -> Result<ComponentInfo> {
+    Ok(Json(svc.get_component_info(id)?))
+}
+
+#[get("/component_infos?<query..>")]
+pub fn query_component_info(svc: Service, query: Form<ComponentInfoQuery>) -> Result<Page<ComponentInfo>> {
+    Ok(Json(svc.query_component_info(&query.0)?.into()))
+}
+
+#[get("/subsystem_info/<subinfoid>/component_infos?<query..>")]
+pub fn query_component_info_by_subsystem_info(svc: Service, subinfoid: i32, query: Form<ComponentInfoQuery>) -> Result<Page<ComponentInfo>> {
+    Ok(Json(svc.query_component_info_by_subsystem_info(subinfoid, query.0)?.into()))
+}
+
+// ===================================================bom=======================================================
+
+#[post("/device_info/<devinfoid>/subsystem_info/<subinfoid>")]
+pub fn attach_subsystem_info(svc: Service, devinfoid: i32, subinfoid: i32) -> Result<usize> {
+    Ok(Json(svc.attach_subsystem_info(devinfoid, subinfoid)?))
+}
+
+#[delete("/device_info/<devinfoid>/subsystem_info/<subinfoid>")]
+pub fn remove_subsystem_info(svc: Service, devinfoid: i32, subinfoid: i32) -> Result<usize> {
+    Ok(Json(svc.remove_subsystem_info(devinfoid, subinfoid)?))
+}
+
+#[post("/device_info/<devinfoid>/subsystem_info/<subinfoid>/component_info/<cominfoid>", format = "application/json", data = "<qty>")]
+pub fn attach_component_info(svc: Service, devinfoid: i32, subinfoid: i32, cominfoid: i32, qty: Json<ComponentQuantity>) -> Result<usize> {
+    Ok(Json(svc.attach_component_info(devinfoid, subinfoid, cominfoid, qty.0.quantity)?))
+}
+
+#[put("/device_info/<devinfoid>/subsystem_info/<subinfoid>/component_info/<cominfoid>", format = "application/json", data = "<qty>")]
+pub fn set_component_info_quantity(svc: Service, devinfoid: i32, subinfoid: i32, cominfoid: i32, qty: Json<ComponentQuantity>) -> Result<usize> {
+    Ok(Json(svc.set_component_info_quantity(devinfoid, subinfoid, cominfoid, qty.0.quantity)?))
+}
+
+#[delete("/device_info/<devinfoid>/subsystem_info/<subinfoid>/component_info/<cominfoid>")]
+pub fn remove_component_info(svc: Service, devinfoid: i32, subinfoid: i32, cominfoid: i32) -> Result<usize> {
+    Ok(Json(svc.remove_component_info(devinfoid, subinfoid, cominfoid)?))
+}
+
+//==============================================device===========================================================
+
+#[post("/device", format = "application/json", data = "<dev>")]
+pub fn add_device(svc: Service, dev: Json<DeviceCreate>) -> Result<i32> {
+    let dev = dev.0;
+    Ok(Json(svc.create_device(dev.device_info_id, dev.unicode)?))
+}
+
+#[delete("/device/<id>")]
+pub fn delete_device(svc: Service, id: i32) -> Result<usize> {
+    Ok(Json(svc.delete_device(id)?))
+}
+
+#[put("/device/<id>", format = "application/json", data = "<upd>")]
+pub fn update_device(svc: Service, id: i32, upd: Json<DeviceUpdate>) -> Result<usize> {
+    Ok(Json(svc.update_device(id, upd.0)?))
+}
+
+#[get("/device/<id>")]
+pub fn get_device(svc: Service, id: i32) -> Result<DeviceTree> {
+    Ok(Json(svc.get_device(id)?))
+}
+
+#[get("/devices?<query..>")]
+pub fn query_device(svc: Service, query: Form<DeviceQuery>) -> Result<Page<DeviceTree>> {
+    Ok(Json(svc.query_device(&query.0)?.into()))
+}
+
+#[get("/device_info/<devinfoid>/devices?<query..>")]
+pub fn query_device_by_device_info(svc: Service, devinfoid: i32, query: Form<DeviceQuery>) -> Result<Page<DeviceTree>> {
+    Ok(Json(svc.query_device_by_device_info(devinfoid, query.0)?.into()))
+}
+
+#[get("/device/<id>/drift")]
+pub fn get_device_drift(svc: Service, id: i32) -> Result<DeviceDrift> {
+    Ok(Json(svc.device_drift(id)?))
+}
+
+#[post("/device/<id>/start", format = "application/json", data = "<ev>")]
+pub fn start_device(svc: Service, id: i32, ev: Json<EventTime>) -> Result<i32> {
+    let at = ev.0.at.unwrap_or_else(|| Local::now().naive_local());
+    Ok(Json(svc.start_device(id, at)?))
+}
+
+#[post("/device/<id>/stop", format = "application/json", data = "<ev>")]
+pub fn stop_device(svc: Service, id: i32, ev: Json<EventTime>) -> Result<i32> {
+    let at = ev.0.at.unwrap_or_else(|| Local::now().naive_local());
+    Ok(Json(svc.stop_device(id, at)?))
+}
+
+#[get("/device/<id>/events?<query..>")]
+pub fn query_device_event(svc: Service, id: i32, query: Form<DeviceEventQuery>) -> Result<Page<DeviceEvent>> {
+    Ok(Json(svc.query_device_event(id, query.0)?.into()))
+}
+
+#[post("/device/<id>/status", format = "application/json", data = "<change>")]
+pub fn change_device_status(svc: Service, id: i32, change: Json<StatusChange>) -> Result<i32> {
+    let change = change.0;
+    let at = change.at.unwrap_or_else(|| Local::now().naive_local());
+    Ok(Json(svc.change_device_status(id, change.status, change.reason, change.description, at)?))
+}
+
+#[get("/device/<id>/status_history?<query..>")]
+pub fn query_status_history(svc: Service, id: i32, query: Form<StatusHistoryQuery>) -> Result<Page<StatusHistory>> {
+    Ok(Json(svc.query_status_history(id, query.0)?.into()))
+}
+
+#[get("/device_info/<devinfoid>/propagation")]
+pub fn preview_template_changes(svc: Service, devinfoid: i32) -> Result<Vec<DeviceDrift>> {
+    Ok(Json(svc.preview_template_changes(devinfoid)?))
+}
+
+#[post("/device_info/<devinfoid>/propagation")]
+pub fn apply_template_changes(svc: Service, devinfoid: i32) -> Result<Vec<DeviceDrift>> {
+    Ok(Json(svc.apply_template_changes(devinfoid)?))
+}
+
+// ===============================================================maintenance=============================================================
+
+#[get("/device/<id>/maintain_plan")]
+pub fn get_maintain_plan(svc: Service, id: i32) -> Result<Vec<MaintainPlan>> {
+    Ok(Json(svc.maintain_plan(id)?))
+}
+
+#[get("/maintain_plans?<query..>")]
+pub fn query_maintain_plan(svc: Service, query: Form<MaintainQuery>) -> Result<Page<MaintainPlan>> {
+    Ok(Json(svc.query_maintain_plan(&query.0)?.into()))
+}
+
+// =============================================================subsystem=============================================================
+
+#[post("/subsystem", format = "application/json", data = "<sub>")]
+pub fn add_subsystem(svc: Service, sub: Json<SubsystemInsert>) -> Result<i32> {
+    Ok(Json(svc.add_subsystem(sub.0)?))
+}
+
+#[delete("/subsystem/<id>")]
+pub fn delete_subsystem(svc: Service, id: i32) -> Result<usize> {
+    Ok(Json(svc.delete_subsystem(id)?))
+}
+
+#[put("/subsystem/<id>", format = "application/json", data = "<upd>")]
+pub fn update_subsystem(svc: Service, id: i32, upd: Json<SubsystemUpdate>) -> Result<usize> {
+    Ok(Json(svc.update_subsystem(id, upd.0)?))
+}
+
+#[get("/subsystem/<id>")]
+pub fn get_subsystem(svc: Service, id: i32) -> Result<SubsystemDetail> {
+    Ok(Json(svc.get_subsystem(id)?))
+}
+
+#[get("/subsystems?<query..>")]
+pub fn query_subsystem(svc: Service, query: Form<SubsystemQuery>) -> Result<Page<SubsystemOfDevice>> {
+    Ok(Json(svc.query_subsystem(&query.0)?.into()))
+}
+
+// =============================================================component=============================================================
+
+#[post("/component", format = "application/json", data = "<com>")]
+pub fn add_component(svc: Service, com: Json<ComponentInsert>) -> Result<i32> {
+    Ok(Json(svc.add_component(com.0)?))
+}
+
+#[delete("/component/<id>")]
+pub fn delete_component(svc: Service, id: i32) -> Result<usize> {
+    Ok(Json(svc.delete_component(id)?))
+}
+
+#[put("/component/<id>", format = "application/json", data = "<upd>")]
+pub fn update_component(svc: Service, id: i32, upd: Json<ComponentUpdate>) -> Result<usize> {
+    Ok(Json(svc.update_component(id, upd.0)?))
+}
+
+#[get("/component/<id>")]
+pub fn get_component(svc: Service, id: i32) -> Result<ComponentDetail> {
+    Ok(Json(svc.get_component(id)?))
+}
+
+#[get("/components?<query..>")]
+pub fn query_component(svc: Service, query: Form<ComponentQuery>) -> Result<Page<ComponentDetail>> {
+    Ok(Json(svc.query_component(&query.0)?.into()))
+}
+
+// =============================================================work_order=============================================================
+
+#[post("/work_order", format = "application/json", data = "<order>")]
+pub fn add_work_order(svc: Service, order: Json<WorkOrderCreate>) -> Result<i32> {
+    Ok(Json(svc.create_work_order(order.0)?))
+}
+
+#[delete("/work_order/<id>")]
+pub fn delete_work_order(svc: Service, id: i32) -> Result<usize> {
+    Ok(Json(svc.delete_work_order(id)?))
+}
+
+#[put("/work_order/<id>", format = "application/json", data = "<upd>")]
+pub fn update_work_order(svc: Service, id: i32, upd: Json<WorkOrderUpdate>) -> Result<usize> {
+    Ok(Json(svc.update_work_order(id, upd.0)?))
+}
+
+#[get("/work_order/<id>")]
+pub fn get_work_order(svc: Service, id: i32) -> Result<WorkOrder> {
+    Ok(Json(svc.get_work_order(id)?))
+}
+
+#[get("/work_orders?<query..>")]
+pub fn query_work_order(svc: Service, query: Form<WorkOrderQuery>) -> Result<Page<WorkOrder>> {
+    Ok(Json(svc.query_work_order(&query.0)?.into()))
+}
+
+#[post("/work_order/<id>/start", format = "application/json", data = "<ev>")]
+pub fn start_work_order(svc: Service, id: i32, ev: Json<WorkOrderEvent>) -> Result<usize> {
+    let at = ev.0.at.unwrap_or_else(|| Local::now().naive_local());
+    Ok(Json(svc.start_work_order(id, at)?))
+}
+
+#[post("/work_order/<id>/complete", format = "application/json", data = "<ev>")]
+pub fn complete_work_order(svc: Service, id: i32, ev: Json<WorkOrderEvent>) -> Result<usize> {
+    let ev = ev.0;
+    let at = ev.at.unwrap_or_else(|| Local::now().naive_local());
+    Ok(Json(svc.complete_work_order(id, at, ev.notes)?))
+}
+
+#[post("/work_order/<id>/cancel", format = "application/json", data = "<ev>")]
+pub fn cancel_work_order(svc: Service, id: i32, ev: Json<WorkOrderEvent>) -> Result<usize> {
+    Ok(Json(svc.cancel_work_order(id, ev.0.notes)?))
+}
+
+// =============================================================fault=============================================================
+
+#[post("/fault", format = "application/json", data = "<report>")]
+pub fn report_fault(svc: Service, report: Json<FaultReport>) -> Result<i32> {
+    Ok(Json(svc.report_fault(report.0)?))
+}
+
+#[delete("/fault/<id>")]
+pub fn delete_fault(svc: Service, id: i32) -> Result<usize> {
+    Ok(Json(svc.delete_fault(id)?))
+}
+
+#[put("/fault/<id>", format = "application/json", data = "<upd>")]
+pub fn update_fault(svc: Service, id: i32, upd: Json<FaultTicketUpdate>) -> Result<usize> {
+    Ok(Json(svc.update_fault(id, upd.0)?))
+}
+
+#[get("/fault/<id>")]
+pub fn get_fault(svc: Service, id: i32) -> Result<FaultTicket> {
+    Ok(Json(svc.get_fault(id)?))
+}
+
+#[get("/faults?<query..>")]
+pub fn query_fault(svc: Service, query: Form<FaultTicketQuery>) -> Result<Page<FaultTicket>> {
+    Ok(Json(svc.query_fault(&query.0)?.into()))
+}
+
+#[post("/fault/<id>/close", format = "application/json", data = "<close>")]
+pub fn close_fault(svc: Service, id: i32, close: Json<FaultClose>) -> Result<FaultCloseResult> {
+    Ok(Json(svc.close_fault(id, close.0)?))
+}
+
+// =============================================================report=============================================================
+
+#[get("/report/reliability?<query..>")]
+pub fn reliability_report(svc: Service, query: Form<ReliabilityQuery>) -> Result<ReliabilityReport> {
+    Ok(Json(svc.reliability_report(&query.0)?))
+}
+
+#[get("/report/spare_parts?<query..>")]
+pub fn spare_part_demand(svc: Service, query: Form<DemandQuery>) -> Result<Vec<PartDemand>> {
+    Ok(Json(svc.spare_part_demand(&query.0)?))
+}
+
+#[get("/report/spare_parts/csv?<query..>")]
+pub fn spare_part_demand_csv(svc: Service, query: Form<DemandQuery>) -> std::result::Result<Content<String>, Error> {
+    Ok(Content(ContentType::CSV, svc.spare_part_demand_csv(&query.0)?))
+}
+
+// =============================================================import=============================================================
+
+// Uploads larger than this are refused rather than imported in part.
+const IMPORT_LIMIT: u64 = 16 * 1024 * 1024;
+
+#[post("/import/bom?<opts..>", data = "<data>")]
+pub fn import_bom(svc: Service, opts: Form<ImportOptions>, data: Data) -> Result<ImportReport> {
+    let mut buf = Vec::new();
+    data.open().take(IMPORT_LIMIT + 1).read_to_end(&mut buf).map_err(|e| Error::new(Status::BadRequest, "BAD_REQUEST", format!("{}", e)))?;
+    if buf.len() as u64 > IMPORT_LIMIT {
+        return Err(Error::new(Status::PayloadTooLarge, "PAYLOAD_TOO_LARGE", format!("upload exceeds {} bytes", IMPORT_LIMIT)));
+    }
+    Ok(Json(svc.import_bom(opts.format, &buf, opts.dry_run.unwrap_or(false))?))
+}
+
+// =============================================================export=============================================================
+
+#[get("/export/devices?<format>&<query..>")]
+pub fn export_devices(svc: Service, format: FileFormat, query: Form<DeviceQuery>) -> std::result::Result<Content<Stream<Box<dyn Read>>>, Error> {
+    let (top, sub) = export::content_type(format);
+    Ok(Content(ContentType::new(top, sub), Stream::from(export::devices(svc, query.0, format)?)))
+}
+
+#[get("/export/device_info/<id>?<format>")]
+pub fn export_device_info(svc: Service, id: i32, format: FileFormat) -> std::result::Result<Content<Stream<Box<dyn Read>>>, Error> {
+    let (top, sub) = export::content_type(format);
+    Ok(Content(ContentType::new(top, sub), Stream::from(export::template(&svc, id, format)?)))
+}
+
+// =============================================================inventory=============================================================
+
+#[post("/storage_location", format = "application/json", data = "<loc>")]
+pub fn add_storage_location(svc: Service, loc: Json<StorageLocationInsert>) -> Result<i32> {
+    Ok(Json(svc.add_storage_location(loc.0.name)?))
+}
+
+#[delete("/storage_location/<id>")]
+pub fn delete_storage_location(svc: Service, id: i32) -> Result<usize> {
+    Ok(Json(svc.delete_storage_location(id)?))
+}
+
+#[get("/storage_locations")]
+pub fn storage_locations(svc: Service) -> Result<Vec<StorageLocation>> {
+    Ok(Json(svc.storage_locations()?))
+}
+
+#[post("/stock/movement", format = "application/json", data = "<mov>")]
+pub fn move_stock(svc: Service, mov: Json<StockMovementRequest>) -> Result<i32> {
+    Ok(Json(svc.move_stock(mov.0)?))
+}
+
+#[get("/stocks?<query..>")]
+pub fn query_stock(svc: Service, query: Form<StockQuery>) -> Result<Page<Stock>> {
+    Ok(Json(svc.query_stock(&query.0)?.into()))
+}
+
+#[get("/stock/movements?<query..>")]
+pub fn query_stock_movement(svc: Service, query: Form<StockMovementQuery>) -> Result<Page<StockMovement>> {
+    Ok(Json(svc.query_stock_movement(&query.0)?.into()))
+}
+
+#[get("/stock/low")]
+pub fn low_stock(svc: Service) -> Result<Vec<LowStock>> {
+    Ok(Json(svc.low_stock()?))
+}
+
+#[put("/component_info/<id>/reorder_point", format = "application/json", data = "<qty>")]
+pub fn set_reorder_point(svc: Service, id: i32, qty: Json<ComponentQuantity>) -> Result<usize> {
+    Ok(Json(svc.set_reorder_point(id, qty.0.quantity)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::storer::connection::Backend;
+    use rocket::http::{ContentType, Status};
+    use rocket::local::{Client, LocalResponse};
+    use serde_json::{json, Value};
+
+    // Each test runs on a thread of its own and so against an empty in-memory store.
+    fn client() -> Client {
+        Client::new(super::super::rocket(Backend::Memory)).expect("invalid rocket instance")
+    }
+
+    fn body(res: &mut LocalResponse) -> Value {
+        serde_json::from_str(&res.body_string().expect("empty response body")).expect("response body is not JSON")
+    }
+
+    fn post(client: &Client, uri: String, data: &Value) -> (Status, Value) {
+        let mut res = client.post(uri).header(ContentType::JSON).body(data.to_string()).dispatch();
+        (res.status(), body(&mut res))
+    }
+
+    fn put(client: &Client, uri: String, data: &Value) -> (Status, Value) {
+        let mut res = client.put(uri).header(ContentType::JSON).body(data.to_string()).dispatch();
+        (res.status(), body(&mut res))
+    }
+
+    fn get(client: &Client, uri: String) -> (Status, Value) {
+        let mut res = client.get(uri).dispatch();
+        (res.status(), body(&mut res))
+    }
+
+    fn delete(client: &Client, uri: String) -> (Status, Value) {
+        let mut res = client.delete(uri).dispatch();
+        (res.status(), body(&mut res))
+    }
+
+    // Walks one template resource through create, get, query, update and delete.
+    fn crud(resource: &str, insert: Value, update: Value, field: &str) {
+        let client = client();
+        let (status, id) = post(&client, format!("/{}", resource), &insert);
+        assert_eq!(status, Status::Ok, "{}: create", resource);
+        let id = id.as_i64().expect("created id");
+
+        let (status, row) = get(&client, format!("/{}/{}", resource, id));
+        assert_eq!(status, Status::Ok, "{}: get", resource);
+        assert_eq!(row["id"], json!(id));
+        assert_eq!(row["name"], insert["name"]);
+        assert_eq!(row[field], insert[field]);
+
+        let (status, page) = get(&client, format!("/{}s?name={}&page=1&size=10", resource, insert["name"].as_str().unwrap()));
+        assert_eq!(status, Status::Ok, "{}: query", resource);
+        assert_eq!(page["total"], json!(1));
+        assert_eq!(page["list"][0]["id"], json!(id));
+        let (_, page) = get(&client, format!("/{}s?name=missing&page=1&size=10", resource));
+        assert_eq!(page["total"], json!(0));
+
+        assert_eq!(put(&client, format!("/{}/{}", resource, id), &update), (Status::Ok, json!(1)), "{}: update", resource);
+        let (_, row) = get(&client, format!("/{}/{}", resource, id));
+        assert_eq!(row[field], update[field]);
+
+        assert_eq!(delete(&client, format!("/{}/{}", resource, id)), (Status::Ok, json!(1)), "{}: delete", resource);
+        let (status, err) = get(&client, format!("/{}/{}", resource, id));
+        assert_eq!(status, Status::NotFound, "{}: get after delete", resource);
+        assert_eq!(err["code"], json!("NOT_FOUND"));
+    }
+
+    #[test]
+    fn device_info_crud() {
+        crud(
+            "device_info",
+            json!({"name": "pump", "model": "P-100", "maintain_interval": 720}),
+            json!({"maintain_interval": 360}),
+            "maintain_interval",
+        );
+    }
+
+    #[test]
+    fn subsystem_info_crud() {
+        crud("subsystem_info", json!({"name": "motor", "maintain_interval": 360}), json!({"maintain_interval": 180}), "maintain_interval");
+    }
+
+    #[test]
+    fn component_info_crud() {
+        crud(
+            "component_info",
+            json!({"name": "bearing", "model": "6204", "maintain_interval": 180}),
+            json!({"model": "6205"}),
+            "model",
+        );
+    }
+
+    #[test]
+    fn invalid_and_duplicate_templates_are_rejected() {
+        let client = client();
+        let (status, err) = post(&client, "/device_info".to_owned(), &json!({"name": "", "model": "P-100", "maintain_interval": -1}));
+        assert_eq!(status, Status::UnprocessableEntity);
+        assert_eq!(err["code"], json!("VALIDATION"));
+        assert_eq!(err["fields"][0]["field"], json!("name"));
+        assert_eq!(err["fields"][1]["field"], json!("maintain_interval"));
+
+        let info = json!({"name": "pump", "model": "P-100", "maintain_interval": 720});
+        assert_eq!(post(&client, "/device_info".to_owned(), &info).0, Status::Ok);
+        let (status, err) = post(&client, "/device_info".to_owned(), &info);
+        assert_eq!(status, Status::Conflict);
+        assert_eq!(err["code"], json!("CONFLICT"));
+
+        let (status, _) = get(&client, "/device_info/999".to_owned());
+        assert_eq!(status, Status::NotFound);
+    }
+
+    #[test]
+    fn component_quantity_needs_a_link() {
+        let client = client();
+        let devinfo = post(&client, "/device_info".to_owned(), &json!({"name": "pump", "model": "P-100", "maintain_interval": 720})).1;
+        let subinfo = post(&client, "/subsystem_info".to_owned(), &json!({"name": "motor", "maintain_interval": 360})).1;
+        let cominfo = post(&client, "/component_info".to_owned(), &json!({"name": "bearing", "model": "6204", "maintain_interval": 180})).1;
+        let uri = format!("/device_info/{}/subsystem_info/{}/component_info/{}", devinfo, subinfo, cominfo);
+
+        let (status, err) = put(&client, uri.clone(), &json!({"quantity": 3}));
+        assert_eq!(status, Status::NotFound);
+        assert_eq!(err["code"], json!("NOT_FOUND"));
+
+        assert_eq!(post(&client, format!("/device_info/{}/subsystem_info/{}", devinfo, subinfo), &json!({})).0, Status::Ok);
+        assert_eq!(post(&client, uri.clone(), &json!({"quantity": 2})).0, Status::Ok);
+        let (status, err) = put(&client, uri.clone(), &json!({"quantity": 0}));
+        assert_eq!(status, Status::UnprocessableEntity);
+        assert_eq!(err["fields"][0]["field"], json!("quantity"));
+        assert_eq!(put(&client, uri, &json!({"quantity": 3})), (Status::Ok, json!(1)));
+    }
+}
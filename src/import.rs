@@ -0,0 +1,138 @@
+use super::dao::{Error, Result};
+use super::model::*;
+use calamine::{Reader, Xlsx};
+use csv;
+use std::collections::HashMap;
+use std::io::Cursor;
+
+pub const COLUMNS: [&str; 9] = [
+    "device_name",
+    "device_model",
+    "device_maintain_interval",
+    "subsystem_name",
+    "subsystem_maintain_interval",
+    "component_name",
+    "component_model",
+    "component_maintain_interval",
+    "quantity",
+];
+
+fn cell<'a>(record: &'a HashMap<&str, String>, column: &str) -> Option<&'a str> {
+    record.get(column).map(|v| v.trim()).filter(|v| !v.is_empty())
+}
+
+fn text(record: &HashMap<&str, String>, column: &str) -> std::result::Result<String, String> {
+    cell(record, column).map(|v| v.to_owned()).ok_or_else(|| format!("{} is required", column))
+}
+
+// Spreadsheets hand numbers over as floats, 8760.0 is accepted as 8760.
+fn number(record: &HashMap<&str, String>, column: &str, default: Option<i32>) -> std::result::Result<i32, String> {
+    match cell(record, column) {
+        Some(v) => match v.parse::<f64>() {
+            Ok(n) if n.fract() == 0.0 && n >= 0.0 && n <= i32::MAX as f64 => Ok(n as i32),
+            _ => Err(format!("{} is not a non-negative integer: {}", column, v)),
+        },
+        None => default.ok_or_else(|| format!("{} is required", column)),
+    }
+}
+
+// A row describes one device_info, optionally one of its subsystems and optionally one component of that subsystem.
+fn parse_row(row: usize, record: &HashMap<&str, String>) -> std::result::Result<BomRow, String> {
+    let subsystem_name = cell(record, "subsystem_name").map(|v| v.to_owned());
+    let component_name = cell(record, "component_name").map(|v| v.to_owned());
+    if subsystem_name.is_none() && component_name.is_some() {
+        return Err("subsystem_name is required for a component".to_owned());
+    }
+    let component_model = match component_name {
+        Some(_) => Some(text(record, "component_model")?),
+        None => None,
+    };
+    let quantity = number(record, "quantity", Some(1))?;
+    if component_name.is_some() && quantity < 1 {
+        return Err(format!("quantity must be at least 1: {}", quantity));
+    }
+    Ok(BomRow {
+        row,
+        device_name: text(record, "device_name")?,
+        device_model: text(record, "device_model")?,
+        device_maintain_interval: number(record, "device_maintain_interval", None)?,
+        subsystem_maintain_interval: number(record, "subsystem_maintain_interval", if subsystem_name.is_some() { None } else { Some(0) })?,
+        component_maintain_interval: number(record, "component_maintain_interval", if component_name.is_some() { None } else { Some(0) })?,
+        quantity,
+        subsystem_name,
+        component_name,
+        component_model,
+    })
+}
+
+// Maps the header line to the known columns, unknown columns are ignored and missing required ones reject the whole file.
+fn header(cells: &[String]) -> Result<Vec<Option<&'static str>>> {
+    let index: Vec<Option<&'static str>> = cells.iter().map(|c| COLUMNS.iter().find(|col| **col == c.trim()).copied()).collect();
+    for required in &["device_name", "device_model", "device_maintain_interval"] {
+        if !index.contains(&Some(*required)) {
+            return Err(Error::Validation(format!("missing column {}", required)));
+        }
+    }
+    Ok(index)
+}
+
+// Each line comes with its 1-based row number in the file, so errors point at what the editor or spreadsheet shows.
+fn parse(lines: Vec<(usize, Vec<String>)>) -> Result<(Vec<BomRow>, Vec<RowError>)> {
+    let mut lines = lines.into_iter();
+    let (_, first) = lines.next().ok_or_else(|| Error::Validation("file is empty".to_owned()))?;
+    let index = header(&first)?;
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    for (row, line) in lines {
+        if line.iter().all(|c| c.trim().is_empty()) {
+            continue;
+        }
+        let record: HashMap<&str, String> = index.iter().zip(line.into_iter()).filter_map(|(col, v)| col.map(|c| (c, v))).collect();
+        match parse_row(row, &record) {
+            Ok(bom) => rows.push(bom),
+            Err(message) => errors.push(RowError { row, message }),
+        }
+    }
+    Ok((rows, errors))
+}
+
+pub fn read_csv(data: &[u8]) -> Result<(Vec<BomRow>, Vec<RowError>)> {
+    let mut r = csv::ReaderBuilder::new().has_headers(false).flexible(true).from_reader(data);
+    let mut lines = Vec::new();
+    // The reader skips blank lines and leaves them out of Position::line, its position sits before them.
+    // Rows are counted up to the first byte of the record instead.
+    let (mut row, mut counted) = (1, 0);
+    for record in r.records() {
+        let record = record.map_err(|e| Error::Validation(format!("{}", e)))?;
+        let mut start = record.position().map_or(counted, |p| p.byte() as usize);
+        start += data[start..].iter().take_while(|b| **b == b'\n' || **b == b'\r').count();
+        row += data[counted..start].iter().filter(|b| **b == b'\n').count();
+        counted = start;
+        lines.push((row, record.iter().map(|v| v.to_owned()).collect()));
+    }
+    parse(lines)
+}
+
+// Only the first worksheet is read.
+pub fn read_xlsx(data: &[u8]) -> Result<(Vec<BomRow>, Vec<RowError>)> {
+    let mut wb: Xlsx<_> = Xlsx::new(Cursor::new(data)).map_err(|e| Error::Validation(format!("{}", e)))?;
+    let range = wb
+        .worksheet_range_at(0)
+        .ok_or_else(|| Error::Validation("workbook has no worksheet".to_owned()))?
+        .map_err(|e| Error::Validation(format!("{}", e)))?;
+    let first = range.start().map_or(0, |(row, _)| row as usize);
+    parse(range.rows().enumerate().map(|(i, r)| (first + i + 1, r.iter().map(|c| c.to_string()).collect())).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_rows_keep_their_line_numbers_across_blank_lines() {
+        let data = "device_name,device_model,device_maintain_interval\npump,P-100,720\n\n\nvalve,V-10,x\n";
+        let (rows, errors) = read_csv(data.as_bytes()).unwrap();
+        assert_eq!(rows.iter().map(|r| r.row).collect::<Vec<usize>>(), vec![2]);
+        assert_eq!(errors.iter().map(|e| e.row).collect::<Vec<usize>>(), vec![5]);
+    }
+}
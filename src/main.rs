@@ -1,33 +1,52 @@
 #![feature(decl_macro, proc_macro_hygiene)]
 
+pub mod bench;
 pub mod dao;
+pub mod drift;
+pub mod export;
 pub mod handler;
+pub mod import;
+pub mod maintenance;
 pub mod model;
+pub mod report;
 pub mod result;
 pub mod schema;
 pub mod service;
+pub mod status;
 pub mod storer;
+pub mod validate;
 
+extern crate calamine;
 extern crate chrono;
+extern crate csv;
 #[macro_use]
 extern crate diesel;
 extern crate dotenv;
 #[macro_use]
 extern crate rocket;
 extern crate r2d2;
+extern crate rust_xlsxwriter;
+
+#[cfg(not(any(feature = "mysql", feature = "postgres", feature = "sqlite")))]
+compile_error!("enable at least one of the mysql, postgres or sqlite features");
 
-use diesel::mysql::MysqlConnection;
-use diesel::r2d2::{ConnectionManager, Pool, PooledConnection};
 use dotenv::dotenv;
-use rocket::http::Status;
-use rocket::request::{FromRequest, Outcome, Request};
-use rocket::State;
 use std::env;
-use std::ops::Deref;
-use std::time;
+use storer::connection::Backend;
+#[cfg(feature = "mysql")]
+use {
+    diesel::mysql::MysqlConnection,
+    diesel::r2d2::{ConnectionManager, PooledConnection},
+    rocket::http::Status,
+    rocket::request::{FromRequest, Outcome, Request},
+    rocket::State,
+    std::ops::Deref,
+};
 
+#[cfg(feature = "mysql")]
 pub struct MysqlConn(PooledConnection<ConnectionManager<MysqlConnection>>);
 
+#[cfg(feature = "mysql")]
 impl Deref for MysqlConn {
     type Target = MysqlConnection;
     fn deref(&self) -> &Self::Target {
@@ -35,14 +54,18 @@ impl Deref for MysqlConn {
     }
 }
 
-
+#[cfg(feature = "mysql")]
 impl<'a, 'r> FromRequest<'a, 'r> for MysqlConn {
     type Error = ();
     fn from_request(request: &'a Request<'r>) -> Outcome<Self, Self::Error> {
-        let pool = request.guard::<State<Pool<ConnectionManager<MysqlConnection>>>>()?;
-        match pool.get() {
-            Ok(conn) => Outcome::Success(MysqlConn(conn)),
-            Err(_) => rocket::Outcome::Failure((Status::InternalServerError, ())),
+        let backend = request.guard::<State<Backend>>()?;
+        match backend.inner() {
+            Backend::Mysql(pool) => match pool.get() {
+                Ok(conn) => Outcome::Success(MysqlConn(conn)),
+                Err(_) => rocket::Outcome::Failure((Status::InternalServerError, ())),
+            },
+            #[allow(unreachable_patterns)]
+            _ => rocket::Outcome::Failure((Status::InternalServerError, ())),
         }
     }
 }
@@ -50,30 +73,101 @@ impl<'a, 'r> FromRequest<'a, 'r> for MysqlConn {
 fn main() {
     dotenv().ok().unwrap();
     let url = env::var("DATABASE_URL").expect("no database url");
-    let pool = Pool::builder()
-        .max_size(10)
-        .max_lifetime(Some(time::Duration::from_secs(300)))
-        .build(ConnectionManager::<MysqlConnection>::new(url))
-        .expect("failed to construct connection pool");
+    let backend = env::var("DATABASE_BACKEND").unwrap_or_else(|_| "mysql".to_owned());
+    let pool = storer::connection::pool(&backend, &url).expect("failed to construct connection pool");
+    if env::args().nth(1).as_ref().map(String::as_str) == Some("bench") {
+        let args: Vec<String> = env::args().skip(2).collect();
+        bench::run(&pool, &args).expect("benchmark failed");
+        return;
+    }
+    rocket(pool).launch();
+}
+
+// Every route of the service, served from the given backend.
+pub fn rocket(backend: Backend) -> rocket::Rocket {
     rocket::ignite()
-        .manage(pool)
+        .manage(backend)
         .mount(
             "/",
             routes![
-                // handler::add_device_info,
-                // handler::bulk_add_device_info,
-                // handler::query_device_info,
-                // handler::get_device_info,
-                // handler::delete_device_info,
-                // handler::update_device_info,
-                // handler::add_device,
-                // handler::bulk_add_device,
-                // handler::get_device,
-                // handler::query_device,
-                // handler::update_device,
-                // handler::delete_device,
-                // handler::query_subsystem,
+                handler::add_device_info,
+                handler::delete_device_info,
+                handler::update_device_info,
+                handler::get_device_info,
+                handler::query_device_info,
+                handler::query_device_info_by_subsystem_info,
+                handler::add_subsystem_info,
+                handler::delete_subsystem_info,
+                handler::update_subsystem_info,
+                handler::get_subsystem_info,
+                handler::query_subsystem_info,
+                handler::query_subsystem_info_by_device_info,
+                handler::query_subsystem_info_by_component_info,
+                handler::add_component_info,
+                handler::delete_component_info,
+                handler::update_component_info,
+                handler::get_component_info,
+                handler::query_component_info,
+                handler::query_component_info_by_subsystem_info,
+                handler::attach_subsystem_info,
+                handler::remove_subsystem_info,
+                handler::attach_component_info,
+                handler::set_component_info_quantity,
+                handler::remove_component_info,
+                handler::add_device,
+                handler::delete_device,
+                handler::update_device,
+                handler::get_device,
+                handler::query_device,
+                handler::query_device_by_device_info,
+                handler::get_device_drift,
+                handler::start_device,
+                handler::stop_device,
+                handler::query_device_event,
+                handler::change_device_status,
+                handler::query_status_history,
+                handler::preview_template_changes,
+                handler::apply_template_changes,
+                handler::get_maintain_plan,
+                handler::query_maintain_plan,
+                handler::add_subsystem,
+                handler::delete_subsystem,
+                handler::update_subsystem,
+                handler::get_subsystem,
+                handler::query_subsystem,
+                handler::add_component,
+                handler::delete_component,
+                handler::update_component,
+                handler::get_component,
+                handler::query_component,
+                handler::add_work_order,
+                handler::delete_work_order,
+                handler::update_work_order,
+                handler::get_work_order,
+                handler::query_work_order,
+                handler::start_work_order,
+                handler::complete_work_order,
+                handler::cancel_work_order,
+                handler::report_fault,
+                handler::delete_fault,
+                handler::update_fault,
+                handler::get_fault,
+                handler::query_fault,
+                handler::close_fault,
+                handler::reliability_report,
+                handler::spare_part_demand,
+                handler::spare_part_demand_csv,
+                handler::import_bom,
+                handler::export_devices,
+                handler::export_device_info,
+                handler::add_storage_location,
+                handler::delete_storage_location,
+                handler::storage_locations,
+                handler::move_stock,
+                handler::query_stock,
+                handler::query_stock_movement,
+                handler::low_stock,
+                handler::set_reorder_point,
             ],
         )
-        .launch();
 }
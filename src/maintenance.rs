@@ -0,0 +1,169 @@
+use super::model::*;
+use chrono::NaiveDateTime;
+use std::collections::BTreeMap;
+
+// Plans whose remaining hours are within this window are reported as due soon when the query gives no window.
+pub const DUE_SOON_HOURS: i32 = 24;
+
+// Whole hours between two instants, rounded to the nearest hour.
+pub fn run_hours(from: NaiveDateTime, to: NaiveDateTime) -> i32 {
+    ((to - from).num_minutes() as f64 / 60.0).round() as i32
+}
+
+// Accumulated run hours of a device, including the still running stretch since last_start_at.
+pub fn runtime(dev: &Device, now: NaiveDateTime) -> i32 {
+    match (dev.status, dev.last_start_at) {
+        (DeviceStatus::Running, Some(start)) if now > start => dev.total_duration + run_hours(start, now),
+        _ => dev.total_duration,
+    }
+}
+
+struct Clock<'a> {
+    name: &'a str,
+    maintain_interval: i32,
+    last_maintain_at: Option<NaiveDateTime>,
+    last_maintain_duration: i32,
+}
+
+fn plan_item(target: MaintainTarget, dev: &Device, subsystem_id: Option<i32>, component_id: Option<i32>, clock: Clock, runtime: i32) -> Option<MaintainPlan> {
+    if clock.maintain_interval <= 0 {
+        return None;
+    }
+    let since = runtime - clock.last_maintain_duration;
+    let remaining = clock.maintain_interval - since;
+    Some(MaintainPlan {
+        target,
+        device_id: dev.id,
+        device_name: dev.name.clone(),
+        subsystem_id,
+        component_id,
+        name: clock.name.to_owned(),
+        maintain_interval: clock.maintain_interval,
+        last_maintain_at: clock.last_maintain_at,
+        runtime_since_maintain: since,
+        remaining,
+        overdue: remaining <= 0,
+    })
+}
+
+// Hours left until the next service of the device and every subsystem and component in it, levels without an interval are skipped.
+pub fn plan(tree: &(Device, Vec<(Subsystem, Vec<Component>)>), now: NaiveDateTime) -> Vec<MaintainPlan> {
+    let (dev, subs) = tree;
+    let hours = runtime(dev, now);
+    let mut plans = Vec::new();
+    plans.extend(plan_item(
+        MaintainTarget::Device,
+        dev,
+        None,
+        None,
+        Clock {
+            name: &dev.name,
+            maintain_interval: dev.maintain_interval,
+            last_maintain_at: dev.last_maintain_at,
+            last_maintain_duration: dev.last_maintain_duration,
+        },
+        hours,
+    ));
+    for (sub, coms) in subs {
+        plans.extend(plan_item(
+            MaintainTarget::Subsystem,
+            dev,
+            Some(sub.id),
+            None,
+            Clock {
+                name: &sub.name,
+                maintain_interval: sub.maintain_interval,
+                last_maintain_at: sub.last_maintain_at,
+                last_maintain_duration: sub.last_maintain_duration,
+            },
+            hours,
+        ));
+        for com in coms {
+            plans.extend(plan_item(
+                MaintainTarget::Component,
+                dev,
+                Some(sub.id),
+                Some(com.id),
+                Clock {
+                    name: &com.name,
+                    maintain_interval: com.maintain_interval,
+                    last_maintain_at: com.last_maintain_at,
+                    last_maintain_duration: com.last_maintain_duration,
+                },
+                hours,
+            ));
+        }
+    }
+    plans
+}
+
+// Run hours a device is expected to accumulate over the coming days, at the utilisation it has had since it was created.
+pub fn expected_hours(dev: &Device, now: NaiveDateTime, days: i32) -> i32 {
+    let lifetime = run_hours(dev.create_at, now);
+    if lifetime <= 0 {
+        return 0;
+    }
+    let utilisation = (runtime(dev, now) as f64 / lifetime as f64).min(1.0);
+    (days as f64 * 24.0 * utilisation).round() as i32
+}
+
+// Replacements a component needs within the horizon, an overdue one counts once right away.
+fn replacements(com: &Component, runtime: i32, horizon: i32) -> i32 {
+    if com.maintain_interval <= 0 {
+        return 0;
+    }
+    let remaining = (com.maintain_interval - (runtime - com.last_maintain_duration)).max(0);
+    if remaining > horizon {
+        0
+    } else {
+        1 + (horizon - remaining) / com.maintain_interval
+    }
+}
+
+// Spare parts the fleet will consume within the horizon, grouped by part name and model. Devices are added a page at a time.
+#[derive(Default)]
+pub struct Demand(BTreeMap<(String, String), PartDemand>);
+
+impl Demand {
+    // horizon gives the run hours for each device.
+    pub fn add_devices<F>(&mut self, trees: &[(Device, Vec<(Subsystem, Vec<Component>)>)], now: NaiveDateTime, horizon: F)
+    where
+        F: Fn(&Device) -> i32,
+    {
+        for (dev, subs) in trees {
+            let hours = runtime(dev, now);
+            let horizon_hours = horizon(dev);
+            for com in subs.iter().flat_map(|(_, coms)| coms) {
+                let quantity = replacements(com, hours, horizon_hours);
+                if quantity == 0 {
+                    continue;
+                }
+                let part = self.0.entry((com.name.clone(), com.model.clone())).or_insert_with(|| PartDemand {
+                    component_info_id: com.component_info_id,
+                    name: com.name.clone(),
+                    model: com.model.clone(),
+                    quantity: 0,
+                    devices: Vec::new(),
+                });
+                part.quantity += quantity;
+                if part.component_info_id.is_none() {
+                    part.component_info_id = com.component_info_id;
+                }
+                match part.devices.iter_mut().find(|d| d.device_id == dev.id) {
+                    Some(d) => d.quantity += quantity,
+                    None => part.devices.push(DeviceDemand {
+                        device_id: dev.id,
+                        device_name: dev.name.clone(),
+                        unicode: dev.unicode.clone(),
+                        horizon_hours,
+                        quantity,
+                    }),
+                }
+            }
+        }
+    }
+
+    pub fn finish(self) -> Vec<PartDemand> {
+        self.0.into_iter().map(|(_, v)| v).collect()
+    }
+}
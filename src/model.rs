@@ -11,7 +11,7 @@ use serde::{Deserialize, Serialize};
 use std::default::Default;
 use std::io::Write;
 
-#[derive(Debug, Copy, Clone, AsExpression, FromSqlRow, Deserialize, Serialize)]
+#[derive(Debug, Copy, Clone, PartialEq, AsExpression, FromSqlRow, Deserialize, Serialize)]
 #[sql_type = "VarChar"]
 pub enum DeviceStatus {
     Running,
@@ -61,6 +61,288 @@ impl<'v> FromFormValue<'v> for DeviceStatus {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, AsExpression, FromSqlRow, Deserialize, Serialize)]
+#[sql_type = "VarChar"]
+pub enum StatusReason {
+    Scheduled,
+    Manual,
+    Maintenance,
+    Fault,
+    Repaired,
+}
+
+impl<DB> ToSql<VarChar, DB> for StatusReason
+where
+    DB: Backend,
+    String: ToSql<VarChar, DB>,
+{
+    fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> serialize::Result {
+        match *self {
+            StatusReason::Scheduled => "Scheduled".to_owned().to_sql(out),
+            StatusReason::Manual => "Manual".to_owned().to_sql(out),
+            StatusReason::Maintenance => "Maintenance".to_owned().to_sql(out),
+            StatusReason::Fault => "Fault".to_owned().to_sql(out),
+            StatusReason::Repaired => "Repaired".to_owned().to_sql(out),
+        }
+    }
+}
+
+impl<DB> FromSql<VarChar, DB> for StatusReason
+where
+    DB: Backend,
+    String: FromSql<VarChar, DB>,
+{
+    fn from_sql(val: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+        match String::from_sql(val)?.as_ref() {
+            "Scheduled" => Ok(Self::Scheduled),
+            "Manual" => Ok(Self::Manual),
+            "Maintenance" => Ok(Self::Maintenance),
+            "Fault" => Ok(Self::Fault),
+            "Repaired" => Ok(Self::Repaired),
+            _ => Err("unknown status reason".into()),
+        }
+    }
+}
+
+impl<'v> FromFormValue<'v> for StatusReason {
+    type Error = String;
+
+    fn from_form_value(s: &'v RawStr) -> Result<Self, Self::Error> {
+        match s.as_str() {
+            "Scheduled" => Ok(Self::Scheduled),
+            "Manual" => Ok(Self::Manual),
+            "Maintenance" => Ok(Self::Maintenance),
+            "Fault" => Ok(Self::Fault),
+            "Repaired" => Ok(Self::Repaired),
+            _ => Err("unknown status reason".to_owned()),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, AsExpression, FromSqlRow, Deserialize, Serialize)]
+#[sql_type = "VarChar"]
+pub enum MaintainTarget {
+    Device,
+    Subsystem,
+    Component,
+}
+
+impl<DB> ToSql<VarChar, DB> for MaintainTarget
+where
+    DB: Backend,
+    String: ToSql<VarChar, DB>,
+{
+    fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> serialize::Result {
+        match *self {
+            MaintainTarget::Device => "Device".to_owned().to_sql(out),
+            MaintainTarget::Subsystem => "Subsystem".to_owned().to_sql(out),
+            MaintainTarget::Component => "Component".to_owned().to_sql(out),
+        }
+    }
+}
+
+impl<DB> FromSql<VarChar, DB> for MaintainTarget
+where
+    DB: Backend,
+    String: FromSql<VarChar, DB>,
+{
+    fn from_sql(val: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+        match String::from_sql(val)?.as_ref() {
+            "Device" => Ok(Self::Device),
+            "Subsystem" => Ok(Self::Subsystem),
+            "Component" => Ok(Self::Component),
+            _ => Err("unknown maintain target".into()),
+        }
+    }
+}
+
+impl<'v> FromFormValue<'v> for MaintainTarget {
+    type Error = String;
+
+    fn from_form_value(s: &'v RawStr) -> Result<Self, Self::Error> {
+        match s.as_str() {
+            "Device" => Ok(Self::Device),
+            "Subsystem" => Ok(Self::Subsystem),
+            "Component" => Ok(Self::Component),
+            _ => Err("unknown maintain target".to_owned()),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, AsExpression, FromSqlRow, Deserialize, Serialize)]
+#[sql_type = "VarChar"]
+pub enum WorkOrderStatus {
+    Planned,
+    InProgress,
+    Done,
+    Cancelled,
+}
+
+impl<DB> ToSql<VarChar, DB> for WorkOrderStatus
+where
+    DB: Backend,
+    String: ToSql<VarChar, DB>,
+{
+    fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> serialize::Result {
+        match *self {
+            WorkOrderStatus::Planned => "Planned".to_owned().to_sql(out),
+            WorkOrderStatus::InProgress => "InProgress".to_owned().to_sql(out),
+            WorkOrderStatus::Done => "Done".to_owned().to_sql(out),
+            WorkOrderStatus::Cancelled => "Cancelled".to_owned().to_sql(out),
+        }
+    }
+}
+
+impl<DB> FromSql<VarChar, DB> for WorkOrderStatus
+where
+    DB: Backend,
+    String: FromSql<VarChar, DB>,
+{
+    fn from_sql(val: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+        match String::from_sql(val)?.as_ref() {
+            "Planned" => Ok(Self::Planned),
+            "InProgress" => Ok(Self::InProgress),
+            "Done" => Ok(Self::Done),
+            "Cancelled" => Ok(Self::Cancelled),
+            _ => Err("unknown work order status".into()),
+        }
+    }
+}
+
+impl<'v> FromFormValue<'v> for WorkOrderStatus {
+    type Error = String;
+
+    fn from_form_value(s: &'v RawStr) -> Result<Self, Self::Error> {
+        match s.as_str() {
+            "Planned" => Ok(Self::Planned),
+            "InProgress" => Ok(Self::InProgress),
+            "Done" => Ok(Self::Done),
+            "Cancelled" => Ok(Self::Cancelled),
+            _ => Err("unknown work order status".to_owned()),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, AsExpression, FromSqlRow, Deserialize, Serialize)]
+#[sql_type = "VarChar"]
+pub enum DeviceEventKind {
+    Start,
+    Stop,
+}
+
+impl<DB> ToSql<VarChar, DB> for DeviceEventKind
+where
+    DB: Backend,
+    String: ToSql<VarChar, DB>,
+{
+    fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> serialize::Result {
+        match *self {
+            DeviceEventKind::Start => "Start".to_owned().to_sql(out),
+            DeviceEventKind::Stop => "Stop".to_owned().to_sql(out),
+        }
+    }
+}
+
+impl<DB> FromSql<VarChar, DB> for DeviceEventKind
+where
+    DB: Backend,
+    String: FromSql<VarChar, DB>,
+{
+    fn from_sql(val: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+        match String::from_sql(val)?.as_ref() {
+            "Start" => Ok(Self::Start),
+            "Stop" => Ok(Self::Stop),
+            _ => Err("unknown device event".into()),
+        }
+    }
+}
+
+impl<'v> FromFormValue<'v> for DeviceEventKind {
+    type Error = String;
+
+    fn from_form_value(s: &'v RawStr) -> Result<Self, Self::Error> {
+        match s.as_str() {
+            "Start" => Ok(Self::Start),
+            "Stop" => Ok(Self::Stop),
+            _ => Err("unknown device event".to_owned()),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, AsExpression, FromSqlRow, Deserialize, Serialize)]
+#[sql_type = "VarChar"]
+pub enum StockMovementKind {
+    Receive,
+    Issue,
+    Adjust,
+    Transfer,
+}
+
+impl<DB> ToSql<VarChar, DB> for StockMovementKind
+where
+    DB: Backend,
+    String: ToSql<VarChar, DB>,
+{
+    fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> serialize::Result {
+        match *self {
+            StockMovementKind::Receive => "Receive".to_owned().to_sql(out),
+            StockMovementKind::Issue => "Issue".to_owned().to_sql(out),
+            StockMovementKind::Adjust => "Adjust".to_owned().to_sql(out),
+            StockMovementKind::Transfer => "Transfer".to_owned().to_sql(out),
+        }
+    }
+}
+
+impl<DB> FromSql<VarChar, DB> for StockMovementKind
+where
+    DB: Backend,
+    String: FromSql<VarChar, DB>,
+{
+    fn from_sql(val: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+        match String::from_sql(val)?.as_ref() {
+            "Receive" => Ok(Self::Receive),
+            "Issue" => Ok(Self::Issue),
+            "Adjust" => Ok(Self::Adjust),
+            "Transfer" => Ok(Self::Transfer),
+            _ => Err("unknown stock movement".into()),
+        }
+    }
+}
+
+impl<'v> FromFormValue<'v> for StockMovementKind {
+    type Error = String;
+
+    fn from_form_value(s: &'v RawStr) -> Result<Self, Self::Error> {
+        match s.as_str() {
+            "Receive" => Ok(Self::Receive),
+            "Issue" => Ok(Self::Issue),
+            "Adjust" => Ok(Self::Adjust),
+            "Transfer" => Ok(Self::Transfer),
+            _ => Err("unknown stock movement".to_owned()),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FileFormat {
+    Csv,
+    Xlsx,
+    Json,
+}
+
+impl<'v> FromFormValue<'v> for FileFormat {
+    type Error = String;
+
+    fn from_form_value(s: &'v RawStr) -> Result<Self, Self::Error> {
+        match s.as_str() {
+            "csv" => Ok(Self::Csv),
+            "xlsx" => Ok(Self::Xlsx),
+            "json" => Ok(Self::Json),
+            _ => Err("unknown file format".to_owned()),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MyDatetime(pub NaiveDateTime);
 
@@ -115,6 +397,7 @@ pub struct DeviceInfoUpdate {
 #[derive(Insertable, Debug, Deserialize)]
 #[table_name = "device"]
 pub struct DeviceInsert {
+    pub device_info_id: Option<i32>,
     pub name: String,
     pub model: String,
     pub maintain_interval: i32,
@@ -129,6 +412,7 @@ pub struct DeviceInsert {
 #[table_name = "device"]
 pub struct Device {
     pub id: i32,
+    pub device_info_id: Option<i32>,
     pub name: String,
     pub model: String,
     pub maintain_interval: i32,
@@ -139,10 +423,13 @@ pub struct Device {
     pub status: DeviceStatus,
     pub create_at: NaiveDateTime,
     pub update_at: NaiveDateTime,
+    pub last_maintain_at: Option<NaiveDateTime>,
+    pub last_maintain_duration: i32,
 }
 
 #[derive(Debug, Queryable, Default, Deserialize, Serialize, FromForm)]
 pub struct DeviceQuery {
+    pub device_info_id: Option<i32>,
     pub name: Option<String>,
     pub model: Option<String>,
     pub maintain_interval_begin: Option<i32>,
@@ -159,16 +446,19 @@ pub struct DeviceQuery {
     pub size: Option<i64>,
 }
 
-#[derive(Debug, AsChangeset, Serialize, Deserialize)]
+#[derive(Debug, AsChangeset, Serialize, Deserialize, Default)]
 #[table_name = "device"]
 pub struct DeviceUpdate {
     pub name: Option<String>,
     pub model: Option<String>,
+    pub maintain_interval: Option<i32>,
     pub unicode: Option<String>,
     pub last_start_at: Option<NaiveDateTime>,
     pub last_stop_at: Option<NaiveDateTime>,
     pub total_duration: Option<i32>,
     pub status: Option<DeviceStatus>,
+    pub last_maintain_at: Option<NaiveDateTime>,
+    pub last_maintain_duration: Option<i32>,
 }
 
 #[derive(Insertable, Debug, Deserialize, Serialize)]
@@ -194,7 +484,7 @@ pub struct SubsystemInfoUpdate {
     pub maintain_interval: Option<i32>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Identifiable, Associations, Queryable)]
+#[derive(Deserialize, Serialize, Debug, Identifiable, Associations, Queryable, Clone)]
 #[table_name = "subsystem_info"]
 pub struct SubsystemInfo {
     pub id: i32,
@@ -208,15 +498,18 @@ pub struct SubsystemInfo {
 #[table_name = "subsystem"]
 pub struct SubsystemInsert {
     pub device_id: i32,
+    pub subsystem_info_id: Option<i32>,
     pub name: String,
     pub maintain_interval: i32,
 }
 
-#[derive(Debug, Deserialize, Serialize, AsChangeset)]
+#[derive(Debug, Deserialize, Serialize, AsChangeset, Default)]
 #[table_name = "subsystem"]
 pub struct SubsystemUpdate {
     pub name: Option<String>,
     pub maintain_interval: Option<i32>,
+    pub last_maintain_at: Option<NaiveDateTime>,
+    pub last_maintain_duration: Option<i32>,
 }
 
 #[derive(Queryable, Debug, Deserialize, Serialize, Default, FromForm)]
@@ -246,13 +539,16 @@ pub struct SubsystemQuery {
 pub struct Subsystem {
     pub id: i32,
     pub device_id: i32,
+    pub subsystem_info_id: Option<i32>,
     pub name: String,
     pub maintain_interval: i32,
     pub create_at: NaiveDateTime,
     pub udpate_at: NaiveDateTime,
+    pub last_maintain_at: Option<NaiveDateTime>,
+    pub last_maintain_duration: i32,
 }
 
-#[derive(Queryable, Debug, Serialize, Deserialize)]
+#[derive(Queryable, Debug, Serialize, Deserialize, Clone)]
 pub struct ComponentInfo {
     pub id: i32,
     pub name: String,
@@ -288,34 +584,48 @@ pub struct ComponentInfoQuery {
     pub size: i64,
 }
 
-#[derive(Debug, Serialize, Deserialize, Queryable, Associations, Identifiable)]
+#[derive(Debug, Serialize, Deserialize, Queryable, Associations, Identifiable, Clone)]
 #[table_name = "component"]
 #[belongs_to(Subsystem)]
 pub struct Component {
     pub id: i32,
     pub subsystem_id: i32,
+    pub component_info_id: Option<i32>,
     pub name: String,
     pub model: String,
     pub maintain_interval: i32,
+    pub position: i32,
     pub create_at: NaiveDateTime,
     pub update_at: NaiveDateTime,
+    pub last_maintain_at: Option<NaiveDateTime>,
+    pub last_maintain_duration: i32,
 }
 
 #[derive(Insertable, Debug, Serialize, Deserialize)]
 #[table_name = "component"]
 pub struct ComponentInsert {
     pub subsystem_id: i32,
+    pub component_info_id: Option<i32>,
     pub name: String,
     pub model: String,
     pub maintain_interval: i32,
+    pub position: i32,
 }
 
-#[derive(Debug, Serialize, Deserialize, FromForm)]
+#[derive(Debug, Serialize, Deserialize, Default, FromForm)]
 pub struct ComponentQuery {
     pub device_name: Option<String>,
     pub device_model: Option<String>,
     pub device_maintain_interval_begin: Option<i32>,
     pub device_maintain_interval_end: Option<i32>,
+    pub device_unicode: Option<String>,
+    pub device_last_start_at_begin: Option<MyDatetime>,
+    pub device_last_start_at_end: Option<MyDatetime>,
+    pub device_last_stop_at_begin: Option<MyDatetime>,
+    pub device_last_stop_at_end: Option<MyDatetime>,
+    pub device_total_duration_begin: Option<i32>,
+    pub device_total_duration_end: Option<i32>,
+    pub device_status: Option<DeviceStatus>,
     pub subsystem_name: Option<String>,
     pub subsystem_maintain_interval_begin: Option<i32>,
     pub subsystem_maintain_interval_end: Option<i32>,
@@ -327,15 +637,17 @@ pub struct ComponentQuery {
     pub size: Option<i64>,
 }
 
-#[derive(Debug, Serialize, Deserialize, AsChangeset)]
+#[derive(Debug, Serialize, Deserialize, AsChangeset, Default)]
 #[table_name = "component"]
 pub struct ComponentUpdate {
     pub name: Option<String>,
     pub model: Option<String>,
     pub maintain_interval: Option<i32>,
+    pub last_maintain_at: Option<NaiveDateTime>,
+    pub last_maintain_duration: Option<i32>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Associations, Identifiable, Queryable)]
+#[derive(Debug, Serialize, Deserialize, Associations, Identifiable, Queryable, Clone)]
 #[table_name = "deviceinfo_subsysteminfo"]
 #[belongs_to(DeviceInfo)]
 #[belongs_to(SubsystemInfo)]
@@ -352,7 +664,7 @@ pub struct DevinfoSubinfoInsert {
     pub subsystem_info_id: i32,
 }
 
-#[derive(Debug, Serialize, Deserialize, Associations, Identifiable, Queryable)]
+#[derive(Debug, Serialize, Deserialize, Associations, Identifiable, Queryable, Clone)]
 #[table_name = "subsysteminfo_componentinfo"]
 #[belongs_to(SubsystemInfo)]
 #[belongs_to(ComponentInfo)]
@@ -372,3 +684,651 @@ pub struct SubinfoCominfoInsert {
     pub component_info_id: i32,
     pub quantity: i32,
 }
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Identifiable, Associations, Clone)]
+#[table_name = "fault_ticket"]
+#[belongs_to(Device)]
+pub struct FaultTicket {
+    pub id: i32,
+    pub device_id: i32,
+    pub subsystem_id: i32,
+    pub component_id: Option<i32>,
+    pub failure_code: String,
+    pub description: Option<String>,
+    pub reported_at: NaiveDateTime,
+    pub repaired_at: Option<NaiveDateTime>,
+    pub resolution: Option<String>,
+    pub create_at: NaiveDateTime,
+    pub update_at: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "fault_ticket"]
+pub struct FaultTicketInsert {
+    pub device_id: i32,
+    pub subsystem_id: i32,
+    pub component_id: Option<i32>,
+    pub failure_code: String,
+    pub description: Option<String>,
+    pub reported_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, AsChangeset, Default)]
+#[table_name = "fault_ticket"]
+pub struct FaultTicketUpdate {
+    pub failure_code: Option<String>,
+    pub description: Option<String>,
+    pub reported_at: Option<NaiveDateTime>,
+    pub repaired_at: Option<NaiveDateTime>,
+    pub resolution: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromForm, Default)]
+pub struct FaultTicketQuery {
+    pub device_id: Option<i32>,
+    pub subsystem_id: Option<i32>,
+    pub component_id: Option<i32>,
+    pub failure_code: Option<String>,
+    pub open: Option<bool>,
+    pub reported_at_begin: Option<MyDatetime>,
+    pub reported_at_end: Option<MyDatetime>,
+    pub repaired_at_begin: Option<MyDatetime>,
+    pub repaired_at_end: Option<MyDatetime>,
+    pub page: Option<i64>,
+    pub size: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Identifiable, Clone)]
+#[table_name = "storage_location"]
+pub struct StorageLocation {
+    pub id: i32,
+    pub name: String,
+    pub create_at: NaiveDateTime,
+    pub update_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, Insertable)]
+#[table_name = "storage_location"]
+pub struct StorageLocationInsert {
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Identifiable, Clone)]
+#[table_name = "stock"]
+pub struct Stock {
+    pub id: i32,
+    pub component_info_id: i32,
+    pub location_id: i32,
+    pub quantity: i32,
+    pub update_at: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "stock"]
+pub struct StockInsert {
+    pub component_info_id: i32,
+    pub location_id: i32,
+    pub quantity: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromForm, Default)]
+pub struct StockQuery {
+    pub component_info_id: Option<i32>,
+    pub location_id: Option<i32>,
+    pub page: Option<i64>,
+    pub size: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Identifiable, Clone)]
+#[table_name = "stock_movement"]
+pub struct StockMovement {
+    pub id: i32,
+    pub component_info_id: i32,
+    pub kind: StockMovementKind,
+    pub from_location_id: Option<i32>,
+    pub to_location_id: Option<i32>,
+    pub quantity: i32,
+    pub note: Option<String>,
+    pub happen_at: NaiveDateTime,
+    pub create_at: NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize, Insertable)]
+#[table_name = "stock_movement"]
+pub struct StockMovementInsert {
+    pub component_info_id: i32,
+    pub kind: StockMovementKind,
+    pub from_location_id: Option<i32>,
+    pub to_location_id: Option<i32>,
+    pub quantity: i32,
+    pub note: Option<String>,
+    pub happen_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromForm, Default)]
+pub struct StockMovementQuery {
+    pub component_info_id: Option<i32>,
+    pub location_id: Option<i32>,
+    pub kind: Option<StockMovementKind>,
+    pub happen_at_begin: Option<MyDatetime>,
+    pub happen_at_end: Option<MyDatetime>,
+    pub page: Option<i64>,
+    pub size: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Clone)]
+#[table_name = "reorder_point"]
+pub struct ReorderPoint {
+    pub component_info_id: i32,
+    pub quantity: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Identifiable, Associations, Clone)]
+#[table_name = "work_order"]
+#[belongs_to(Device)]
+pub struct WorkOrder {
+    pub id: i32,
+    pub device_id: i32,
+    pub subsystem_id: Option<i32>,
+    pub component_id: Option<i32>,
+    pub target: MaintainTarget,
+    pub status: WorkOrderStatus,
+    pub technician: String,
+    pub notes: Option<String>,
+    pub planned_at: Option<NaiveDateTime>,
+    pub started_at: Option<NaiveDateTime>,
+    pub finished_at: Option<NaiveDateTime>,
+    pub create_at: NaiveDateTime,
+    pub update_at: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "work_order"]
+pub struct WorkOrderInsert {
+    pub device_id: i32,
+    pub subsystem_id: Option<i32>,
+    pub component_id: Option<i32>,
+    pub target: MaintainTarget,
+    pub status: WorkOrderStatus,
+    pub technician: String,
+    pub notes: Option<String>,
+    pub planned_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize, AsChangeset, Default)]
+#[table_name = "work_order"]
+pub struct WorkOrderUpdate {
+    pub technician: Option<String>,
+    pub notes: Option<String>,
+    pub planned_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, AsChangeset)]
+#[table_name = "work_order"]
+pub struct WorkOrderTransition {
+    pub status: WorkOrderStatus,
+    pub started_at: Option<NaiveDateTime>,
+    pub finished_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromForm, Default)]
+pub struct WorkOrderQuery {
+    pub device_id: Option<i32>,
+    pub subsystem_id: Option<i32>,
+    pub component_id: Option<i32>,
+    pub target: Option<MaintainTarget>,
+    pub status: Option<WorkOrderStatus>,
+    pub technician: Option<String>,
+    pub planned_at_begin: Option<MyDatetime>,
+    pub planned_at_end: Option<MyDatetime>,
+    pub finished_at_begin: Option<MyDatetime>,
+    pub finished_at_end: Option<MyDatetime>,
+    pub page: Option<i64>,
+    pub size: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Identifiable, Associations, Clone)]
+#[table_name = "device_status_history"]
+#[belongs_to(Device)]
+pub struct StatusHistory {
+    pub id: i32,
+    pub device_id: i32,
+    pub from_status: DeviceStatus,
+    pub to_status: DeviceStatus,
+    pub reason: StatusReason,
+    pub description: Option<String>,
+    pub happen_at: NaiveDateTime,
+    pub create_at: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "device_status_history"]
+pub struct StatusHistoryInsert {
+    pub device_id: i32,
+    pub from_status: DeviceStatus,
+    pub to_status: DeviceStatus,
+    pub reason: StatusReason,
+    pub description: Option<String>,
+    pub happen_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromForm, Default)]
+pub struct StatusHistoryQuery {
+    pub device_id: Option<i32>,
+    pub from_status: Option<DeviceStatus>,
+    pub to_status: Option<DeviceStatus>,
+    pub reason: Option<StatusReason>,
+    pub happen_at_begin: Option<MyDatetime>,
+    pub happen_at_end: Option<MyDatetime>,
+    pub page: Option<i64>,
+    pub size: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Identifiable, Associations, Clone)]
+#[table_name = "device_event"]
+#[belongs_to(Device)]
+pub struct DeviceEvent {
+    pub id: i32,
+    pub device_id: i32,
+    pub kind: DeviceEventKind,
+    pub happen_at: NaiveDateTime,
+    pub duration: i32,
+    pub create_at: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "device_event"]
+pub struct DeviceEventInsert {
+    pub device_id: i32,
+    pub kind: DeviceEventKind,
+    pub happen_at: NaiveDateTime,
+    pub duration: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromForm, Default)]
+pub struct DeviceEventQuery {
+    pub device_id: Option<i32>,
+    pub kind: Option<DeviceEventKind>,
+    pub happen_at_begin: Option<MyDatetime>,
+    pub happen_at_end: Option<MyDatetime>,
+    pub page: Option<i64>,
+    pub size: Option<i64>,
+}
+
+//  ===================================================================================
+
+#[derive(Debug, Deserialize)]
+pub struct DeviceCreate {
+    pub device_info_id: i32,
+    pub unicode: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WorkOrderCreate {
+    pub device_id: i32,
+    pub subsystem_id: Option<i32>,
+    pub component_id: Option<i32>,
+    pub technician: String,
+    pub notes: Option<String>,
+    pub planned_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WorkOrderEvent {
+    pub at: Option<NaiveDateTime>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FaultReport {
+    pub device_id: i32,
+    pub subsystem_id: Option<i32>,
+    pub component_id: Option<i32>,
+    pub failure_code: String,
+    pub description: Option<String>,
+    pub reported_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FaultClose {
+    pub repaired_at: Option<NaiveDateTime>,
+    pub resolution: String,
+    #[serde(default)]
+    pub restore_device: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FaultCloseResult {
+    pub device_id: i32,
+    pub open_tickets: i64,
+    pub can_restore_device: bool,
+    pub device_restored: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StockMovementRequest {
+    pub component_info_id: i32,
+    pub kind: StockMovementKind,
+    pub from_location_id: Option<i32>,
+    pub to_location_id: Option<i32>,
+    pub quantity: i32,
+    pub note: Option<String>,
+    pub at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LowStock {
+    pub component_info: ComponentInfo,
+    pub quantity: i64,
+    pub reorder_point: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StatusChange {
+    pub status: DeviceStatus,
+    pub reason: StatusReason,
+    pub description: Option<String>,
+    pub at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EventTime {
+    pub at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ComponentQuantity {
+    pub quantity: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub total: i64,
+    pub list: Vec<T>,
+}
+
+impl<T> From<(Vec<T>, i64)> for Page<T> {
+    fn from((list, total): (Vec<T>, i64)) -> Self {
+        Page { total, list }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SubsystemTree {
+    pub subsystem: Subsystem,
+    pub components: Vec<Component>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeviceTree {
+    pub device: Device,
+    pub subsystems: Vec<SubsystemTree>,
+}
+
+impl From<(Device, Vec<(Subsystem, Vec<Component>)>)> for DeviceTree {
+    fn from((device, subs): (Device, Vec<(Subsystem, Vec<Component>)>)) -> Self {
+        DeviceTree {
+            device,
+            subsystems: subs.into_iter().map(|(subsystem, components)| SubsystemTree { subsystem, components }).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TemplateComponent {
+    pub component_info: ComponentInfo,
+    pub quantity: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TemplateSubsystem {
+    pub subsystem_info: SubsystemInfo,
+    pub components: Vec<TemplateComponent>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TemplateTree {
+    pub device_info: DeviceInfo,
+    pub subsystems: Vec<TemplateSubsystem>,
+}
+
+impl From<(DeviceInfo, Vec<(SubsystemInfo, Vec<(ComponentInfo, i32)>)>)> for TemplateTree {
+    fn from((device_info, subinfos): (DeviceInfo, Vec<(SubsystemInfo, Vec<(ComponentInfo, i32)>)>)) -> Self {
+        TemplateTree {
+            device_info,
+            subsystems: subinfos
+                .into_iter()
+                .map(|(subsystem_info, cominfos)| TemplateSubsystem {
+                    subsystem_info,
+                    components: cominfos
+                        .into_iter()
+                        .map(|(component_info, quantity)| TemplateComponent { component_info, quantity })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SubsystemDetail {
+    pub device: Device,
+    pub subsystem: Subsystem,
+    pub components: Vec<Component>,
+}
+
+impl From<(Device, Subsystem, Vec<Component>)> for SubsystemDetail {
+    fn from((device, subsystem, components): (Device, Subsystem, Vec<Component>)) -> Self {
+        SubsystemDetail { device, subsystem, components }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SubsystemOfDevice {
+    pub device: Device,
+    pub subsystem: Subsystem,
+}
+
+impl From<(Device, Subsystem)> for SubsystemOfDevice {
+    fn from((device, subsystem): (Device, Subsystem)) -> Self {
+        SubsystemOfDevice { device, subsystem }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ComponentDetail {
+    pub device: Device,
+    pub subsystem: Subsystem,
+    pub component: Component,
+}
+
+impl From<(Device, Subsystem, Component)> for ComponentDetail {
+    fn from((device, subsystem, component): (Device, Subsystem, Component)) -> Self {
+        ComponentDetail { device, subsystem, component }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct IntervalDrift {
+    pub template: i32,
+    pub actual: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ComponentDrift {
+    pub component_info_id: Option<i32>,
+    pub name: String,
+    pub model: String,
+    pub template_quantity: i32,
+    pub actual_quantity: i32,
+    pub maintain_interval: Option<IntervalDrift>,
+    pub component_ids: Vec<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SubsystemDrift {
+    pub subsystem_info_id: Option<i32>,
+    pub subsystem_id: Option<i32>,
+    pub name: String,
+    pub maintain_interval: Option<IntervalDrift>,
+    pub components: Vec<ComponentDrift>,
+}
+
+// A subsystem or component the template no longer has, kept because work orders or fault tickets point at it.
+#[derive(Debug, Serialize)]
+pub struct DriftConflict {
+    pub subsystem_id: i32,
+    pub component_id: Option<i32>,
+    pub work_orders: i64,
+    pub fault_tickets: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeviceDrift {
+    pub device_id: i32,
+    pub device_info_id: i32,
+    pub maintain_interval: Option<IntervalDrift>,
+    pub subsystems: Vec<SubsystemDrift>,
+    pub conflicts: Vec<DriftConflict>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MaintainPlan {
+    pub target: MaintainTarget,
+    pub device_id: i32,
+    pub device_name: String,
+    pub subsystem_id: Option<i32>,
+    pub component_id: Option<i32>,
+    pub name: String,
+    pub maintain_interval: i32,
+    pub last_maintain_at: Option<NaiveDateTime>,
+    pub runtime_since_maintain: i32,
+    pub remaining: i32,
+    pub overdue: bool,
+}
+
+#[derive(Debug, Default, FromForm)]
+pub struct MaintainQuery {
+    pub device_name: Option<String>,
+    pub device_model: Option<String>,
+    pub device_status: Option<DeviceStatus>,
+    pub target: Option<MaintainTarget>,
+    pub due_within: Option<i32>,
+    pub overdue: Option<bool>,
+    pub page: Option<i64>,
+    pub size: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BomRow {
+    pub row: usize,
+    pub device_name: String,
+    pub device_model: String,
+    pub device_maintain_interval: i32,
+    pub subsystem_name: Option<String>,
+    pub subsystem_maintain_interval: i32,
+    pub component_name: Option<String>,
+    pub component_model: Option<String>,
+    pub component_maintain_interval: i32,
+    pub quantity: i32,
+}
+
+#[derive(Debug, FromForm)]
+pub struct ImportOptions {
+    pub format: FileFormat,
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldError {
+    pub field: &'static str,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RowError {
+    pub row: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportCount {
+    pub device_info: usize,
+    pub subsystem_info: usize,
+    pub component_info: usize,
+    pub deviceinfo_subsysteminfo: usize,
+    pub subsysteminfo_componentinfo: usize,
+}
+
+impl ImportCount {
+    pub fn merge(&mut self, other: ImportCount) {
+        self.device_info += other.device_info;
+        self.subsystem_info += other.subsystem_info;
+        self.component_info += other.component_info;
+        self.deviceinfo_subsysteminfo += other.deviceinfo_subsysteminfo;
+        self.subsysteminfo_componentinfo += other.subsysteminfo_componentinfo;
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub rows: usize,
+    pub imported: usize,
+    pub created: ImportCount,
+    pub updated: ImportCount,
+    pub errors: Vec<RowError>,
+}
+
+#[derive(Debug, Default, FromForm)]
+pub struct DemandQuery {
+    pub device_name: Option<String>,
+    pub device_model: Option<String>,
+    pub days: Option<i32>,
+    pub hours: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeviceDemand {
+    pub device_id: i32,
+    pub device_name: String,
+    pub unicode: String,
+    pub horizon_hours: i32,
+    pub quantity: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PartDemand {
+    pub component_info_id: Option<i32>,
+    pub name: String,
+    pub model: String,
+    pub quantity: i32,
+    pub devices: Vec<DeviceDemand>,
+}
+
+#[derive(Debug, Default, FromForm)]
+pub struct ReliabilityQuery {
+    pub begin: Option<MyDatetime>,
+    pub end: Option<MyDatetime>,
+    pub device_name: Option<String>,
+    pub device_model: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReliabilityKpi {
+    pub key: String,
+    pub device_id: Option<i32>,
+    pub runtime: i32,
+    pub failures: i64,
+    pub repaired: i64,
+    pub mtbf: Option<f64>,
+    pub mttr: Option<f64>,
+    pub availability: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReliabilityReport {
+    pub by_device: Vec<ReliabilityKpi>,
+    pub by_template: Vec<ReliabilityKpi>,
+    pub by_component_model: Vec<ReliabilityKpi>,
+}
@@ -0,0 +1,171 @@
+use super::dao;
+use super::maintenance;
+use super::model::*;
+use chrono::{Duration, NaiveDateTime};
+use csv;
+use std::collections::BTreeMap;
+use std::error::Error;
+
+#[derive(Default)]
+struct Tally {
+    device_id: Option<i32>,
+    runtime: i32,
+    failures: i64,
+    repaired: i64,
+    repair_hours: f64,
+}
+
+impl Tally {
+    fn into_kpi(self, key: String) -> ReliabilityKpi {
+        let mtbf = if self.failures > 0 { Some(self.runtime as f64 / self.failures as f64) } else { None };
+        let mttr = if self.repaired > 0 { Some(self.repair_hours / self.repaired as f64) } else { None };
+        let availability = match (mtbf, mttr) {
+            (Some(b), Some(r)) if b + r > 0.0 => Some(b / (b + r)),
+            _ if self.failures == 0 => Some(1.0),
+            _ => None,
+        };
+        ReliabilityKpi {
+            key,
+            device_id: self.device_id,
+            runtime: self.runtime,
+            failures: self.failures,
+            repaired: self.repaired,
+            mtbf,
+            mttr,
+            availability,
+        }
+    }
+
+    fn add_ticket(&mut self, ticket: &FaultTicket) {
+        self.failures += 1;
+        if let Some(repaired_at) = ticket.repaired_at {
+            self.repaired += 1;
+            self.repair_hours += (repaired_at - ticket.reported_at).num_minutes() as f64 / 60.0;
+        }
+    }
+}
+
+// Whole hours of [from, to) that fall inside [begin, end).
+fn overlap_hours(from: NaiveDateTime, to: NaiveDateTime, begin: Option<NaiveDateTime>, end: NaiveDateTime) -> i32 {
+    let from = begin.map_or(from, |b| from.max(b));
+    let to = to.min(end);
+    if to > from {
+        maintenance::run_hours(from, to)
+    } else {
+        0
+    }
+}
+
+// Hours of every run ending in a Stop event, clipped to [begin, end) and summed per device.
+fn stop_hours(stops: &[DeviceEvent], begin: Option<NaiveDateTime>, end: NaiveDateTime) -> BTreeMap<i32, i32> {
+    let mut hours: BTreeMap<i32, i32> = BTreeMap::new();
+    for ev in stops.iter().filter(|e| e.kind == DeviceEventKind::Stop) {
+        let start = ev.happen_at - Duration::hours(ev.duration as i64);
+        *hours.entry(ev.device_id).or_insert(0) += overlap_hours(start, ev.happen_at, begin, end);
+    }
+    hours
+}
+
+// MTBF is run hours per failure, MTTR is hours from report to repair, availability is MTBF / (MTBF + MTTR).
+// Every component shares the run hours of its device. Devices are added a page at a time, the fault tickets once all of them are in.
+pub struct Reliability {
+    range: Option<(Option<NaiveDateTime>, NaiveDateTime)>,
+    now: NaiveDateTime,
+    stop_hours: BTreeMap<i32, i32>,
+    by_device: BTreeMap<String, Tally>,
+    by_template: BTreeMap<String, Tally>,
+    by_model: BTreeMap<String, Tally>,
+    device_keys: BTreeMap<i32, (String, String)>,
+    component_models: BTreeMap<i32, String>,
+}
+
+impl Reliability {
+    pub fn new(stops: &[DeviceEvent], begin: Option<NaiveDateTime>, end: Option<NaiveDateTime>, now: NaiveDateTime) -> Self {
+        let range = if begin.is_none() && end.is_none() { None } else { Some((begin, end.map_or(now, |e| e.min(now)))) };
+        Reliability {
+            range,
+            now,
+            stop_hours: range.map(|(begin, end)| stop_hours(stops, begin, end)).unwrap_or_default(),
+            by_device: BTreeMap::new(),
+            by_template: BTreeMap::new(),
+            by_model: BTreeMap::new(),
+            device_keys: BTreeMap::new(),
+            component_models: BTreeMap::new(),
+        }
+    }
+
+    // Run hours inside [begin, end), a device still running adds its open stretch; without a range it is the whole run time of the device.
+    fn runtime(&self, dev: &Device) -> i32 {
+        match self.range {
+            None => maintenance::runtime(dev, self.now),
+            Some((begin, end)) => {
+                let stopped = self.stop_hours.get(&dev.id).cloned().unwrap_or(0);
+                match (dev.status, dev.last_start_at) {
+                    (DeviceStatus::Running, Some(start)) => stopped + overlap_hours(start, self.now, begin, end),
+                    _ => stopped,
+                }
+            }
+        }
+    }
+
+    pub fn add_devices(&mut self, devs: &[(Device, Vec<(Subsystem, Vec<Component>)>)]) {
+        for (dev, subs) in devs {
+            let runtime = self.runtime(dev);
+            let device_key = format!("{} ({})", dev.name, dev.unicode);
+            let template_key = format!("{} {}", dev.name, dev.model);
+            let t = self.by_device.entry(device_key.clone()).or_default();
+            t.device_id = Some(dev.id);
+            t.runtime += runtime;
+            self.by_template.entry(template_key.clone()).or_default().runtime += runtime;
+            for com in subs.iter().flat_map(|(_, coms)| coms) {
+                self.by_model.entry(com.model.clone()).or_default().runtime += runtime;
+                self.component_models.insert(com.id, com.model.clone());
+            }
+            self.device_keys.insert(dev.id, (device_key, template_key));
+        }
+    }
+
+    pub fn finish(mut self, tickets: &[FaultTicket]) -> ReliabilityReport {
+        for ticket in tickets {
+            if let Some((device_key, template_key)) = self.device_keys.get(&ticket.device_id) {
+                self.by_device.entry(device_key.clone()).or_default().add_ticket(ticket);
+                self.by_template.entry(template_key.clone()).or_default().add_ticket(ticket);
+            }
+            if let Some(model) = ticket.component_id.and_then(|id| self.component_models.get(&id)) {
+                self.by_model.entry(model.clone()).or_default().add_ticket(ticket);
+            }
+        }
+        ReliabilityReport {
+            by_device: self.by_device.into_iter().map(|(k, t)| t.into_kpi(k)).collect(),
+            by_template: self.by_template.into_iter().map(|(k, t)| t.into_kpi(k)).collect(),
+            by_component_model: self.by_model.into_iter().map(|(k, t)| t.into_kpi(k)).collect(),
+        }
+    }
+}
+
+// One row per part and contributing device, the part columns are repeated so the sheet can be filtered and summed directly.
+pub fn demand_csv(demands: &[PartDemand]) -> dao::Result<String> {
+    write_demand_csv(demands).map_err(|e| dao::Error::Internal(format!("{}", e)))
+}
+
+fn write_demand_csv(demands: &[PartDemand]) -> Result<String, Box<dyn Error>> {
+    let mut w = csv::Writer::from_writer(Vec::new());
+    w.write_record(&["component_info_id", "name", "model", "total_quantity", "device_id", "device_name", "unicode", "horizon_hours", "quantity"])?;
+    for part in demands {
+        let component_info_id = part.component_info_id.map(|v| v.to_string()).unwrap_or_default();
+        for dev in &part.devices {
+            w.write_record(&[
+                component_info_id.clone(),
+                part.name.clone(),
+                part.model.clone(),
+                part.quantity.to_string(),
+                dev.device_id.to_string(),
+                dev.device_name.clone(),
+                dev.unicode.clone(),
+                dev.horizon_hours.to_string(),
+                dev.quantity.to_string(),
+            ])?;
+        }
+    }
+    Ok(String::from_utf8(w.into_inner()?)?)
+}
@@ -1,49 +1,70 @@
+use super::dao;
+use super::model::FieldError;
 use rocket::response::Responder;
 use rocket::Request;
 use rocket::response;
 use std::io::Cursor;
-use rocket::http::{ Status, Header };
+use rocket::http::{ Status, ContentType };
 use std::fmt;
 use std::fmt::Display;
-use diesel;
 use rocket_contrib::json::Json;
+use serde_json::json;
 
 #[derive(Debug)]
 pub struct Error {
     status: Status,
+    code: &'static str,
     detail: String,
+    fields: Vec<FieldError>,
 }
 
 impl Error {
-    pub fn new(status: Status, detail: String) -> Self {
+    pub fn new(status: Status, code: &'static str, detail: String) -> Self {
         Self {
             status,
+            code,
             detail,
+            fields: Vec::new(),
         }
     }
 }
 
 impl Display for Error {
         fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-            write!(f, "({}, {})", self.status, self.detail)
+            write!(f, "({}, {}, {})", self.status, self.code, self.detail)
         }
 }
 
-impl From<diesel::result::Error> for Error {
-    fn from(e: diesel::result::Error) -> Error {
-        Self::new(Status::new(599, "database error"), format!("{}", e))
+impl From<dao::Error> for Error {
+    fn from(e: dao::Error) -> Error {
+        let status = match e {
+            dao::Error::NotFound(_) => Status::NotFound,
+            dao::Error::Conflict(_) | dao::Error::ForeignKey(_) => Status::Conflict,
+            dao::Error::Validation(_) | dao::Error::Invalid(_) => Status::UnprocessableEntity,
+            dao::Error::Internal(_) => Status::InternalServerError,
+        };
+        let mut err = Self::new(status, e.code(), format!("{}", e));
+        if let dao::Error::Invalid(fields) = e {
+            err.fields = fields;
+        }
+        err
     }
 }
 
 impl<'r> Responder<'r> for Error {
     fn respond_to(self, _: &Request) -> response::Result<'r> {
+        let body = if self.fields.is_empty() {
+            json!({ "code": self.code, "message": self.detail })
+        } else {
+            json!({ "code": self.code, "message": self.detail, "fields": self.fields })
+        };
         response::ResponseBuilder::new(response::Response::new())
         .status(self.status)
-        .sized_body(Cursor::new(self.detail))
-        .header(Header::new("Content-Type", "text/plain;charset=utf-8"))
+        .sized_body(Cursor::new(body.to_string()))
+        .header(ContentType::JSON)
         .ok()
     }
 }
 
 
-pub type Result<T> = std::result::Result<Json<T>, Error>;
\ No newline at end of file
+pub type Result<T> = std::result::Result<Json<T>, Error>;
@@ -2,11 +2,15 @@ table! {
     component (id) {
         id -> Integer,
         subsystem_id -> Integer,
+        component_info_id -> Nullable<Integer>,
         name -> Varchar,
         model -> Varchar,
         maintain_interval -> Integer,
+        position -> Integer,
         create_at -> Timestamp,
         update_at -> Timestamp,
+        last_maintain_at -> Nullable<Timestamp>,
+        last_maintain_duration -> Integer,
     }
 }
 
@@ -24,16 +28,30 @@ table! {
 table! {
     device (id) {
         id -> Integer,
+        device_info_id -> Nullable<Integer>,
         name -> Varchar,
         model -> Varchar,
         maintain_interval -> Integer,
         unicode -> Varchar,
-        last_start_at -> Nullable<Datetime>,
-        last_stop_at -> Nullable<Datetime>,
+        last_start_at -> Nullable<Timestamp>,
+        last_stop_at -> Nullable<Timestamp>,
         total_duration -> Integer,
         status -> Varchar,
         create_at -> Timestamp,
         update_at -> Timestamp,
+        last_maintain_at -> Nullable<Timestamp>,
+        last_maintain_duration -> Integer,
+    }
+}
+
+table! {
+    device_event (id) {
+        id -> Integer,
+        device_id -> Integer,
+        kind -> Varchar,
+        happen_at -> Timestamp,
+        duration -> Integer,
+        create_at -> Timestamp,
     }
 }
 
@@ -48,6 +66,19 @@ table! {
     }
 }
 
+table! {
+    device_status_history (id) {
+        id -> Integer,
+        device_id -> Integer,
+        from_status -> Varchar,
+        to_status -> Varchar,
+        reason -> Varchar,
+        description -> Nullable<Text>,
+        happen_at -> Timestamp,
+        create_at -> Timestamp,
+    }
+}
+
 table! {
     deviceinfo_subsysteminfo (id) {
         id -> Integer,
@@ -56,14 +87,73 @@ table! {
     }
 }
 
+table! {
+    fault_ticket (id) {
+        id -> Integer,
+        device_id -> Integer,
+        subsystem_id -> Integer,
+        component_id -> Nullable<Integer>,
+        failure_code -> Varchar,
+        description -> Nullable<Text>,
+        reported_at -> Timestamp,
+        repaired_at -> Nullable<Timestamp>,
+        resolution -> Nullable<Text>,
+        create_at -> Timestamp,
+        update_at -> Timestamp,
+    }
+}
+
+table! {
+    reorder_point (component_info_id) {
+        component_info_id -> Integer,
+        quantity -> Integer,
+    }
+}
+
+table! {
+    stock (id) {
+        id -> Integer,
+        component_info_id -> Integer,
+        location_id -> Integer,
+        quantity -> Integer,
+        update_at -> Timestamp,
+    }
+}
+
+table! {
+    stock_movement (id) {
+        id -> Integer,
+        component_info_id -> Integer,
+        kind -> Varchar,
+        from_location_id -> Nullable<Integer>,
+        to_location_id -> Nullable<Integer>,
+        quantity -> Integer,
+        note -> Nullable<Text>,
+        happen_at -> Timestamp,
+        create_at -> Timestamp,
+    }
+}
+
+table! {
+    storage_location (id) {
+        id -> Integer,
+        name -> Varchar,
+        create_at -> Timestamp,
+        update_at -> Timestamp,
+    }
+}
+
 table! {
     subsystem (id) {
         id -> Integer,
         device_id -> Integer,
+        subsystem_info_id -> Nullable<Integer>,
         name -> Varchar,
         maintain_interval -> Integer,
         create_at -> Timestamp,
         update_at -> Timestamp,
+        last_maintain_at -> Nullable<Timestamp>,
+        last_maintain_duration -> Integer,
     }
 }
 
@@ -87,21 +177,56 @@ table! {
     }
 }
 
+table! {
+    work_order (id) {
+        id -> Integer,
+        device_id -> Integer,
+        subsystem_id -> Nullable<Integer>,
+        component_id -> Nullable<Integer>,
+        target -> Varchar,
+        status -> Varchar,
+        technician -> Varchar,
+        notes -> Nullable<Text>,
+        planned_at -> Nullable<Timestamp>,
+        started_at -> Nullable<Timestamp>,
+        finished_at -> Nullable<Timestamp>,
+        create_at -> Timestamp,
+        update_at -> Timestamp,
+    }
+}
+
 joinable!(component -> subsystem (subsystem_id));
+joinable!(device_event -> device (device_id));
+joinable!(device_status_history -> device (device_id));
 joinable!(deviceinfo_subsysteminfo -> device_info (device_info_id));
+joinable!(fault_ticket -> device (device_id));
+joinable!(fault_ticket -> subsystem (subsystem_id));
 joinable!(deviceinfo_subsysteminfo -> subsystem_info (subsystem_info_id));
+joinable!(reorder_point -> component_info (component_info_id));
+joinable!(stock -> component_info (component_info_id));
+joinable!(stock -> storage_location (location_id));
+joinable!(stock_movement -> component_info (component_info_id));
 joinable!(subsystem -> device (device_id));
 joinable!(subsysteminfo_componentinfo -> component_info (component_info_id));
 joinable!(subsysteminfo_componentinfo -> device_info (device_info_id));
 joinable!(subsysteminfo_componentinfo -> subsystem_info (subsystem_info_id));
+joinable!(work_order -> device (device_id));
 
 allow_tables_to_appear_in_same_query!(
     component,
     component_info,
     device,
+    device_event,
     device_info,
+    device_status_history,
     deviceinfo_subsysteminfo,
+    fault_ticket,
+    reorder_point,
+    stock,
+    stock_movement,
+    storage_location,
     subsystem,
     subsystem_info,
     subsysteminfo_componentinfo,
+    work_order,
 );
@@ -1,25 +1,96 @@
 use super::dao::*;
+use super::drift;
+use super::import;
+use super::maintenance;
 use super::model::*;
+use super::report;
+use super::status;
+use super::validate::{Checker, Validate};
+use chrono::{Local, NaiveDateTime};
 use rocket::request::{FromRequest, Outcome, Request};
-use std::error::Error;
-
-type Result<T> = std::result::Result<T, Box<dyn Error>>;
 
 pub trait Server {
     fn add_component_info(&self, name: String, model: String, interval: i32) -> Result<i32>;
     fn delete_component_info(&self, compinfo_id: i32) -> Result<usize>;
+    fn update_component_info(&self, cominfo_id: i32, upd: ComponentInfoUpdate) -> Result<usize>;
+    fn get_component_info(&self, cominfo_id: i32) -> Result<ComponentInfo>;
+    fn query_component_info(&self, query: &ComponentInfoQuery) -> Result<(Vec<ComponentInfo>, i64)>;
+    fn query_component_info_by_subsystem_info(&self, subinfo_id: i32, query: ComponentInfoQuery) -> Result<(Vec<ComponentInfo>, i64)>;
     fn add_subsystem_info(&self, name: String, interval: i32) -> Result<i32>;
     fn delete_subsystem_info(&self, subinfo_id: i32) -> Result<usize>;
+    fn update_subsystem_info(&self, subinfo_id: i32, upd: SubsystemInfoUpdate) -> Result<usize>;
+    fn get_subsystem_info(&self, subinfo_id: i32) -> Result<SubsystemInfo>;
+    fn query_subsystem_info(&self, query: &SubsystemInfoQuery) -> Result<(Vec<SubsystemInfo>, i64)>;
+    fn query_subsystem_info_by_device_info(&self, devinfo_id: i32, query: SubsystemInfoQuery) -> Result<(Vec<SubsystemInfo>, i64)>;
+    fn query_subsystem_info_by_component_info(&self, cominfo_id: i32, query: SubsystemInfoQuery) -> Result<(Vec<SubsystemInfo>, i64)>;
     fn add_device_info(&self, name: String, model: String, interval: i32) -> Result<i32>;
     fn delete_device_info(&self, devinfo_id: i32) -> Result<usize>;
+    fn update_device_info(&self, devinfo_id: i32, upd: DeviceInfoUpdate) -> Result<usize>;
+    fn get_device_info(&self, devinfo_id: i32) -> Result<DeviceInfo>;
+    fn query_device_info(&self, query: &DeviceInfoQuery) -> Result<(Vec<DeviceInfo>, i64)>;
+    fn query_device_info_by_subsystem_info(&self, subinfo_id: i32, query: DeviceInfoQuery) -> Result<(Vec<DeviceInfo>, i64)>;
     fn attach_subsystem_info(&self, devinfo_id: i32, subinfo_id: i32) -> Result<usize>;
     fn remove_subsystem_info(&self, devinfo_id: i32, subinfo_id: i32) -> Result<usize>;
     fn attach_component_info(&self, devinfo_id: i32, subinfo_id: i32, cominfo_id: i32, quantity: i32) -> Result<usize>;
     fn remove_component_info(&self, devinfo_id: i32, subinfo_id: i32, cominfo_id: i32) -> Result<usize>;
-    fn create_device(&self, devinfo_id: i32, unicode: String) -> Result<()>;
+    fn set_component_info_quantity(&self, devinfo_id: i32, subinfo_id: i32, cominfo_id: i32, quantity: i32) -> Result<usize>;
+    fn create_device(&self, devinfo_id: i32, unicode: String) -> Result<i32>;
     fn delete_device(&self, dev_id: i32) -> Result<usize>;
+    fn update_device(&self, dev_id: i32, upd: DeviceUpdate) -> Result<usize>;
+    fn get_device(&self, dev_id: i32) -> Result<DeviceTree>;
+    fn query_device(&self, query: &DeviceQuery) -> Result<(Vec<DeviceTree>, i64)>;
+    fn query_device_by_device_info(&self, devinfo_id: i32, query: DeviceQuery) -> Result<(Vec<DeviceTree>, i64)>;
+    fn device_drift(&self, dev_id: i32) -> Result<DeviceDrift>;
+    fn start_device(&self, dev_id: i32, at: NaiveDateTime) -> Result<i32>;
+    fn stop_device(&self, dev_id: i32, at: NaiveDateTime) -> Result<i32>;
+    fn change_device_status(&self, dev_id: i32, to: DeviceStatus, reason: StatusReason, description: Option<String>, at: NaiveDateTime) -> Result<i32>;
+    fn query_status_history(&self, dev_id: i32, query: StatusHistoryQuery) -> Result<(Vec<StatusHistory>, i64)>;
+    fn query_device_event(&self, dev_id: i32, query: DeviceEventQuery) -> Result<(Vec<DeviceEvent>, i64)>;
+    fn preview_template_changes(&self, devinfo_id: i32) -> Result<Vec<DeviceDrift>>;
+    fn apply_template_changes(&self, devinfo_id: i32) -> Result<Vec<DeviceDrift>>;
+    fn maintain_plan(&self, dev_id: i32) -> Result<Vec<MaintainPlan>>;
+    fn query_maintain_plan(&self, query: &MaintainQuery) -> Result<(Vec<MaintainPlan>, i64)>;
+    fn add_subsystem(&self, sub: SubsystemInsert) -> Result<i32>;
+    fn delete_subsystem(&self, sub_id: i32) -> Result<usize>;
+    fn update_subsystem(&self, sub_id: i32, upd: SubsystemUpdate) -> Result<usize>;
+    fn get_subsystem(&self, sub_id: i32) -> Result<SubsystemDetail>;
+    fn query_subsystem(&self, query: &SubsystemQuery) -> Result<(Vec<SubsystemOfDevice>, i64)>;
+    fn add_component(&self, com: ComponentInsert) -> Result<i32>;
+    fn delete_component(&self, com_id: i32) -> Result<usize>;
+    fn update_component(&self, com_id: i32, upd: ComponentUpdate) -> Result<usize>;
+    fn get_component(&self, com_id: i32) -> Result<ComponentDetail>;
+    fn query_component(&self, query: &ComponentQuery) -> Result<(Vec<ComponentDetail>, i64)>;
+    fn create_work_order(&self, order: WorkOrderCreate) -> Result<i32>;
+    fn delete_work_order(&self, order_id: i32) -> Result<usize>;
+    fn update_work_order(&self, order_id: i32, upd: WorkOrderUpdate) -> Result<usize>;
+    fn get_work_order(&self, order_id: i32) -> Result<WorkOrder>;
+    fn query_work_order(&self, query: &WorkOrderQuery) -> Result<(Vec<WorkOrder>, i64)>;
+    fn start_work_order(&self, order_id: i32, at: NaiveDateTime) -> Result<usize>;
+    fn complete_work_order(&self, order_id: i32, at: NaiveDateTime, notes: Option<String>) -> Result<usize>;
+    fn cancel_work_order(&self, order_id: i32, notes: Option<String>) -> Result<usize>;
+    fn report_fault(&self, report: FaultReport) -> Result<i32>;
+    fn delete_fault(&self, ticket_id: i32) -> Result<usize>;
+    fn update_fault(&self, ticket_id: i32, upd: FaultTicketUpdate) -> Result<usize>;
+    fn get_fault(&self, ticket_id: i32) -> Result<FaultTicket>;
+    fn query_fault(&self, query: &FaultTicketQuery) -> Result<(Vec<FaultTicket>, i64)>;
+    fn close_fault(&self, ticket_id: i32, close: FaultClose) -> Result<FaultCloseResult>;
+    fn reliability_report(&self, query: &ReliabilityQuery) -> Result<ReliabilityReport>;
+    fn spare_part_demand(&self, query: &DemandQuery) -> Result<Vec<PartDemand>>;
+    fn import_bom(&self, format: FileFormat, data: &[u8], dry_run: bool) -> Result<ImportReport>;
+    fn spare_part_demand_csv(&self, query: &DemandQuery) -> Result<String>;
+    fn add_storage_location(&self, name: String) -> Result<i32>;
+    fn delete_storage_location(&self, location_id: i32) -> Result<usize>;
+    fn storage_locations(&self) -> Result<Vec<StorageLocation>>;
+    fn move_stock(&self, mov: StockMovementRequest) -> Result<i32>;
+    fn query_stock(&self, query: &StockQuery) -> Result<(Vec<Stock>, i64)>;
+    fn query_stock_movement(&self, query: &StockMovementQuery) -> Result<(Vec<StockMovement>, i64)>;
+    fn set_reorder_point(&self, cominfo_id: i32, quantity: i32) -> Result<usize>;
+    fn low_stock(&self) -> Result<Vec<LowStock>>;
 }
 
+// Devices loaded per storer query when a report walks the whole fleet.
+const DEVICE_PAGE: i64 = 200;
+
 pub struct Service {
     pub devinfo: Box<dyn DeviceInfoStorer>,
     pub subinfo: Box<dyn SubsystemInfoStorer>,
@@ -28,42 +99,426 @@ pub struct Service {
     pub sub: Box<dyn SubsystemStorer>,
     pub com: Box<dyn ComponentStorer>,
     pub rel: Box<dyn RelationStorer>,
+    pub wo: Box<dyn WorkOrderStorer>,
+    pub event: Box<dyn DeviceEventStorer>,
+    pub history: Box<dyn StatusHistoryStorer>,
+    pub fault: Box<dyn FaultStorer>,
+    pub inv: Box<dyn InventoryStorer>,
+    pub tx: Box<dyn Transactor>,
 }
 
 impl Service {
-    pub fn new(
-        devinfo: Box<dyn DeviceInfoStorer>,
-        subinfo: Box<dyn SubsystemInfoStorer>,
-        cominfo: Box<dyn ComponentInfoStorer>,
-        dev: Box<dyn DeviceStorer>,
-        sub: Box<dyn SubsystemStorer>,
-        com: Box<dyn ComponentStorer>,
-        rel: Box<dyn RelationStorer>,
-    ) -> Self {
-        Service {
-            devinfo,
-            subinfo,
-            cominfo,
-            dev,
-            sub,
-            com,
-            rel,
+    // Checks that the subsystem and component belong to the device and fills in the subsystem of a component.
+    fn resolve_target(&self, device_id: i32, subsystem_id: Option<i32>, component_id: Option<i32>) -> Result<(MaintainTarget, Option<i32>)> {
+        match (subsystem_id, component_id) {
+            (_, Some(com_id)) => {
+                let (dev, sub, _) = self.com.get(com_id)?;
+                if dev.id != device_id || subsystem_id.map_or(false, |id| id != sub.id) {
+                    return Err(Error::Validation("component does not belong to the given device or subsystem".to_owned()));
+                }
+                Ok((MaintainTarget::Component, Some(sub.id)))
+            }
+            (Some(sub_id), None) => {
+                let (dev, _, _) = self.sub.get(sub_id)?;
+                if dev.id != device_id {
+                    return Err(Error::Validation("subsystem does not belong to the given device".to_owned()));
+                }
+                Ok((MaintainTarget::Subsystem, Some(sub_id)))
+            }
+            (None, None) => {
+                self.dev.get(device_id)?;
+                Ok((MaintainTarget::Device, None))
+            }
+        }
+    }
+
+    fn component_inserts(subid: i32, cominfo: &ComponentInfo, positions: std::ops::RangeInclusive<i32>) -> Vec<ComponentInsert> {
+        positions
+            .map(|position| ComponentInsert {
+                subsystem_id: subid,
+                component_info_id: Some(cominfo.id),
+                name: cominfo.name.clone(),
+                model: cominfo.model.clone(),
+                maintain_interval: cominfo.maintain_interval,
+                position: position,
+            })
+            .collect()
+    }
+
+    fn insert_components(&self, subid: i32, cominfo: &ComponentInfo, positions: std::ops::RangeInclusive<i32>, runtime: i32) -> Result<()> {
+        let ids = self.com.bulk_insert_ids(&Self::component_inserts(subid, cominfo, positions))?;
+        for id in ids {
+            self.start_component_clock(id, runtime)?;
+        }
+        Ok(())
+    }
+
+    // Parts fitted to a device that has already run start their maintenance clock at the device's current run hours.
+    fn start_subsystem_clock(&self, subid: i32, runtime: i32) -> Result<()> {
+        if runtime > 0 {
+            self.sub.udpate(
+                subid,
+                SubsystemUpdate {
+                    last_maintain_duration: Some(runtime),
+                    ..Default::default()
+                },
+            )?;
+        }
+        Ok(())
+    }
+
+    fn start_component_clock(&self, comid: i32, runtime: i32) -> Result<()> {
+        if runtime > 0 {
+            self.com.update(
+                comid,
+                ComponentUpdate {
+                    last_maintain_duration: Some(runtime),
+                    ..Default::default()
+                },
+            )?;
+        }
+        Ok(())
+    }
+
+    // Counts the work orders and fault tickets that would cascade away with a subsystem or component.
+    fn history(&self, subsystem_id: i32, component_id: Option<i32>) -> Result<(i64, i64)> {
+        let (_, work_orders) = self.wo.query(&WorkOrderQuery {
+            subsystem_id: Some(subsystem_id),
+            component_id,
+            page: Some(1),
+            size: Some(1),
+            ..Default::default()
+        })?;
+        let (_, fault_tickets) = self.fault.query(&FaultTicketQuery {
+            subsystem_id: Some(subsystem_id),
+            component_id,
+            page: Some(1),
+            size: Some(1),
+            ..Default::default()
+        })?;
+        Ok((work_orders, fault_tickets))
+    }
+
+    fn conflict(&self, subsystem_id: i32, component_id: Option<i32>) -> Result<Option<DriftConflict>> {
+        let (work_orders, fault_tickets) = self.history(subsystem_id, component_id)?;
+        Ok(if work_orders + fault_tickets > 0 {
+            Some(DriftConflict {
+                subsystem_id,
+                component_id,
+                work_orders,
+                fault_tickets,
+            })
+        } else {
+            None
+        })
+    }
+
+    // Walks the devices matching the query a page at a time, so fleet-wide reports never hold every device tree at once.
+    fn each_device_page<F>(&self, mut query: DeviceQuery, mut f: F) -> Result<()>
+    where
+        F: FnMut(&[(Device, Vec<(Subsystem, Vec<Component>)>)]),
+    {
+        query.size = Some(DEVICE_PAGE);
+        for page in 1.. {
+            query.page = Some(page);
+            let (devs, total) = self.dev.query(&query)?;
+            f(&devs);
+            if devs.is_empty() || page * DEVICE_PAGE >= total {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    // Lists the parts apply_drift would delete that still have history as conflicts, they are kept instead.
+    fn with_conflicts(&self, devinfo: &(DeviceInfo, Vec<(SubsystemInfo, Vec<(ComponentInfo, i32)>)>), mut drift: DeviceDrift) -> Result<DeviceDrift> {
+        let mut conflicts = Vec::new();
+        for subdrift in &drift.subsystems {
+            let template = subdrift.subsystem_info_id.and_then(|id| devinfo.1.iter().find(|(s, _)| s.id == id));
+            match (subdrift.subsystem_id, template) {
+                (Some(subid), None) => {
+                    if subdrift.subsystem_info_id.is_some() {
+                        conflicts.extend(self.conflict(subid, None)?);
+                    }
+                }
+                (Some(subid), Some((_, cominfos))) => {
+                    for comdrift in &subdrift.components {
+                        let doomed: &[i32] = match comdrift.component_info_id.and_then(|id| cominfos.iter().find(|(c, _)| c.id == id)) {
+                            Some(_) => comdrift.component_ids.get(comdrift.template_quantity as usize..).unwrap_or(&[]),
+                            None if comdrift.component_info_id.is_some() => &comdrift.component_ids,
+                            None => &[],
+                        };
+                        for id in doomed {
+                            conflicts.extend(self.conflict(subid, Some(*id))?);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        drift.conflicts = conflicts;
+        Ok(drift)
+    }
+
+    // Brings a derived device in line with its template. Subsystems and components without lineage were added by hand and are left alone,
+    // so are the ones listed as conflicts.
+    fn apply_drift(&self, devinfo: &(DeviceInfo, Vec<(SubsystemInfo, Vec<(ComponentInfo, i32)>)>), drift: &DeviceDrift) -> Result<()> {
+        let runtime = maintenance::runtime(&self.dev.get(drift.device_id)?.0, Local::now().naive_local());
+        let kept = |id: i32| drift.conflicts.iter().any(|c| c.component_id == Some(id));
+        if let Some(v) = drift.maintain_interval.as_ref() {
+            self.dev.update(
+                drift.device_id,
+                DeviceUpdate {
+                    maintain_interval: Some(v.template),
+                    ..Default::default()
+                },
+            )?;
+        }
+        for subdrift in &drift.subsystems {
+            let template = subdrift.subsystem_info_id.and_then(|id| devinfo.1.iter().find(|(s, _)| s.id == id));
+            match (subdrift.subsystem_id, template) {
+                (None, Some((subinfo, cominfos))) => {
+                    let subid = self.sub.insert(SubsystemInsert {
+                        device_id: drift.device_id,
+                        subsystem_info_id: Some(subinfo.id),
+                        name: subinfo.name.clone(),
+                        maintain_interval: subinfo.maintain_interval,
+                    })?;
+                    self.start_subsystem_clock(subid, runtime)?;
+                    for (cominfo, quantity) in cominfos {
+                        self.insert_components(subid, cominfo, 1..=*quantity, runtime)?;
+                    }
+                }
+                (Some(subid), None) => {
+                    if subdrift.subsystem_info_id.is_some() && !drift.conflicts.iter().any(|c| c.subsystem_id == subid && c.component_id.is_none()) {
+                        self.sub.delete(subid)?;
+                    }
+                }
+                (Some(subid), Some((_, cominfos))) => {
+                    if let Some(v) = subdrift.maintain_interval.as_ref() {
+                        self.sub.udpate(
+                            subid,
+                            SubsystemUpdate {
+                                maintain_interval: Some(v.template),
+                                ..Default::default()
+                            },
+                        )?;
+                    }
+                    for comdrift in &subdrift.components {
+                        let cominfo = comdrift.component_info_id.and_then(|id| cominfos.iter().find(|(c, _)| c.id == id));
+                        match cominfo {
+                            Some((cominfo, _)) => {
+                                if comdrift.template_quantity > comdrift.actual_quantity {
+                                    self.insert_components(subid, cominfo, comdrift.actual_quantity + 1..=comdrift.template_quantity, runtime)?;
+                                }
+                                for id in comdrift.component_ids.iter().skip(comdrift.template_quantity as usize) {
+                                    if !kept(*id) {
+                                        self.com.delete(*id)?;
+                                    }
+                                }
+                                if comdrift.maintain_interval.is_some() {
+                                    for id in comdrift.component_ids.iter().take(comdrift.template_quantity as usize) {
+                                        self.com.update(
+                                            *id,
+                                            ComponentUpdate {
+                                                maintain_interval: Some(cominfo.maintain_interval),
+                                                ..Default::default()
+                                            },
+                                        )?;
+                                    }
+                                }
+                            }
+                            None => {
+                                if comdrift.component_info_id.is_some() {
+                                    for id in comdrift.component_ids.iter().filter(|id| !kept(**id)) {
+                                        self.com.delete(*id)?;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                (None, None) => {}
+            }
+        }
+        Ok(())
+    }
+
+    // Creates or updates the catalog entries and links of one BOM row, returns what was created and what was updated.
+    fn upsert_bom_row(&self, row: &BomRow) -> Result<(ImportCount, ImportCount)> {
+        let mut created = ImportCount::default();
+        let mut updated = ImportCount::default();
+        let devinfo_id = match self.devinfo.find(&row.device_name, &row.device_model)? {
+            Some(info) => {
+                if info.maintain_interval != row.device_maintain_interval {
+                    self.devinfo.update(
+                        info.id,
+                        DeviceInfoUpdate {
+                            name: None,
+                            model: None,
+                            maintain_interval: Some(row.device_maintain_interval),
+                        },
+                    )?;
+                    updated.device_info += 1;
+                }
+                info.id
+            }
+            None => {
+                created.device_info += 1;
+                let info = DeviceInfoInsert {
+                    name: row.device_name.clone(),
+                    model: row.device_model.clone(),
+                    maintain_interval: row.device_maintain_interval,
+                };
+                info.validate()?;
+                self.devinfo.insert(info)?
+            }
+        };
+        let subsystem_name = match &row.subsystem_name {
+            Some(v) => v,
+            None => return Ok((created, updated)),
+        };
+        let subinfo_id = match self.subinfo.find(subsystem_name)? {
+            Some(info) => {
+                if info.maintain_interval != row.subsystem_maintain_interval {
+                    self.subinfo.update(
+                        info.id,
+                        SubsystemInfoUpdate {
+                            name: None,
+                            maintain_interval: Some(row.subsystem_maintain_interval),
+                        },
+                    )?;
+                    updated.subsystem_info += 1;
+                }
+                info.id
+            }
+            None => {
+                created.subsystem_info += 1;
+                let info = SubsystemInfoInsert {
+                    name: subsystem_name.clone(),
+                    maintain_interval: row.subsystem_maintain_interval,
+                };
+                info.validate()?;
+                self.subinfo.insert(info)?
+            }
+        };
+        if !self.rel.has_deviceinfo_subsysteminfo(devinfo_id, subinfo_id)? {
+            self.rel.insert_deviceinfo_subsysteminfo(DevinfoSubinfoInsert {
+                device_info_id: devinfo_id,
+                subsystem_info_id: subinfo_id,
+            })?;
+            created.deviceinfo_subsysteminfo += 1;
+        }
+        let (component_name, component_model) = match (&row.component_name, &row.component_model) {
+            (Some(name), Some(model)) => (name, model),
+            _ => return Ok((created, updated)),
+        };
+        let cominfo_id = match self.cominfo.find(component_name, component_model)? {
+            Some(info) => {
+                if info.maintain_interval != row.component_maintain_interval {
+                    self.cominfo.update(
+                        info.id,
+                        ComponentInfoUpdate {
+                            name: None,
+                            model: None,
+                            maintain_interval: Some(row.component_maintain_interval),
+                        },
+                    )?;
+                    updated.component_info += 1;
+                }
+                info.id
+            }
+            None => {
+                created.component_info += 1;
+                let info = ComponentInfoInsert {
+                    name: component_name.clone(),
+                    model: component_model.clone(),
+                    maintain_interval: row.component_maintain_interval,
+                };
+                info.validate()?;
+                self.cominfo.insert(info)?
+            }
+        };
+        let rel = SubinfoCominfoInsert {
+            device_info_id: devinfo_id,
+            subsystem_info_id: subinfo_id,
+            component_info_id: cominfo_id,
+            quantity: row.quantity,
+        };
+        rel.validate()?;
+        match self.rel.subsysteminfo_componentinfo_quantity(devinfo_id, subinfo_id, cominfo_id)? {
+            Some(quantity) if quantity == row.quantity => {}
+            Some(_) => {
+                self.rel.update_subsysteminfo_componentinfo(devinfo_id, subinfo_id, cominfo_id, row.quantity)?;
+                updated.subsysteminfo_componentinfo += 1;
+            }
+            None => {
+                self.rel.insert_subsysteminfo_componentinfo(rel)?;
+                created.subsysteminfo_componentinfo += 1;
+            }
+        }
+        Ok((created, updated))
+    }
+
+    // Runs f inside a single transaction on the shared connection, all storers take part in it.
+    pub fn atomic<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&Service) -> Result<T>,
+    {
+        self.tx.begin()?;
+        match f(self) {
+            Ok(v) => {
+                self.tx.commit()?;
+                Ok(v)
+            }
+            Err(e) => {
+                self.tx.rollback()?;
+                Err(e)
+            }
         }
     }
 }
 
+use super::storer::connection::Backend;
+#[cfg(feature = "mysql")]
 use super::storer::mysqlstorer;
-use std::rc::Rc;
-use diesel::r2d2::{ConnectionManager, Pool};
-use rocket::State;
+#[cfg(feature = "postgres")]
+use super::storer::pgstorer;
+#[cfg(feature = "sqlite")]
+use super::storer::sqlitestorer;
+#[cfg(feature = "mysql")]
 use diesel::MysqlConnection;
+#[cfg(feature = "postgres")]
+use diesel::PgConnection;
+#[cfg(feature = "sqlite")]
+use diesel::SqliteConnection;
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+use diesel::r2d2::{ConnectionManager, PooledConnection};
+use rocket::State;
+use std::rc::Rc;
 
 impl<'a, 'r> FromRequest<'a, 'r> for Service {
     type Error = ();
     fn from_request(req: &'a Request<'r>) -> Outcome<Self, Self::Error> {
-        let pool = req.guard::<State<Pool<ConnectionManager<MysqlConnection>>>>()?;
-        let conn = Rc::new(pool.get().unwrap());
-        let svc = Service {
+        let backend = req.guard::<State<Backend>>()?;
+        let svc = match backend.inner() {
+            #[cfg(feature = "mysql")]
+            Backend::Mysql(pool) => Service::mysql(Rc::new(pool.get().unwrap())),
+            #[cfg(feature = "postgres")]
+            Backend::Postgres(pool) => Service::postgres(Rc::new(pool.get().unwrap())),
+            #[cfg(feature = "sqlite")]
+            Backend::Sqlite(pool) => Service::sqlite(Rc::new(pool.get().unwrap())),
+            #[cfg(test)]
+            Backend::Memory => Service::memory(Memory::local()),
+        };
+        Outcome::Success(svc)
+    }
+}
+
+impl Service {
+    #[cfg(feature = "mysql")]
+    pub fn mysql(conn: Rc<PooledConnection<ConnectionManager<MysqlConnection>>>) -> Self {
+        Service {
             devinfo: Box::new(mysqlstorer::DeviceInfoRepository::new(conn.clone())),
             subinfo: Box::new(mysqlstorer::SubsystemInfoRepository::new(conn.clone())),
             cominfo: Box::new(mysqlstorer::ComponentInfoRepository::new(conn.clone())),
@@ -71,110 +526,1029 @@ impl<'a, 'r> FromRequest<'a, 'r> for Service {
             sub: Box::new(mysqlstorer::SubsystemRepository::new(conn.clone())),
             com: Box::new(mysqlstorer::ComponentRepository::new(conn.clone())),
             rel: Box::new(mysqlstorer::RelationRepository::new(conn.clone())),
-        };
-        Outcome::Success(svc)
+            wo: Box::new(mysqlstorer::WorkOrderRepository::new(conn.clone())),
+            event: Box::new(mysqlstorer::DeviceEventRepository::new(conn.clone())),
+            history: Box::new(mysqlstorer::StatusHistoryRepository::new(conn.clone())),
+            fault: Box::new(mysqlstorer::FaultRepository::new(conn.clone())),
+            inv: Box::new(mysqlstorer::InventoryRepository::new(conn.clone())),
+            tx: Box::new(mysqlstorer::TransactionRepository::new(conn)),
+        }
+    }
+
+    #[cfg(feature = "postgres")]
+    pub fn postgres(conn: Rc<PooledConnection<ConnectionManager<PgConnection>>>) -> Self {
+        Service {
+            devinfo: Box::new(pgstorer::DeviceInfoRepository::new(conn.clone())),
+            subinfo: Box::new(pgstorer::SubsystemInfoRepository::new(conn.clone())),
+            cominfo: Box::new(pgstorer::ComponentInfoRepository::new(conn.clone())),
+            dev: Box::new(pgstorer::DeviceRepository::new(conn.clone())),
+            sub: Box::new(pgstorer::SubsystemRepository::new(conn.clone())),
+            com: Box::new(pgstorer::ComponentRepository::new(conn.clone())),
+            rel: Box::new(pgstorer::RelationRepository::new(conn.clone())),
+            wo: Box::new(pgstorer::WorkOrderRepository::new(conn.clone())),
+            event: Box::new(pgstorer::DeviceEventRepository::new(conn.clone())),
+            history: Box::new(pgstorer::StatusHistoryRepository::new(conn.clone())),
+            fault: Box::new(pgstorer::FaultRepository::new(conn.clone())),
+            inv: Box::new(pgstorer::InventoryRepository::new(conn.clone())),
+            tx: Box::new(pgstorer::TransactionRepository::new(conn)),
+        }
+    }
+
+    #[cfg(feature = "sqlite")]
+    pub fn sqlite(conn: Rc<PooledConnection<ConnectionManager<SqliteConnection>>>) -> Self {
+        Service {
+            devinfo: Box::new(sqlitestorer::DeviceInfoRepository::new(conn.clone())),
+            subinfo: Box::new(sqlitestorer::SubsystemInfoRepository::new(conn.clone())),
+            cominfo: Box::new(sqlitestorer::ComponentInfoRepository::new(conn.clone())),
+            dev: Box::new(sqlitestorer::DeviceRepository::new(conn.clone())),
+            sub: Box::new(sqlitestorer::SubsystemRepository::new(conn.clone())),
+            com: Box::new(sqlitestorer::ComponentRepository::new(conn.clone())),
+            rel: Box::new(sqlitestorer::RelationRepository::new(conn.clone())),
+            wo: Box::new(sqlitestorer::WorkOrderRepository::new(conn.clone())),
+            event: Box::new(sqlitestorer::DeviceEventRepository::new(conn.clone())),
+            history: Box::new(sqlitestorer::StatusHistoryRepository::new(conn.clone())),
+            fault: Box::new(sqlitestorer::FaultRepository::new(conn.clone())),
+            inv: Box::new(sqlitestorer::InventoryRepository::new(conn.clone())),
+            tx: Box::new(sqlitestorer::TransactionRepository::new(conn)),
+        }
+    }
+}
+
+use super::storer::memstorer::{self, Memory};
+use std::cell::RefCell;
+
+impl Service {
+    // A service over an in-memory store, services built from the same store see each other's writes.
+    pub fn memory(db: Rc<RefCell<Memory>>) -> Self {
+        Service {
+            devinfo: Box::new(memstorer::DeviceInfoRepository::new(db.clone())),
+            subinfo: Box::new(memstorer::SubsystemInfoRepository::new(db.clone())),
+            cominfo: Box::new(memstorer::ComponentInfoRepository::new(db.clone())),
+            dev: Box::new(memstorer::DeviceRepository::new(db.clone())),
+            sub: Box::new(memstorer::SubsystemRepository::new(db.clone())),
+            com: Box::new(memstorer::ComponentRepository::new(db.clone())),
+            rel: Box::new(memstorer::RelationRepository::new(db.clone())),
+            wo: Box::new(memstorer::WorkOrderRepository::new(db.clone())),
+            event: Box::new(memstorer::DeviceEventRepository::new(db.clone())),
+            history: Box::new(memstorer::StatusHistoryRepository::new(db.clone())),
+            fault: Box::new(memstorer::FaultRepository::new(db.clone())),
+            inv: Box::new(memstorer::InventoryRepository::new(db.clone())),
+            tx: Box::new(memstorer::TransactionRepository::new(db)),
+        }
     }
 }
 
 impl Server for Service {
     fn add_device_info(&self, name: String, model: String, interval: i32) -> Result<i32> {
-        Ok(self.devinfo.insert(DeviceInfoInsert {
+        let info = DeviceInfoInsert {
             name: name,
             model: model,
             maintain_interval: interval,
-        })?)
+        };
+        info.validate()?;
+        Ok(self.devinfo.insert(info)?)
     }
 
     fn delete_device_info(&self, devinfo_id: i32) -> Result<usize> {
         Ok(self.devinfo.delete(devinfo_id)?)
     }
 
+    fn update_device_info(&self, devinfo_id: i32, upd: DeviceInfoUpdate) -> Result<usize> {
+        upd.validate()?;
+        Ok(self.devinfo.update(devinfo_id, upd)?)
+    }
+
+    fn get_device_info(&self, devinfo_id: i32) -> Result<DeviceInfo> {
+        Ok(self.devinfo.get(devinfo_id)?)
+    }
+
+    fn query_device_info(&self, query: &DeviceInfoQuery) -> Result<(Vec<DeviceInfo>, i64)> {
+        query.validate()?;
+        Ok(self.devinfo.query(query)?)
+    }
+
+    fn query_device_info_by_subsystem_info(&self, subinfo_id: i32, query: DeviceInfoQuery) -> Result<(Vec<DeviceInfo>, i64)> {
+        query.validate()?;
+        Ok(self.devinfo.query_by_subsystem_info(subinfo_id, query)?)
+    }
+
     fn add_subsystem_info(&self, name: String, interval: i32) -> Result<i32> {
-        Ok(self.subinfo.insert(SubsystemInfoInsert {
+        let info = SubsystemInfoInsert {
             name: name,
             maintain_interval: interval,
-        })?)
+        };
+        info.validate()?;
+        Ok(self.subinfo.insert(info)?)
     }
 
     fn delete_subsystem_info(&self, subinfo_id: i32) -> Result<usize> {
         Ok(self.subinfo.delete(subinfo_id)?)
     }
 
+    fn update_subsystem_info(&self, subinfo_id: i32, upd: SubsystemInfoUpdate) -> Result<usize> {
+        upd.validate()?;
+        Ok(self.subinfo.update(subinfo_id, upd)?)
+    }
+
+    fn get_subsystem_info(&self, subinfo_id: i32) -> Result<SubsystemInfo> {
+        Ok(self.subinfo.get(subinfo_id)?)
+    }
+
+    fn query_subsystem_info(&self, query: &SubsystemInfoQuery) -> Result<(Vec<SubsystemInfo>, i64)> {
+        query.validate()?;
+        Ok(self.subinfo.query(query)?)
+    }
+
+    fn query_subsystem_info_by_device_info(&self, devinfo_id: i32, query: SubsystemInfoQuery) -> Result<(Vec<SubsystemInfo>, i64)> {
+        query.validate()?;
+        Ok(self.subinfo.query_by_device_info(devinfo_id, query)?)
+    }
+
+    fn query_subsystem_info_by_component_info(&self, cominfo_id: i32, query: SubsystemInfoQuery) -> Result<(Vec<SubsystemInfo>, i64)> {
+        query.validate()?;
+        Ok(self.subinfo.query_by_component_info(cominfo_id, query)?)
+    }
+
     fn add_component_info(&self, name: String, model: String, interval: i32) -> Result<i32> {
-        Ok(self.cominfo.insert(ComponentInfoInsert {
+        let info = ComponentInfoInsert {
             name: name,
             model: model,
             maintain_interval: interval,
-        })?)
+        };
+        info.validate()?;
+        Ok(self.cominfo.insert(info)?)
     }
 
     fn delete_component_info(&self, cominfo_id: i32) -> Result<usize> {
         Ok(self.cominfo.delete(cominfo_id)?)
     }
 
+    fn update_component_info(&self, cominfo_id: i32, upd: ComponentInfoUpdate) -> Result<usize> {
+        upd.validate()?;
+        Ok(self.cominfo.update(cominfo_id, upd)?)
+    }
+
+    fn get_component_info(&self, cominfo_id: i32) -> Result<ComponentInfo> {
+        Ok(self.cominfo.get(cominfo_id)?)
+    }
+
+    fn query_component_info(&self, query: &ComponentInfoQuery) -> Result<(Vec<ComponentInfo>, i64)> {
+        query.validate()?;
+        Ok(self.cominfo.query(query)?)
+    }
+
+    fn query_component_info_by_subsystem_info(&self, subinfo_id: i32, query: ComponentInfoQuery) -> Result<(Vec<ComponentInfo>, i64)> {
+        query.validate()?;
+        Ok(self.cominfo.query_by_subsystem_info(subinfo_id, query)?)
+    }
+
     fn attach_subsystem_info(&self, devinfo_id: i32, subinfo_id: i32) -> Result<usize> {
         self.devinfo.get(devinfo_id)?;
         self.subinfo.get(subinfo_id)?;
-        Ok(self.rel.insert_deviceinfo_subsysteminfo(DevinfoSubinfoInsert {
+        let rel = DevinfoSubinfoInsert {
             device_info_id: devinfo_id,
             subsystem_info_id: subinfo_id,
-        })?)
+        };
+        rel.validate()?;
+        Ok(self.rel.insert_deviceinfo_subsysteminfo(rel)?)
     }
 
     fn remove_subsystem_info(&self, devinfo_id: i32, subinfo_id: i32) -> Result<usize> {
-        self.rel.delete_deviceinfo_subsysteminfo(devinfo_id, subinfo_id)?;
-        Ok(self.rel.bulk_delete_subsysteminfo_componentinfo(devinfo_id, subinfo_id)?)
+        self.atomic(|svc| {
+            svc.rel.delete_deviceinfo_subsysteminfo(devinfo_id, subinfo_id)?;
+            Ok(svc.rel.bulk_delete_subsysteminfo_componentinfo(devinfo_id, subinfo_id)?)
+        })
     }
 
     fn attach_component_info(&self, devinfo_id: i32, subinfo_id: i32, cominfo_id: i32, quantity: i32) -> Result<usize> {
-        self.subinfo.get(subinfo_id)?;
-        self.cominfo.get(cominfo_id)?;
-        Ok(self.rel.insert_subsysteminfo_componentinfo(SubinfoCominfoInsert {
+        let rel = SubinfoCominfoInsert {
             device_info_id: devinfo_id,
             subsystem_info_id: subinfo_id,
             component_info_id: cominfo_id,
             quantity: quantity,
-        })?)
+        };
+        rel.validate()?;
+        self.devinfo.get(devinfo_id)?;
+        self.subinfo.get(subinfo_id)?;
+        self.cominfo.get(cominfo_id)?;
+        if !self.rel.has_deviceinfo_subsysteminfo(devinfo_id, subinfo_id)? {
+            return Err(Error::Validation("subsystem info is not attached to the device info".to_owned()));
+        }
+        Ok(self.rel.insert_subsysteminfo_componentinfo(rel)?)
     }
 
     fn remove_component_info(&self, devinfo_id: i32, subinfo_id: i32, cominfo_id: i32) -> Result<usize> {
         Ok(self.rel.delete_subsysteminfo_componentinfo(devinfo_id, subinfo_id, cominfo_id)?)
     }
 
-    fn create_device(&self, devinfo_id: i32, unicode: String) -> Result<()> {
+    fn set_component_info_quantity(&self, devinfo_id: i32, subinfo_id: i32, cominfo_id: i32, quantity: i32) -> Result<usize> {
+        let rel = SubinfoCominfoInsert {
+            device_info_id: devinfo_id,
+            subsystem_info_id: subinfo_id,
+            component_info_id: cominfo_id,
+            quantity: quantity,
+        };
+        rel.validate()?;
+        if self.rel.subsysteminfo_componentinfo_quantity(devinfo_id, subinfo_id, cominfo_id)?.is_none() {
+            return Err(Error::NotFound("component info is not attached to the subsystem info".to_owned()));
+        }
+        Ok(self.rel.update_subsysteminfo_componentinfo(devinfo_id, subinfo_id, cominfo_id, quantity)?)
+    }
+
+    fn create_device(&self, devinfo_id: i32, unicode: String) -> Result<i32> {
+        let devinfo = self.devinfo.detail(devinfo_id)?;
+        self.atomic(|svc| {
+            let devins = DeviceInsert {
+                device_info_id: Some(devinfo.0.id),
+                name: devinfo.0.name,
+                model: devinfo.0.model,
+                maintain_interval: devinfo.0.maintain_interval,
+                unicode: unicode,
+                last_start_at: None,
+                last_stop_at: None,
+                total_duration: 0,
+                status: DeviceStatus::Stopped,
+            };
+            devins.validate()?;
+            let devid = svc.dev.insert(devins)?;
+            let subins: Vec<SubsystemInsert> = devinfo
+                .1
+                .iter()
+                .map(|(subinfo, _)| SubsystemInsert {
+                    device_id: devid,
+                    subsystem_info_id: Some(subinfo.id),
+                    name: subinfo.name.clone(),
+                    maintain_interval: subinfo.maintain_interval,
+                })
+                .collect();
+            let subids = svc.sub.bulk_insert_ids(&subins)?;
+            let mut comins = Vec::new();
+            for (subid, (_, cominfos)) in subids.into_iter().zip(devinfo.1.iter()) {
+                for (cominfo, quantity) in cominfos {
+                    comins.extend(Self::component_inserts(subid, cominfo, 1..=*quantity));
+                }
+            }
+            svc.com.bulk_insert(&comins)?;
+            Ok(devid)
+        })
+    }
+
+    // fk_device does not cascade, so the subsystems go first and their components follow them.
+    fn delete_device(&self, id: i32) -> Result<usize> {
+        self.atomic(|svc| {
+            let (_, subs) = match svc.dev.get(id) {
+                Ok(v) => v,
+                Err(Error::NotFound(_)) => return Ok(0),
+                Err(e) => return Err(e),
+            };
+            for (sub, _) in subs {
+                svc.sub.delete(sub.id)?;
+            }
+            Ok(svc.dev.delete(id)?)
+        })
+    }
+
+    fn update_device(&self, id: i32, upd: DeviceUpdate) -> Result<usize> {
+        upd.validate()?;
+        if upd.status.is_some() {
+            return Err(Error::Validation("device status can only be changed through a status transition".to_owned()));
+        }
+        let mut c = Checker::default();
+        c.forbidden("last_start_at", upd.last_start_at.is_some());
+        c.forbidden("last_stop_at", upd.last_stop_at.is_some());
+        c.forbidden("total_duration", upd.total_duration.is_some());
+        c.forbidden("last_maintain_at", upd.last_maintain_at.is_some());
+        c.forbidden("last_maintain_duration", upd.last_maintain_duration.is_some());
+        c.finish()?;
+        Ok(self.dev.update(id, upd)?)
+    }
+
+    fn get_device(&self, id: i32) -> Result<DeviceTree> {
+        Ok(self.dev.get(id)?.into())
+    }
+
+    fn query_device(&self, query: &DeviceQuery) -> Result<(Vec<DeviceTree>, i64)> {
+        query.validate()?;
+        let (devs, total) = self.dev.query(query)?;
+        Ok((devs.into_iter().map(DeviceTree::from).collect(), total))
+    }
+
+    fn query_device_by_device_info(&self, devinfo_id: i32, mut query: DeviceQuery) -> Result<(Vec<DeviceTree>, i64)> {
+        query.device_info_id = Some(devinfo_id);
+        self.query_device(&query)
+    }
+
+    fn device_drift(&self, id: i32) -> Result<DeviceDrift> {
+        let dev = self.dev.get(id)?;
+        let devinfo_id = dev.0.device_info_id.ok_or_else(|| Error::Validation("device was not created from a template".to_owned()))?;
         let devinfo = self.devinfo.detail(devinfo_id)?;
-        let devins = DeviceInsert {
-            name: devinfo.0.name,
-            model: devinfo.0.model,
-            maintain_interval: devinfo.0.maintain_interval,
-            unicode: unicode,
-            last_start_at: None,
-            last_stop_at: None,
-            total_duration: 0,
-            status: DeviceStatus::Stopped,
-        };
-        let devid = self.dev.insert(devins)?;
-        for subinfo in devinfo.1 {
-            let subins = SubsystemInsert {
-                device_id: devid,
-                name: subinfo.0.name,
-                maintain_interval: subinfo.0.maintain_interval,
+        self.with_conflicts(&devinfo, drift::diff(&devinfo, &dev))
+    }
+
+    fn start_device(&self, id: i32, at: NaiveDateTime) -> Result<i32> {
+        self.change_device_status(id, DeviceStatus::Running, StatusReason::Manual, None, at)
+    }
+
+    fn stop_device(&self, id: i32, at: NaiveDateTime) -> Result<i32> {
+        self.change_device_status(id, DeviceStatus::Stopped, StatusReason::Manual, None, at)
+    }
+
+    // Entering Running stamps last_start_at, leaving it accumulates the run hours, every change is written to the status history.
+    fn change_device_status(&self, id: i32, to: DeviceStatus, reason: StatusReason, description: Option<String>, at: NaiveDateTime) -> Result<i32> {
+        self.atomic(|svc| {
+            let (dev, _) = svc.dev.get(id)?;
+            status::check(dev.status, to, reason, description.as_ref().map(String::as_str))?;
+            let mut upd = DeviceUpdate {
+                status: Some(to),
+                ..Default::default()
             };
-            let subid = self.sub.insert(subins)?;
-            for cominfo in subinfo.1 {
-                let comins = ComponentInsert {
-                    subsystem_id: subid,
-                    name: cominfo.name,
-                    model: cominfo.model,
-                    maintain_interval: cominfo.maintain_interval,
+            if to == DeviceStatus::Running {
+                if dev.last_stop_at.map_or(false, |stop| at < stop) {
+                    return Err(Error::Validation("start time is earlier than the last stop".to_owned()));
+                }
+                upd.last_start_at = Some(at);
+                svc.event.insert(DeviceEventInsert {
+                    device_id: id,
+                    kind: DeviceEventKind::Start,
+                    happen_at: at,
+                    duration: 0,
+                })?;
+            }
+            if dev.status == DeviceStatus::Running {
+                let start = dev.last_start_at.unwrap_or(at);
+                if at < start {
+                    return Err(Error::Validation("stop time is earlier than the last start".to_owned()));
+                }
+                let hours = maintenance::run_hours(start, at);
+                upd.last_stop_at = Some(at);
+                upd.total_duration = Some(dev.total_duration + hours);
+                svc.event.insert(DeviceEventInsert {
+                    device_id: id,
+                    kind: DeviceEventKind::Stop,
+                    happen_at: at,
+                    duration: hours,
+                })?;
+            }
+            svc.dev.update(id, upd)?;
+            Ok(svc.history.insert(StatusHistoryInsert {
+                device_id: id,
+                from_status: dev.status,
+                to_status: to,
+                reason: reason,
+                description: description,
+                happen_at: at,
+            })?)
+        })
+    }
+
+    fn query_status_history(&self, id: i32, mut query: StatusHistoryQuery) -> Result<(Vec<StatusHistory>, i64)> {
+        query.validate()?;
+        query.device_id = Some(id);
+        Ok(self.history.query(&query)?)
+    }
+
+    fn query_device_event(&self, id: i32, mut query: DeviceEventQuery) -> Result<(Vec<DeviceEvent>, i64)> {
+        query.validate()?;
+        query.device_id = Some(id);
+        Ok(self.event.query(&query)?)
+    }
+
+    fn preview_template_changes(&self, devinfo_id: i32) -> Result<Vec<DeviceDrift>> {
+        let devinfo = self.devinfo.detail(devinfo_id)?;
+        let (devs, _) = self.dev.query(&DeviceQuery {
+            device_info_id: Some(devinfo_id),
+            ..Default::default()
+        })?;
+        devs.iter()
+            .map(|dev| drift::diff(&devinfo, dev))
+            .filter(|d| d.maintain_interval.is_some() || !d.subsystems.is_empty())
+            .map(|d| self.with_conflicts(&devinfo, d))
+            .collect()
+    }
+
+    fn apply_template_changes(&self, devinfo_id: i32) -> Result<Vec<DeviceDrift>> {
+        self.atomic(|svc| {
+            let devinfo = svc.devinfo.detail(devinfo_id)?;
+            let drifts = svc.preview_template_changes(devinfo_id)?;
+            for drift in &drifts {
+                svc.apply_drift(&devinfo, drift)?;
+            }
+            Ok(drifts)
+        })
+    }
+
+    fn maintain_plan(&self, id: i32) -> Result<Vec<MaintainPlan>> {
+        let dev = self.dev.get(id)?;
+        Ok(maintenance::plan(&dev, Local::now().naive_local()))
+    }
+
+    fn query_maintain_plan(&self, query: &MaintainQuery) -> Result<(Vec<MaintainPlan>, i64)> {
+        query.validate()?;
+        let devices = DeviceQuery {
+            name: query.device_name.clone(),
+            model: query.device_model.clone(),
+            status: query.device_status,
+            ..Default::default()
+        };
+        let now = Local::now().naive_local();
+        let within = query.due_within.unwrap_or(maintenance::DUE_SOON_HOURS);
+        let mut plans: Vec<MaintainPlan> = Vec::new();
+        self.each_device_page(devices, |devs| {
+            plans.extend(
+                devs.iter()
+                    .flat_map(|dev| maintenance::plan(dev, now))
+                    .filter(|p| p.remaining <= within)
+                    .filter(|p| query.target.map_or(true, |t| t == p.target))
+                    .filter(|p| query.overdue.map_or(true, |o| o == p.overdue)),
+            )
+        })?;
+        plans.sort_by_key(|p| p.remaining);
+        let total = plans.len() as i64;
+        if let (Some(p), Some(s)) = (query.page, query.size) {
+            plans = plans.into_iter().skip(((p - 1) * s) as usize).take(s as usize).collect();
+        }
+        Ok((plans, total))
+    }
+
+    fn add_subsystem(&self, sub: SubsystemInsert) -> Result<i32> {
+        sub.validate()?;
+        let (dev, _) = self.dev.get(sub.device_id)?;
+        let runtime = maintenance::runtime(&dev, Local::now().naive_local());
+        self.atomic(|svc| {
+            let id = svc.sub.insert(sub)?;
+            svc.start_subsystem_clock(id, runtime)?;
+            Ok(id)
+        })
+    }
+
+    fn delete_subsystem(&self, id: i32) -> Result<usize> {
+        Ok(self.sub.delete(id)?)
+    }
+
+    fn update_subsystem(&self, id: i32, upd: SubsystemUpdate) -> Result<usize> {
+        upd.validate()?;
+        Ok(self.sub.udpate(id, upd)?)
+    }
+
+    fn get_subsystem(&self, id: i32) -> Result<SubsystemDetail> {
+        Ok(self.sub.get(id)?.into())
+    }
+
+    fn query_subsystem(&self, query: &SubsystemQuery) -> Result<(Vec<SubsystemOfDevice>, i64)> {
+        query.validate()?;
+        let (list, total) = self.sub.query(query)?;
+        Ok((list.into_iter().map(SubsystemOfDevice::from).collect(), total))
+    }
+
+    fn add_component(&self, com: ComponentInsert) -> Result<i32> {
+        com.validate()?;
+        let (dev, _, _) = self.sub.get(com.subsystem_id)?;
+        let runtime = maintenance::runtime(&dev, Local::now().naive_local());
+        self.atomic(|svc| {
+            let id = svc.com.insert(com)?;
+            svc.start_component_clock(id, runtime)?;
+            Ok(id)
+        })
+    }
+
+    fn delete_component(&self, id: i32) -> Result<usize> {
+        Ok(self.com.delete(id)?)
+    }
+
+    fn update_component(&self, id: i32, upd: ComponentUpdate) -> Result<usize> {
+        upd.validate()?;
+        Ok(self.com.update(id, upd)?)
+    }
+
+    fn get_component(&self, id: i32) -> Result<ComponentDetail> {
+        Ok(self.com.get(id)?.into())
+    }
+
+    fn query_component(&self, query: &ComponentQuery) -> Result<(Vec<ComponentDetail>, i64)> {
+        query.validate()?;
+        let (list, total) = self.com.query(query)?;
+        Ok((list.into_iter().map(ComponentDetail::from).collect(), total))
+    }
+
+    fn create_work_order(&self, order: WorkOrderCreate) -> Result<i32> {
+        let (target, subsystem_id) = self.resolve_target(order.device_id, order.subsystem_id, order.component_id)?;
+        let ins = WorkOrderInsert {
+            device_id: order.device_id,
+            subsystem_id: subsystem_id,
+            component_id: order.component_id,
+            target: target,
+            status: WorkOrderStatus::Planned,
+            technician: order.technician,
+            notes: order.notes,
+            planned_at: order.planned_at,
+        };
+        ins.validate()?;
+        Ok(self.wo.insert(ins)?)
+    }
+
+    fn delete_work_order(&self, id: i32) -> Result<usize> {
+        Ok(self.wo.delete(id)?)
+    }
+
+    fn update_work_order(&self, id: i32, upd: WorkOrderUpdate) -> Result<usize> {
+        upd.validate()?;
+        Ok(self.wo.update(id, upd)?)
+    }
+
+    fn get_work_order(&self, id: i32) -> Result<WorkOrder> {
+        Ok(self.wo.get(id)?)
+    }
+
+    fn query_work_order(&self, query: &WorkOrderQuery) -> Result<(Vec<WorkOrder>, i64)> {
+        query.validate()?;
+        Ok(self.wo.query(query)?)
+    }
+
+    fn start_work_order(&self, id: i32, at: NaiveDateTime) -> Result<usize> {
+        let order = self.wo.get(id)?;
+        if order.status != WorkOrderStatus::Planned {
+            return Err(Error::Conflict(format!("cannot start a work order in {:?} status", order.status)));
+        }
+        Ok(self.wo.transition(
+            id,
+            WorkOrderTransition {
+                status: WorkOrderStatus::InProgress,
+                started_at: Some(at),
+                finished_at: None,
+            },
+        )?)
+    }
+
+    // Completing a work order resets the maintenance clock of its target to the device runtime at completion.
+    fn complete_work_order(&self, id: i32, at: NaiveDateTime, notes: Option<String>) -> Result<usize> {
+        self.atomic(|svc| {
+            let order = svc.wo.get(id)?;
+            if order.status != WorkOrderStatus::Planned && order.status != WorkOrderStatus::InProgress {
+                return Err(Error::Conflict(format!("cannot complete a work order in {:?} status", order.status)));
+            }
+            let (dev, _) = svc.dev.get(order.device_id)?;
+            let runtime = maintenance::runtime(&dev, at);
+            match (order.target, order.subsystem_id, order.component_id) {
+                (MaintainTarget::Component, _, Some(com_id)) => {
+                    svc.com.update(
+                        com_id,
+                        ComponentUpdate {
+                            last_maintain_at: Some(at),
+                            last_maintain_duration: Some(runtime),
+                            ..Default::default()
+                        },
+                    )?;
+                }
+                (MaintainTarget::Subsystem, Some(sub_id), _) => {
+                    svc.sub.udpate(
+                        sub_id,
+                        SubsystemUpdate {
+                            last_maintain_at: Some(at),
+                            last_maintain_duration: Some(runtime),
+                            ..Default::default()
+                        },
+                    )?;
+                }
+                _ => {
+                    svc.dev.update(
+                        dev.id,
+                        DeviceUpdate {
+                            last_maintain_at: Some(at),
+                            last_maintain_duration: Some(runtime),
+                            ..Default::default()
+                        },
+                    )?;
+                }
+            }
+            if notes.is_some() {
+                svc.wo.update(
+                    id,
+                    WorkOrderUpdate {
+                        notes: notes,
+                        ..Default::default()
+                    },
+                )?;
+            }
+            Ok(svc.wo.transition(
+                id,
+                WorkOrderTransition {
+                    status: WorkOrderStatus::Done,
+                    started_at: order.started_at.or(Some(at)),
+                    finished_at: Some(at),
+                },
+            )?)
+        })
+    }
+
+    fn cancel_work_order(&self, id: i32, notes: Option<String>) -> Result<usize> {
+        self.atomic(|svc| {
+            let order = svc.wo.get(id)?;
+            if order.status != WorkOrderStatus::Planned && order.status != WorkOrderStatus::InProgress {
+                return Err(Error::Conflict(format!("cannot cancel a work order in {:?} status", order.status)));
+            }
+            if notes.is_some() {
+                svc.wo.update(
+                    id,
+                    WorkOrderUpdate {
+                        notes: notes,
+                        ..Default::default()
+                    },
+                )?;
+            }
+            Ok(svc.wo.transition(
+                id,
+                WorkOrderTransition {
+                    status: WorkOrderStatus::Cancelled,
+                    started_at: None,
+                    finished_at: None,
+                },
+            )?)
+        })
+    }
+
+    // A fault report puts the device into Breakdown unless it is already there.
+    fn report_fault(&self, report: FaultReport) -> Result<i32> {
+        if report.subsystem_id.is_none() && report.component_id.is_none() {
+            return Err(Error::Validation("a fault ticket must point at a subsystem or a component".to_owned()));
+        }
+        let (_, subsystem_id) = self.resolve_target(report.device_id, report.subsystem_id, report.component_id)?;
+        let reported_at = report.reported_at.unwrap_or_else(|| Local::now().naive_local());
+        let ticket = FaultTicketInsert {
+            device_id: report.device_id,
+            subsystem_id: subsystem_id.unwrap_or_default(),
+            component_id: report.component_id,
+            failure_code: report.failure_code.clone(),
+            description: report.description.clone(),
+            reported_at: reported_at,
+        };
+        ticket.validate()?;
+        self.atomic(|svc| {
+            let id = svc.fault.insert(ticket)?;
+            let (dev, _) = svc.dev.get(report.device_id)?;
+            if dev.status != DeviceStatus::Breakdown {
+                let description = match report.description {
+                    Some(d) => format!("{}: {}", report.failure_code, d),
+                    None => report.failure_code,
                 };
-                self.com.insert(comins)?;
+                svc.change_device_status(dev.id, DeviceStatus::Breakdown, StatusReason::Fault, Some(description), reported_at)?;
             }
+            Ok(id)
+        })
+    }
+
+    fn delete_fault(&self, id: i32) -> Result<usize> {
+        Ok(self.fault.delete(id)?)
+    }
+
+    fn update_fault(&self, id: i32, upd: FaultTicketUpdate) -> Result<usize> {
+        upd.validate()?;
+        let mut c = Checker::default();
+        c.forbidden("repaired_at", upd.repaired_at.is_some());
+        c.forbidden("resolution", upd.resolution.is_some());
+        c.finish()?;
+        Ok(self.fault.update(id, upd)?)
+    }
+
+    fn get_fault(&self, id: i32) -> Result<FaultTicket> {
+        Ok(self.fault.get(id)?)
+    }
+
+    fn query_fault(&self, query: &FaultTicketQuery) -> Result<(Vec<FaultTicket>, i64)> {
+        query.validate()?;
+        Ok(self.fault.query(query)?)
+    }
+
+    // Once the last open ticket of a device in Breakdown is closed the device may go back to Stopped, it only does when the caller asks for it.
+    fn close_fault(&self, id: i32, close: FaultClose) -> Result<FaultCloseResult> {
+        let repaired_at = close.repaired_at.unwrap_or_else(|| Local::now().naive_local());
+        self.atomic(|svc| {
+            let ticket = svc.fault.get(id)?;
+            if ticket.repaired_at.is_some() {
+                return Err(Error::Conflict("fault ticket is already closed".to_owned()));
+            }
+            if repaired_at < ticket.reported_at {
+                return Err(Error::Validation("repair time is earlier than the report time".to_owned()));
+            }
+            svc.fault.update(
+                id,
+                FaultTicketUpdate {
+                    repaired_at: Some(repaired_at),
+                    resolution: Some(close.resolution.clone()),
+                    ..Default::default()
+                },
+            )?;
+            let (_, open_tickets) = svc.fault.query(&FaultTicketQuery {
+                device_id: Some(ticket.device_id),
+                open: Some(true),
+                ..Default::default()
+            })?;
+            let (dev, _) = svc.dev.get(ticket.device_id)?;
+            let can_restore_device = open_tickets == 0 && dev.status == DeviceStatus::Breakdown;
+            let device_restored = can_restore_device && close.restore_device;
+            if device_restored {
+                svc.change_device_status(dev.id, DeviceStatus::Stopped, StatusReason::Repaired, Some(close.resolution), repaired_at)?;
+            }
+            Ok(FaultCloseResult {
+                device_id: dev.id,
+                open_tickets,
+                can_restore_device: can_restore_device && !device_restored,
+                device_restored,
+            })
+        })
+    }
+
+    fn reliability_report(&self, query: &ReliabilityQuery) -> Result<ReliabilityReport> {
+        query.validate()?;
+        let (tickets, _) = self.fault.query(&FaultTicketQuery {
+            reported_at_begin: query.begin.as_ref().map(|v| MyDatetime(v.0)),
+            reported_at_end: query.end.as_ref().map(|v| MyDatetime(v.0)),
+            ..Default::default()
+        })?;
+        // A run stopped after the end may still have started inside the range.
+        let (stops, _) = self.event.query(&DeviceEventQuery {
+            kind: Some(DeviceEventKind::Stop),
+            happen_at_begin: query.begin.as_ref().map(|v| MyDatetime(v.0)),
+            ..Default::default()
+        })?;
+        let (begin, end) = (query.begin.as_ref().map(|v| v.0), query.end.as_ref().map(|v| v.0));
+        let mut report = report::Reliability::new(&stops, begin, end, Local::now().naive_local());
+        let devices = DeviceQuery {
+            name: query.device_name.clone(),
+            model: query.device_model.clone(),
+            ..Default::default()
+        };
+        self.each_device_page(devices, |devs| report.add_devices(devs))?;
+        Ok(report.finish(&tickets))
+    }
+
+    // The horizon is given either in calendar days, converted per device by its utilisation, or directly in run hours.
+    fn spare_part_demand(&self, query: &DemandQuery) -> Result<Vec<PartDemand>> {
+        query.validate()?;
+        let devices = DeviceQuery {
+            name: query.device_name.clone(),
+            model: query.device_model.clone(),
+            ..Default::default()
+        };
+        let now = Local::now().naive_local();
+        let mut demand = maintenance::Demand::default();
+        match (query.days, query.hours) {
+            (Some(days), None) if days > 0 => self.each_device_page(devices, |devs| demand.add_devices(devs, now, |dev| maintenance::expected_hours(dev, now, days)))?,
+            (None, Some(hours)) if hours > 0 => self.each_device_page(devices, |devs| demand.add_devices(devs, now, |_| hours))?,
+            _ => return Err(Error::Validation("exactly one positive horizon of days or hours is required".to_owned())),
         }
-        Ok(())
+        Ok(demand.finish())
     }
 
-    fn delete_device(&self, id: i32) -> Result<usize> {
-        Ok(self.dev.delete(id)?)
+    fn spare_part_demand_csv(&self, query: &DemandQuery) -> Result<String> {
+        report::demand_csv(&self.spare_part_demand(query)?)
+    }
+
+    // Every row runs in its own savepoint so a bad row is reported without losing the others, a dry run rolls the whole import back.
+    fn import_bom(&self, format: FileFormat, data: &[u8], dry_run: bool) -> Result<ImportReport> {
+        let (rows, errors) = match format {
+            FileFormat::Csv => import::read_csv(data)?,
+            FileFormat::Xlsx => import::read_xlsx(data)?,
+            FileFormat::Json => return Err(Error::Validation("json import is not supported".to_owned())),
+        };
+        let mut report = ImportReport {
+            dry_run,
+            rows: rows.len() + errors.len(),
+            errors,
+            ..Default::default()
+        };
+        self.tx.begin()?;
+        for row in &rows {
+            match self.atomic(|svc| svc.upsert_bom_row(row)) {
+                Ok((created, updated)) => {
+                    report.imported += 1;
+                    report.created.merge(created);
+                    report.updated.merge(updated);
+                }
+                Err(e) => report.errors.push(RowError {
+                    row: row.row,
+                    message: format!("{}", e),
+                }),
+            }
+        }
+        if dry_run {
+            self.tx.rollback()?;
+        } else {
+            self.tx.commit()?;
+        }
+        report.errors.sort_by_key(|e| e.row);
+        Ok(report)
+    }
+
+    fn add_storage_location(&self, name: String) -> Result<i32> {
+        let loc = StorageLocationInsert { name };
+        loc.validate()?;
+        Ok(self.inv.insert_location(loc)?)
+    }
+
+    fn delete_storage_location(&self, id: i32) -> Result<usize> {
+        Ok(self.inv.delete_location(id)?)
+    }
+
+    fn storage_locations(&self) -> Result<Vec<StorageLocation>> {
+        Ok(self.inv.locations()?)
+    }
+
+    // Every movement is written to the ledger together with the stock change, stock never goes below zero.
+    fn move_stock(&self, mov: StockMovementRequest) -> Result<i32> {
+        let (from, to) = match mov.kind {
+            StockMovementKind::Receive => (None, mov.to_location_id),
+            StockMovementKind::Issue => (mov.from_location_id, None),
+            StockMovementKind::Adjust => (None, mov.to_location_id),
+            StockMovementKind::Transfer => (mov.from_location_id, mov.to_location_id),
+        };
+        match mov.kind {
+            StockMovementKind::Receive | StockMovementKind::Adjust if to.is_none() => return Err(Error::Validation("to_location_id is required".to_owned())),
+            StockMovementKind::Issue if from.is_none() => return Err(Error::Validation("from_location_id is required".to_owned())),
+            StockMovementKind::Transfer if from.is_none() || to.is_none() => return Err(Error::Validation("from_location_id and to_location_id are required".to_owned())),
+            StockMovementKind::Transfer if from == to => return Err(Error::Validation("cannot transfer to the same location".to_owned())),
+            StockMovementKind::Adjust if mov.quantity == 0 => return Err(Error::Validation("quantity must not be zero".to_owned())),
+            StockMovementKind::Receive | StockMovementKind::Issue | StockMovementKind::Transfer if mov.quantity <= 0 => {
+                return Err(Error::Validation("quantity must be positive".to_owned()))
+            }
+            _ => {}
+        }
+        let at = mov.at.unwrap_or_else(|| Local::now().naive_local());
+        self.atomic(|svc| {
+            svc.cominfo.get(mov.component_info_id)?;
+            if let Some(loc) = from {
+                if svc.inv.take_stock(mov.component_info_id, loc, mov.quantity)? == 0 {
+                    return Err(Error::Conflict("insufficient stock".to_owned()));
+                }
+            }
+            if let Some(loc) = to {
+                if mov.quantity < 0 {
+                    if svc.inv.take_stock(mov.component_info_id, loc, -mov.quantity)? == 0 {
+                        return Err(Error::Conflict("insufficient stock".to_owned()));
+                    }
+                } else {
+                    svc.inv.change_stock(mov.component_info_id, loc, mov.quantity)?;
+                }
+            }
+            Ok(svc.inv.insert_movement(StockMovementInsert {
+                component_info_id: mov.component_info_id,
+                kind: mov.kind,
+                from_location_id: from,
+                to_location_id: to,
+                quantity: mov.quantity,
+                note: mov.note.clone(),
+                happen_at: at,
+            })?)
+        })
+    }
+
+    fn query_stock(&self, query: &StockQuery) -> Result<(Vec<Stock>, i64)> {
+        query.validate()?;
+        Ok(self.inv.query_stock(query)?)
+    }
+
+    fn query_stock_movement(&self, query: &StockMovementQuery) -> Result<(Vec<StockMovement>, i64)> {
+        query.validate()?;
+        Ok(self.inv.query_movement(query)?)
+    }
+
+    fn set_reorder_point(&self, cominfo_id: i32, quantity: i32) -> Result<usize> {
+        if quantity < 0 {
+            return Err(Error::Validation("reorder point must not be negative".to_owned()));
+        }
+        Ok(self.inv.set_reorder_point(ReorderPoint {
+            component_info_id: cominfo_id,
+            quantity,
+        })?)
+    }
+
+    fn low_stock(&self) -> Result<Vec<LowStock>> {
+        Ok(self
+            .inv
+            .low_stock()?
+            .into_iter()
+            .map(|(component_info, quantity, reorder_point)| LowStock {
+                component_info,
+                quantity,
+                reorder_point,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Stores components in memory but refuses every insert, standing in for a database that fails halfway through a write.
+    struct FailingComponents(memstorer::ComponentRepository);
+
+    fn refused() -> Error {
+        Error::Internal("component insert refused".to_owned())
+    }
+
+    impl ComponentStorer for FailingComponents {
+        fn insert(&self, _: ComponentInsert) -> Result<i32> {
+            Err(refused())
+        }
+        fn bulk_insert(&self, _: &Vec<ComponentInsert>) -> Result<usize> {
+            Err(refused())
+        }
+        fn bulk_insert_ids(&self, _: &Vec<ComponentInsert>) -> Result<Vec<i32>> {
+            Err(refused())
+        }
+        fn delete(&self, id: i32) -> Result<usize> {
+            self.0.delete(id)
+        }
+        fn update(&self, id: i32, upd: ComponentUpdate) -> Result<usize> {
+            self.0.update(id, upd)
+        }
+        fn get(&self, id: i32) -> Result<(Device, Subsystem, Component)> {
+            self.0.get(id)
+        }
+        fn query(&self, query: &ComponentQuery) -> Result<(Vec<(Device, Subsystem, Component)>, i64)> {
+            self.0.query(query)
+        }
+    }
+
+    fn failing_service(db: Rc<RefCell<Memory>>) -> Service {
+        Service {
+            com: Box::new(FailingComponents(memstorer::ComponentRepository::new(db.clone()))),
+            ..Service::memory(db)
+        }
+    }
+
+    #[test]
+    fn create_device_leaves_nothing_behind_when_components_fail() {
+        let db = Memory::shared();
+        let svc = failing_service(db.clone());
+        let devinfo_id = svc.add_device_info("pump".to_owned(), "P-100".to_owned(), 720).unwrap();
+        let subinfo_id = svc.add_subsystem_info("motor".to_owned(), 360).unwrap();
+        let cominfo_id = svc.add_component_info("bearing".to_owned(), "6204".to_owned(), 180).unwrap();
+        svc.attach_subsystem_info(devinfo_id, subinfo_id).unwrap();
+        svc.attach_component_info(devinfo_id, subinfo_id, cominfo_id, 2).unwrap();
+
+        match svc.create_device(devinfo_id, "pump-1".to_owned()) {
+            Err(Error::Internal(msg)) => assert_eq!(msg, "component insert refused"),
+            other => panic!("expected the component failure, got {:?}", other),
+        }
+
+        // A storer that works reads the same rows, so nothing is hidden by the failing one.
+        let svc = Service::memory(db);
+        assert_eq!(svc.dev.query(&DeviceQuery::default()).unwrap().1, 0);
+        assert_eq!(svc.sub.query(&SubsystemQuery::default()).unwrap().1, 0);
+        assert_eq!(svc.com.query(&ComponentQuery::default()).unwrap().1, 0);
+        // The templates written before the failure are untouched.
+        assert_eq!(svc.rel.subsysteminfo_componentinfo_quantity(devinfo_id, subinfo_id, cominfo_id).unwrap(), Some(2));
+    }
+
+    #[test]
+    fn template_changes_keep_components_with_history() {
+        let svc = Service::memory(Memory::shared());
+        let devinfo_id = svc.add_device_info("pump".to_owned(), "P-100".to_owned(), 720).unwrap();
+        let subinfo_id = svc.add_subsystem_info("motor".to_owned(), 360).unwrap();
+        let cominfo_id = svc.add_component_info("bearing".to_owned(), "6204".to_owned(), 180).unwrap();
+        svc.attach_subsystem_info(devinfo_id, subinfo_id).unwrap();
+        svc.attach_component_info(devinfo_id, subinfo_id, cominfo_id, 2).unwrap();
+        let dev_id = svc.create_device(devinfo_id, "pump-1".to_owned()).unwrap();
+        let (_, subs) = svc.dev.get(dev_id).unwrap();
+        let (sub_id, worked, idle) = (subs[0].0.id, subs[0].1[0].id, subs[0].1[1].id);
+        svc.create_work_order(WorkOrderCreate {
+            device_id: dev_id,
+            subsystem_id: None,
+            component_id: Some(worked),
+            technician: "ann".to_owned(),
+            notes: None,
+            planned_at: None,
+        })
+        .unwrap();
+        svc.remove_component_info(devinfo_id, subinfo_id, cominfo_id).unwrap();
+
+        let drifts = svc.preview_template_changes(devinfo_id).unwrap();
+        assert_eq!(drifts.len(), 1);
+        let conflicts: Vec<(i32, Option<i32>, i64, i64)> = drifts[0].conflicts.iter().map(|c| (c.subsystem_id, c.component_id, c.work_orders, c.fault_tickets)).collect();
+        assert_eq!(conflicts, vec![(sub_id, Some(worked), 1, 0)]);
+
+        svc.apply_template_changes(devinfo_id).unwrap();
+        assert!(svc.com.get(worked).is_ok());
+        assert!(matches!(svc.com.get(idle), Err(Error::NotFound(_))));
+        assert_eq!(svc.wo.query(&WorkOrderQuery::default()).unwrap().1, 1);
+    }
+
+    #[test]
+    fn device_pages_visit_every_device_once() {
+        let svc = Service::memory(Memory::shared());
+        let devinfo_id = svc.add_device_info("pump".to_owned(), "P-100".to_owned(), 720).unwrap();
+        let mut ids: Vec<i32> = (0..DEVICE_PAGE + 1).map(|i| svc.create_device(devinfo_id, format!("pump-{}", i)).unwrap()).collect();
+        let mut pages = Vec::new();
+        let mut seen = Vec::new();
+        svc.each_device_page(DeviceQuery::default(), |devs| {
+            pages.push(devs.len());
+            seen.extend(devs.iter().map(|(dev, _)| dev.id));
+        })
+        .unwrap();
+        assert_eq!(pages, vec![DEVICE_PAGE as usize, 1]);
+        ids.sort();
+        seen.sort();
+        assert_eq!(seen, ids);
     }
 }
@@ -0,0 +1,20 @@
+use super::dao::{Error, Result};
+use super::model::*;
+
+// The device status state machine: which transitions exist and which reason codes each one accepts.
+pub fn check(from: DeviceStatus, to: DeviceStatus, reason: StatusReason, description: Option<&str>) -> Result<()> {
+    let allowed: &[StatusReason] = match (from, to) {
+        (DeviceStatus::Stopped, DeviceStatus::Running) => &[StatusReason::Scheduled, StatusReason::Manual],
+        (DeviceStatus::Running, DeviceStatus::Stopped) => &[StatusReason::Scheduled, StatusReason::Manual, StatusReason::Maintenance],
+        (DeviceStatus::Running, DeviceStatus::Breakdown) | (DeviceStatus::Stopped, DeviceStatus::Breakdown) => &[StatusReason::Fault],
+        (DeviceStatus::Breakdown, DeviceStatus::Stopped) => &[StatusReason::Repaired],
+        _ => return Err(Error::Conflict(format!("transition from {:?} to {:?} is not allowed", from, to))),
+    };
+    if !allowed.contains(&reason) {
+        return Err(Error::Validation(format!("reason {:?} is not accepted for a transition from {:?} to {:?}", reason, from, to)));
+    }
+    if reason == StatusReason::Fault && description.map_or(true, |d| d.trim().is_empty()) {
+        return Err(Error::Validation("a fault description is required".to_owned()));
+    }
+    Ok(())
+}
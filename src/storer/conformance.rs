@@ -0,0 +1,193 @@
+// Behaviour every storer has to share, run against the in-memory store and against each database named in the environment:
+// TEST_MYSQL_URL, TEST_POSTGRES_URL and TEST_SQLITE_URL point at migrated databases, a backend whose variable is unset is skipped.
+// Each database test runs inside a transaction that is never committed, so the databases are left as they were found.
+use super::super::dao::Error;
+use super::super::model::*;
+use super::super::service::{Server, Service};
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+use super::connection::{self, Backend};
+use super::memstorer::Memory;
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+use diesel::Connection;
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+use std::env;
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+use std::rc::Rc;
+
+fn backends() -> Vec<(&'static str, Service)> {
+    let mut svcs = vec![("memory", Service::memory(Memory::shared()))];
+    #[cfg(feature = "mysql")]
+    {
+        if let Ok(url) = env::var("TEST_MYSQL_URL") {
+            match connection::pool("mysql", &url).expect("failed to connect to TEST_MYSQL_URL") {
+                Backend::Mysql(pool) => {
+                    let conn = pool.get().expect("failed to connect to TEST_MYSQL_URL");
+                    conn.begin_test_transaction().expect("failed to begin the test transaction");
+                    svcs.push(("mysql", Service::mysql(Rc::new(conn))));
+                }
+                #[allow(unreachable_patterns)]
+                _ => unreachable!(),
+            }
+        }
+    }
+    #[cfg(feature = "postgres")]
+    {
+        if let Ok(url) = env::var("TEST_POSTGRES_URL") {
+            match connection::pool("postgres", &url).expect("failed to connect to TEST_POSTGRES_URL") {
+                Backend::Postgres(pool) => {
+                    let conn = pool.get().expect("failed to connect to TEST_POSTGRES_URL");
+                    conn.begin_test_transaction().expect("failed to begin the test transaction");
+                    svcs.push(("postgres", Service::postgres(Rc::new(conn))));
+                }
+                #[allow(unreachable_patterns)]
+                _ => unreachable!(),
+            }
+        }
+    }
+    #[cfg(feature = "sqlite")]
+    {
+        if let Ok(url) = env::var("TEST_SQLITE_URL") {
+            match connection::pool("sqlite", &url).expect("failed to open TEST_SQLITE_URL") {
+                Backend::Sqlite(pool) => {
+                    let conn = pool.get().expect("failed to open TEST_SQLITE_URL");
+                    conn.begin_test_transaction().expect("failed to begin the test transaction");
+                    svcs.push(("sqlite", Service::sqlite(Rc::new(conn))));
+                }
+                #[allow(unreachable_patterns)]
+                _ => unreachable!(),
+            }
+        }
+    }
+    svcs
+}
+
+fn devinfo(svc: &Service, name: &str) -> i32 {
+    svc.devinfo
+        .insert(DeviceInfoInsert {
+            name: name.to_owned(),
+            model: "conformance".to_owned(),
+            maintain_interval: 720,
+        })
+        .unwrap()
+}
+
+fn devinfo_query(name: &str, page: i64, size: i64) -> DeviceInfoQuery {
+    DeviceInfoQuery {
+        name: Some(name.to_owned()),
+        page: page,
+        size: size,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn duplicate_keys_conflict() {
+    for (backend, svc) in backends() {
+        devinfo(&svc, "conformance-unique");
+        // A failed statement aborts the whole PostgreSQL transaction, so the duplicates go in under a savepoint.
+        let dup = svc.atomic(|s| {
+            s.devinfo.insert(DeviceInfoInsert {
+                name: "conformance-unique".to_owned(),
+                model: "conformance".to_owned(),
+                maintain_interval: 0,
+            })
+        });
+        assert!(matches!(dup, Err(Error::Conflict(_))), "{}: duplicate device info gave {:?}", backend, dup);
+
+        svc.add_subsystem_info("conformance-unique".to_owned(), 0).unwrap();
+        let dup = svc.atomic(|s| {
+            s.subinfo.insert(SubsystemInfoInsert {
+                name: "conformance-unique".to_owned(),
+                maintain_interval: 0,
+            })
+        });
+        assert!(matches!(dup, Err(Error::Conflict(_))), "{}: duplicate subsystem info gave {:?}", backend, dup);
+    }
+}
+
+#[test]
+fn missing_parents_are_foreign_key_errors() {
+    for (backend, svc) in backends() {
+        let res = svc.rel.insert_deviceinfo_subsysteminfo(DevinfoSubinfoInsert {
+            device_info_id: i32::max_value(),
+            subsystem_info_id: i32::max_value(),
+        });
+        assert!(matches!(res, Err(Error::ForeignKey(_))), "{}: dangling relation gave {:?}", backend, res);
+    }
+}
+
+#[test]
+fn deleting_templates_cascades_to_relations() {
+    for (backend, svc) in backends() {
+        let devinfo_id = devinfo(&svc, "conformance-cascade");
+        let subinfo_id = svc.add_subsystem_info("conformance-cascade".to_owned(), 360).unwrap();
+        let cominfo_id = svc.add_component_info("conformance-cascade".to_owned(), "conformance".to_owned(), 180).unwrap();
+        svc.attach_subsystem_info(devinfo_id, subinfo_id).unwrap();
+        svc.attach_component_info(devinfo_id, subinfo_id, cominfo_id, 2).unwrap();
+        assert_eq!(svc.rel.subsysteminfo_componentinfo_quantity(devinfo_id, subinfo_id, cominfo_id).unwrap(), Some(2), "{}", backend);
+
+        svc.cominfo.delete(cominfo_id).unwrap();
+        assert_eq!(svc.rel.subsysteminfo_componentinfo_quantity(devinfo_id, subinfo_id, cominfo_id).unwrap(), None, "{}", backend);
+        svc.subinfo.delete(subinfo_id).unwrap();
+        assert!(!svc.rel.has_deviceinfo_subsysteminfo(devinfo_id, subinfo_id).unwrap(), "{}", backend);
+    }
+}
+
+#[test]
+fn deleting_a_device_removes_its_tree() {
+    for (backend, svc) in backends() {
+        let devinfo_id = devinfo(&svc, "conformance-tree");
+        let subinfo_id = svc.add_subsystem_info("conformance-tree".to_owned(), 360).unwrap();
+        let cominfo_id = svc.add_component_info("conformance-tree".to_owned(), "conformance".to_owned(), 180).unwrap();
+        svc.attach_subsystem_info(devinfo_id, subinfo_id).unwrap();
+        svc.attach_component_info(devinfo_id, subinfo_id, cominfo_id, 2).unwrap();
+        let dev_id = svc.create_device(devinfo_id, "conformance-tree-1".to_owned()).unwrap();
+
+        let (_, subs) = svc.dev.get(dev_id).unwrap();
+        assert_eq!(subs.len(), 1, "{}", backend);
+        assert_eq!(subs[0].1.len(), 2, "{}", backend);
+        let sub_id = subs[0].0.id;
+        let com_id = subs[0].1[0].id;
+
+        assert_eq!(svc.delete_device(dev_id).unwrap(), 1, "{}", backend);
+        assert!(matches!(svc.dev.get(dev_id), Err(Error::NotFound(_))), "{}: device survived", backend);
+        assert!(matches!(svc.sub.get(sub_id), Err(Error::NotFound(_))), "{}: subsystem survived", backend);
+        assert!(matches!(svc.com.get(com_id), Err(Error::NotFound(_))), "{}: component survived", backend);
+    }
+}
+
+#[test]
+fn pages_split_the_matches_without_overlap() {
+    for (backend, svc) in backends() {
+        let mut ids: Vec<i32> = (0..5).map(|i| devinfo(&svc, &format!("conformance-page-{}", i))).collect();
+        ids.sort();
+
+        let mut seen = Vec::new();
+        for (page, len) in vec![(1, 2), (2, 2), (3, 1), (4, 0)] {
+            let (rows, total) = svc.devinfo.query(&devinfo_query("conformance-page-", page, 2)).unwrap();
+            assert_eq!(total, 5, "{}: total of page {}", backend, page);
+            assert_eq!(rows.len(), len, "{}: length of page {}", backend, page);
+            seen.extend(rows.iter().map(|r| r.id));
+        }
+        seen.sort();
+        assert_eq!(seen, ids, "{}", backend);
+    }
+}
+
+#[test]
+fn like_filters_match_substrings_case_sensitively() {
+    for (backend, svc) in backends() {
+        let pump = devinfo(&svc, "conformance-like-Pump");
+        devinfo(&svc, "conformance-like-Valve");
+
+        let (rows, total) = svc.devinfo.query(&devinfo_query("like-Pu", 1, 10)).unwrap();
+        assert_eq!(total, 1, "{}", backend);
+        assert_eq!(rows.iter().map(|r| r.id).collect::<Vec<i32>>(), vec![pump], "{}", backend);
+
+        let (rows, total) = svc.devinfo.query(&devinfo_query("like-pump", 1, 10)).unwrap();
+        assert_eq!((rows.len(), total), (0, 0), "{}", backend);
+
+        let (_, total) = svc.devinfo.query(&devinfo_query("conformance-like-", 1, 10)).unwrap();
+        assert_eq!(total, 2, "{}", backend);
+    }
+}
@@ -1,9 +1,76 @@
+#[cfg(feature = "sqlite")]
+use diesel::connection::SimpleConnection;
+#[cfg(feature = "sqlite")]
+use diesel::r2d2::{self, CustomizeConnection};
+use diesel::r2d2::{ConnectionManager, Pool};
+#[cfg(feature = "mysql")]
 use diesel::Connection;
+#[cfg(feature = "mysql")]
 use diesel::MysqlConnection;
+#[cfg(feature = "postgres")]
+use diesel::PgConnection;
+#[cfg(feature = "sqlite")]
+use diesel::SqliteConnection;
 use std::error::Error;
+#[cfg(any(feature = "mysql", feature = "postgres"))]
+use std::time;
 
 pub type Result<T> = std::result::Result<T, Box<dyn Error>>;
 
+#[cfg(feature = "mysql")]
 pub fn new_conn(dsn: &str) -> Result<MysqlConnection> {
     Ok(MysqlConnection::establish(&dsn)?)
 }
+
+// The database the service stores into, chosen at startup by DATABASE_BACKEND among the backends compiled in.
+pub enum Backend {
+    #[cfg(feature = "mysql")]
+    Mysql(Pool<ConnectionManager<MysqlConnection>>),
+    #[cfg(feature = "postgres")]
+    Postgres(Pool<ConnectionManager<PgConnection>>),
+    #[cfg(feature = "sqlite")]
+    Sqlite(Pool<ConnectionManager<SqliteConnection>>),
+    // The in-memory store of the thread serving the request, so that the handler tests keep their rows from one request to the next.
+    #[cfg(test)]
+    Memory,
+}
+
+pub fn pool(backend: &str, url: &str) -> Result<Backend> {
+    match backend {
+        #[cfg(feature = "mysql")]
+        "mysql" => Ok(Backend::Mysql(
+            Pool::builder()
+                .max_size(10)
+                .max_lifetime(Some(time::Duration::from_secs(300)))
+                .build(ConnectionManager::<MysqlConnection>::new(url))?,
+        )),
+        #[cfg(feature = "postgres")]
+        "postgres" => Ok(Backend::Postgres(
+            Pool::builder()
+                .max_size(10)
+                .max_lifetime(Some(time::Duration::from_secs(300)))
+                .build(ConnectionManager::<PgConnection>::new(url))?,
+        )),
+        #[cfg(feature = "sqlite")]
+        "sqlite" => Ok(Backend::Sqlite(
+            Pool::builder()
+                .max_size(4)
+                .connection_customizer(Box::new(SqliteOptions))
+                .build(ConnectionManager::<SqliteConnection>::new(url))?,
+        )),
+        _ => Err(format!("database backend {} is unknown or not compiled in", backend).into()),
+    }
+}
+
+#[cfg(feature = "sqlite")]
+#[derive(Debug)]
+struct SqliteOptions;
+
+// SQLite checks foreign keys only when each connection asks for it, and LIKE has to be told to honour case the way the MySQL collation does.
+#[cfg(feature = "sqlite")]
+impl CustomizeConnection<SqliteConnection, r2d2::Error> for SqliteOptions {
+    fn on_acquire(&self, conn: &mut SqliteConnection) -> std::result::Result<(), r2d2::Error> {
+        conn.batch_execute("PRAGMA foreign_keys = ON; PRAGMA case_sensitive_like = ON; PRAGMA busy_timeout = 5000;")
+            .map_err(r2d2::Error::QueryError)
+    }
+}
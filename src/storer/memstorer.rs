@@ -0,0 +1,1660 @@
+use super::super::dao;
+use super::super::dao::{ComponentInfoStorer, ComponentStorer, DeviceEventStorer, DeviceInfoStorer, DeviceStorer, FaultStorer, InventoryStorer, RelationStorer, StatusHistoryStorer, SubsystemInfoStorer, SubsystemStorer, Transactor, WorkOrderStorer};
+use super::super::model::*;
+use chrono::{Local, NaiveDateTime, Timelike};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+#[derive(Default, Clone)]
+struct Tables {
+    device_info: Vec<DeviceInfo>,
+    subsystem_info: Vec<SubsystemInfo>,
+    component_info: Vec<ComponentInfo>,
+    device: Vec<Device>,
+    subsystem: Vec<Subsystem>,
+    component: Vec<Component>,
+    deviceinfo_subsysteminfo: Vec<DeviceinfoSubsysteminfo>,
+    subsysteminfo_componentinfo: Vec<SubsysteminfoComponentinfo>,
+    work_order: Vec<WorkOrder>,
+    device_event: Vec<DeviceEvent>,
+    device_status_history: Vec<StatusHistory>,
+    fault_ticket: Vec<FaultTicket>,
+    storage_location: Vec<StorageLocation>,
+    stock: Vec<Stock>,
+    stock_movement: Vec<StockMovement>,
+    reorder_point: Vec<ReorderPoint>,
+}
+
+#[cfg(test)]
+thread_local! {
+    static LOCAL: Rc<RefCell<Memory>> = Memory::shared();
+}
+
+// Rows of every table and the snapshots of open transactions, ids keep counting across a rollback as MySQL's auto increment does.
+#[derive(Default)]
+pub struct Memory {
+    tables: Tables,
+    snapshots: Vec<Tables>,
+    ids: BTreeMap<&'static str, i32>,
+}
+
+impl Memory {
+    pub fn shared() -> Rc<RefCell<Memory>> {
+        Rc::new(RefCell::new(Memory::default()))
+    }
+
+    // The store of the current thread, every test runs on a thread of its own and so starts empty.
+    #[cfg(test)]
+    pub fn local() -> Rc<RefCell<Memory>> {
+        LOCAL.with(|db| db.clone())
+    }
+
+    fn next_id(&mut self, table: &'static str) -> i32 {
+        let id = self.ids.entry(table).or_insert(0);
+        *id += 1;
+        *id
+    }
+
+    // A multi-row statement either inserts every row or none of them.
+    fn atomically<T, F>(&mut self, f: F) -> dao::Result<T>
+    where
+        F: FnOnce(&mut Memory) -> dao::Result<T>,
+    {
+        let saved = self.tables.clone();
+        let res = f(self);
+        if res.is_err() {
+            self.tables = saved;
+        }
+        res
+    }
+}
+
+fn now() -> NaiveDateTime {
+    Local::now().naive_local().with_nanosecond(0).unwrap_or_else(|| Local::now().naive_local())
+}
+
+fn not_found() -> dao::Error {
+    dao::Error::NotFound("record not found".to_owned())
+}
+
+fn duplicate(key: &str) -> dao::Error {
+    dao::Error::Conflict(format!("Duplicate entry for key '{}'", key))
+}
+
+fn missing_parent(fk: &str) -> dao::Error {
+    dao::Error::ForeignKey(format!("Cannot add or update a child row: a foreign key constraint fails ({})", fk))
+}
+
+fn referenced(fk: &str) -> dao::Error {
+    dao::Error::ForeignKey(format!("Cannot delete or update a parent row: a foreign key constraint fails ({})", fk))
+}
+
+// LIKE '%pattern%' under the tables' binary collation, % and _ inside the pattern keep their wildcard meaning.
+fn like(value: &str, pattern: &str) -> bool {
+    let v: Vec<char> = value.chars().collect();
+    let p: Vec<char> = format!("%{}%", pattern).chars().collect();
+    wildcard(&v, &p)
+}
+
+fn wildcard(v: &[char], p: &[char]) -> bool {
+    match p.split_first() {
+        None => v.is_empty(),
+        Some(('%', rest)) => (0..=v.len()).any(|i| wildcard(&v[i..], rest)),
+        Some(('_', rest)) => !v.is_empty() && wildcard(&v[1..], rest),
+        Some((c, rest)) => v.first() == Some(c) && wildcard(&v[1..], rest),
+    }
+}
+
+fn within<T: PartialOrd>(v: T, begin: Option<T>, end: Option<T>) -> bool {
+    begin.map_or(true, |b| v >= b) && end.map_or(true, |e| v < e)
+}
+
+// A NULL column never satisfies a range condition.
+fn within_at(v: Option<NaiveDateTime>, begin: &Option<MyDatetime>, end: &Option<MyDatetime>) -> bool {
+    match v {
+        Some(v) => within(v, begin.as_ref().map(|b| b.0), end.as_ref().map(|e| e.0)),
+        None => begin.is_none() && end.is_none(),
+    }
+}
+
+fn paginate<T>(rows: Vec<T>, page: i64, size: i64) -> Vec<T> {
+    rows.into_iter().skip(((page - 1) * size).max(0) as usize).take(size.max(0) as usize).collect()
+}
+
+fn paginate_opt<T>(rows: Vec<T>, page: Option<i64>, size: Option<i64>) -> Vec<T> {
+    match (page, size) {
+        (Some(p), Some(s)) => paginate(rows, p, s),
+        _ => rows,
+    }
+}
+
+fn remove<T, F>(rows: &mut Vec<T>, f: F) -> usize
+where
+    F: Fn(&T) -> bool,
+{
+    let before = rows.len();
+    rows.retain(|r| !f(r));
+    before - rows.len()
+}
+
+// ======================================================constraints=====================================================
+
+impl Tables {
+    fn has_device_info(&self, id: i32) -> bool {
+        self.device_info.iter().any(|r| r.id == id)
+    }
+
+    fn has_subsystem_info(&self, id: i32) -> bool {
+        self.subsystem_info.iter().any(|r| r.id == id)
+    }
+
+    fn has_component_info(&self, id: i32) -> bool {
+        self.component_info.iter().any(|r| r.id == id)
+    }
+
+    fn has_device(&self, id: i32) -> bool {
+        self.device.iter().any(|r| r.id == id)
+    }
+
+    fn has_subsystem(&self, id: i32) -> bool {
+        self.subsystem.iter().any(|r| r.id == id)
+    }
+
+    fn has_component(&self, id: i32) -> bool {
+        self.component.iter().any(|r| r.id == id)
+    }
+
+    fn has_location(&self, id: i32) -> bool {
+        self.storage_location.iter().any(|r| r.id == id)
+    }
+
+    fn remove_device_info(&mut self, id: i32) -> usize {
+        let n = remove(&mut self.device_info, |r| r.id == id);
+        self.deviceinfo_subsysteminfo.retain(|r| r.device_info_id != id);
+        self.subsysteminfo_componentinfo.retain(|r| r.device_info_id != id);
+        self.device.iter_mut().filter(|r| r.device_info_id == Some(id)).for_each(|r| r.device_info_id = None);
+        n
+    }
+
+    fn remove_subsystem_info(&mut self, id: i32) -> usize {
+        let n = remove(&mut self.subsystem_info, |r| r.id == id);
+        self.deviceinfo_subsysteminfo.retain(|r| r.subsystem_info_id != id);
+        self.subsysteminfo_componentinfo.retain(|r| r.subsystem_info_id != id);
+        self.subsystem.iter_mut().filter(|r| r.subsystem_info_id == Some(id)).for_each(|r| r.subsystem_info_id = None);
+        n
+    }
+
+    fn remove_component_info(&mut self, id: i32) -> dao::Result<usize> {
+        if self.stock_movement.iter().any(|r| r.component_info_id == id) {
+            return Err(referenced("fk_movement_componentinfo"));
+        }
+        let n = remove(&mut self.component_info, |r| r.id == id);
+        self.subsysteminfo_componentinfo.retain(|r| r.component_info_id != id);
+        self.stock.retain(|r| r.component_info_id != id);
+        self.reorder_point.retain(|r| r.component_info_id != id);
+        self.component.iter_mut().filter(|r| r.component_info_id == Some(id)).for_each(|r| r.component_info_id = None);
+        Ok(n)
+    }
+
+    fn remove_device(&mut self, id: i32) -> dao::Result<usize> {
+        if self.subsystem.iter().any(|r| r.device_id == id) {
+            return Err(referenced("fk_device"));
+        }
+        let n = remove(&mut self.device, |r| r.id == id);
+        self.work_order.retain(|r| r.device_id != id);
+        self.device_event.retain(|r| r.device_id != id);
+        self.device_status_history.retain(|r| r.device_id != id);
+        self.fault_ticket.retain(|r| r.device_id != id);
+        Ok(n)
+    }
+
+    fn remove_subsystem(&mut self, id: i32) -> usize {
+        let coms: Vec<i32> = self.component.iter().filter(|r| r.subsystem_id == id).map(|r| r.id).collect();
+        for com in coms {
+            self.remove_component(com);
+        }
+        let n = remove(&mut self.subsystem, |r| r.id == id);
+        self.work_order.retain(|r| r.subsystem_id != Some(id));
+        self.fault_ticket.retain(|r| r.subsystem_id != id);
+        n
+    }
+
+    fn remove_component(&mut self, id: i32) -> usize {
+        let n = remove(&mut self.component, |r| r.id == id);
+        self.work_order.retain(|r| r.component_id != Some(id));
+        self.fault_ticket.retain(|r| r.component_id != Some(id));
+        n
+    }
+
+    fn device_tree(&self, dev: &Device) -> (Device, Vec<(Subsystem, Vec<Component>)>) {
+        let subs = self
+            .subsystem
+            .iter()
+            .filter(|s| s.device_id == dev.id)
+            .map(|s| (s.clone(), self.component.iter().filter(|c| c.subsystem_id == s.id).cloned().collect()))
+            .collect();
+        (dev.clone(), subs)
+    }
+}
+
+//===========================================================device info===================================================
+
+fn device_info_matches(query: &DeviceInfoQuery, info: &DeviceInfo) -> bool {
+    query.name.as_ref().map_or(true, |v| like(&info.name, v))
+        && query.model.as_ref().map_or(true, |v| like(&info.model, v))
+        && within(info.maintain_interval, query.maintain_interval_begin, query.maintain_interval_end)
+}
+
+fn insert_device_info(db: &mut Memory, info: &DeviceInfoInsert) -> dao::Result<i32> {
+    if db.tables.device_info.iter().any(|r| r.name == info.name && r.model == info.model) {
+        return Err(duplicate("uni_name_model"));
+    }
+    let id = db.next_id("device_info");
+    let now = now();
+    db.tables.device_info.push(DeviceInfo {
+        id,
+        name: info.name.clone(),
+        model: info.model.clone(),
+        maintain_interval: info.maintain_interval,
+        create_at: now,
+        update_at: now,
+    });
+    Ok(id)
+}
+
+pub struct DeviceInfoRepository(Rc<RefCell<Memory>>);
+
+impl DeviceInfoRepository {
+    pub fn new(db: Rc<RefCell<Memory>>) -> Self {
+        DeviceInfoRepository(db)
+    }
+
+    fn filtered(&self, query: &DeviceInfoQuery) -> Vec<DeviceInfo> {
+        self.0.borrow().tables.device_info.iter().filter(|r| device_info_matches(query, r)).cloned().collect()
+    }
+}
+
+impl DeviceInfoStorer for DeviceInfoRepository {
+    fn insert(&self, info: DeviceInfoInsert) -> dao::Result<i32> {
+        insert_device_info(&mut self.0.borrow_mut(), &info)
+    }
+
+    fn bulk_insert(&self, infos: &Vec<DeviceInfoInsert>) -> dao::Result<usize> {
+        self.0.borrow_mut().atomically(|db| {
+            for info in infos {
+                insert_device_info(db, info)?;
+            }
+            Ok(infos.len())
+        })
+    }
+
+    fn bulk_insert_ids(&self, infos: &Vec<DeviceInfoInsert>) -> dao::Result<Vec<i32>> {
+        self.0.borrow_mut().atomically(|db| infos.iter().map(|info| insert_device_info(db, info)).collect())
+    }
+
+    fn delete(&self, id: i32) -> dao::Result<usize> {
+        Ok(self.0.borrow_mut().tables.remove_device_info(id))
+    }
+
+    fn bulk_delete(&self, query: DeviceInfoQuery) -> dao::Result<usize> {
+        let mut db = self.0.borrow_mut();
+        let ids: Vec<i32> = db
+            .tables
+            .device_info
+            .iter()
+            .filter(|r| {
+                query.name.as_ref().map_or(true, |v| r.name == *v)
+                    && query.model.as_ref().map_or(true, |v| r.model == *v)
+                    && within(r.maintain_interval, query.maintain_interval_begin, query.maintain_interval_end)
+            })
+            .map(|r| r.id)
+            .collect();
+        Ok(ids.into_iter().map(|id| db.tables.remove_device_info(id)).sum())
+    }
+
+    fn update(&self, id: i32, upd: DeviceInfoUpdate) -> dao::Result<usize> {
+        let mut db = self.0.borrow_mut();
+        let rows = &mut db.tables.device_info;
+        let i = match rows.iter().position(|r| r.id == id) {
+            Some(i) => i,
+            None => return Ok(0),
+        };
+        let name = upd.name.unwrap_or_else(|| rows[i].name.clone());
+        let model = upd.model.unwrap_or_else(|| rows[i].model.clone());
+        if rows.iter().any(|r| r.id != id && r.name == name && r.model == model) {
+            return Err(duplicate("uni_name_model"));
+        }
+        let row = &mut rows[i];
+        row.name = name;
+        row.model = model;
+        row.maintain_interval = upd.maintain_interval.unwrap_or(row.maintain_interval);
+        row.update_at = now();
+        Ok(1)
+    }
+
+    fn get(&self, id: i32) -> dao::Result<DeviceInfo> {
+        self.0.borrow().tables.device_info.iter().find(|r| r.id == id).cloned().ok_or_else(not_found)
+    }
+
+    fn find(&self, name: &str, model: &str) -> dao::Result<Option<DeviceInfo>> {
+        Ok(self.0.borrow().tables.device_info.iter().find(|r| r.name == name && r.model == model).cloned())
+    }
+
+    fn query(&self, query: &DeviceInfoQuery) -> dao::Result<(Vec<DeviceInfo>, i64)> {
+        let rows = self.filtered(query);
+        let c = rows.len() as i64;
+        Ok((paginate(rows, query.page, query.size), c))
+    }
+
+    fn query_by_subsystem_info(&self, subinfoid: i32, query: DeviceInfoQuery) -> dao::Result<(Vec<DeviceInfo>, i64)> {
+        let db = self.0.borrow();
+        let t = &db.tables;
+        let rows: Vec<DeviceInfo> = t
+            .device_info
+            .iter()
+            .filter(|d| t.deviceinfo_subsysteminfo.iter().any(|r| r.device_info_id == d.id && r.subsystem_info_id == subinfoid))
+            .filter(|d| device_info_matches(&query, d))
+            .cloned()
+            .collect();
+        let c = rows.len() as i64;
+        Ok((paginate(rows, query.page, query.size), c))
+    }
+
+    fn count(&self, query: DeviceInfoQuery) -> dao::Result<i64> {
+        Ok(self.filtered(&query).len() as i64)
+    }
+
+    fn is_exist(&self, id: i32) -> dao::Result<bool> {
+        Ok(self.0.borrow().tables.has_device_info(id))
+    }
+
+    fn detail(&self, id: i32) -> dao::Result<(DeviceInfo, Vec<(SubsystemInfo, Vec<(ComponentInfo, i32)>)>)> {
+        let db = self.0.borrow();
+        let t = &db.tables;
+        let dev = t.device_info.iter().find(|r| r.id == id).cloned().ok_or_else(not_found)?;
+        let subs = t
+            .deviceinfo_subsysteminfo
+            .iter()
+            .filter(|r| r.device_info_id == id)
+            .filter_map(|r| t.subsystem_info.iter().find(|s| s.id == r.subsystem_info_id))
+            .map(|s| {
+                let coms = t
+                    .subsysteminfo_componentinfo
+                    .iter()
+                    .filter(|r| r.device_info_id == id && r.subsystem_info_id == s.id)
+                    .filter_map(|r| t.component_info.iter().find(|c| c.id == r.component_info_id).map(|c| (c.clone(), r.quantity)))
+                    .collect();
+                (s.clone(), coms)
+            })
+            .collect();
+        Ok((dev, subs))
+    }
+}
+
+// ===================================================subsystem_info======================================================
+
+fn subsystem_info_matches(query: &SubsystemInfoQuery, info: &SubsystemInfo) -> bool {
+    query.name.as_ref().map_or(true, |v| like(&info.name, v)) && within(info.maintain_interval, query.maintain_interval_begin, query.maintain_interval_end)
+}
+
+fn insert_subsystem_info(db: &mut Memory, info: &SubsystemInfoInsert) -> dao::Result<i32> {
+    if db.tables.subsystem_info.iter().any(|r| r.name == info.name) {
+        return Err(duplicate("uni_name"));
+    }
+    let id = db.next_id("subsystem_info");
+    let now = now();
+    db.tables.subsystem_info.push(SubsystemInfo {
+        id,
+        name: info.name.clone(),
+        maintain_interval: info.maintain_interval,
+        create_at: now,
+        update_at: now,
+    });
+    Ok(id)
+}
+
+pub struct SubsystemInfoRepository(Rc<RefCell<Memory>>);
+
+impl SubsystemInfoRepository {
+    pub fn new(db: Rc<RefCell<Memory>>) -> Self {
+        SubsystemInfoRepository(db)
+    }
+
+    fn filtered(&self, query: &SubsystemInfoQuery) -> Vec<SubsystemInfo> {
+        self.0.borrow().tables.subsystem_info.iter().filter(|r| subsystem_info_matches(query, r)).cloned().collect()
+    }
+}
+
+impl SubsystemInfoStorer for SubsystemInfoRepository {
+    fn insert(&self, info: SubsystemInfoInsert) -> dao::Result<i32> {
+        insert_subsystem_info(&mut self.0.borrow_mut(), &info)
+    }
+
+    fn bulk_insert(&self, infos: &Vec<SubsystemInfoInsert>) -> dao::Result<usize> {
+        self.0.borrow_mut().atomically(|db| {
+            for info in infos {
+                insert_subsystem_info(db, info)?;
+            }
+            Ok(infos.len())
+        })
+    }
+
+    fn bulk_insert_ids(&self, infos: &Vec<SubsystemInfoInsert>) -> dao::Result<Vec<i32>> {
+        self.0.borrow_mut().atomically(|db| infos.iter().map(|info| insert_subsystem_info(db, info)).collect())
+    }
+
+    fn delete(&self, id: i32) -> dao::Result<usize> {
+        Ok(self.0.borrow_mut().tables.remove_subsystem_info(id))
+    }
+
+    fn update(&self, id: i32, upd: SubsystemInfoUpdate) -> dao::Result<usize> {
+        let mut db = self.0.borrow_mut();
+        let rows = &mut db.tables.subsystem_info;
+        let i = match rows.iter().position(|r| r.id == id) {
+            Some(i) => i,
+            None => return Ok(0),
+        };
+        let name = upd.name.unwrap_or_else(|| rows[i].name.clone());
+        if rows.iter().any(|r| r.id != id && r.name == name) {
+            return Err(duplicate("uni_name"));
+        }
+        let row = &mut rows[i];
+        row.name = name;
+        row.maintain_interval = upd.maintain_interval.unwrap_or(row.maintain_interval);
+        row.update_at = now();
+        Ok(1)
+    }
+
+    fn get(&self, id: i32) -> dao::Result<SubsystemInfo> {
+        self.0.borrow().tables.subsystem_info.iter().find(|r| r.id == id).cloned().ok_or_else(not_found)
+    }
+
+    fn find(&self, name: &str) -> dao::Result<Option<SubsystemInfo>> {
+        Ok(self.0.borrow().tables.subsystem_info.iter().find(|r| r.name == name).cloned())
+    }
+
+    fn query(&self, query: &SubsystemInfoQuery) -> dao::Result<(Vec<SubsystemInfo>, i64)> {
+        let rows = self.filtered(query);
+        let c = rows.len() as i64;
+        Ok((paginate(rows, query.page, query.size), c))
+    }
+
+    fn query_by_device_info(&self, devinfoid: i32, query: SubsystemInfoQuery) -> dao::Result<(Vec<SubsystemInfo>, i64)> {
+        let db = self.0.borrow();
+        let t = &db.tables;
+        let rows: Vec<SubsystemInfo> = t
+            .deviceinfo_subsysteminfo
+            .iter()
+            .filter(|r| r.device_info_id == devinfoid)
+            .filter_map(|r| t.subsystem_info.iter().find(|s| s.id == r.subsystem_info_id))
+            .filter(|s| subsystem_info_matches(&query, s))
+            .cloned()
+            .collect();
+        let c = rows.len() as i64;
+        Ok((paginate(rows, query.page, query.size), c))
+    }
+
+    // One row per template that lists the component under the subsystem, the way the join returns them.
+    fn query_by_component_info(&self, comid: i32, query: SubsystemInfoQuery) -> dao::Result<(Vec<SubsystemInfo>, i64)> {
+        let db = self.0.borrow();
+        let t = &db.tables;
+        let rows: Vec<SubsystemInfo> = t
+            .subsysteminfo_componentinfo
+            .iter()
+            .filter(|r| r.component_info_id == comid)
+            .filter_map(|r| t.subsystem_info.iter().find(|s| s.id == r.subsystem_info_id))
+            .filter(|s| subsystem_info_matches(&query, s))
+            .cloned()
+            .collect();
+        let c = rows.len() as i64;
+        Ok((paginate(rows, query.page, query.size), c))
+    }
+
+    fn count(&self, query: SubsystemInfoQuery) -> dao::Result<i64> {
+        Ok(self.filtered(&query).len() as i64)
+    }
+
+    fn is_exist(&self, id: i32) -> dao::Result<bool> {
+        Ok(self.0.borrow().tables.has_subsystem_info(id))
+    }
+}
+
+// =======================================================component_info===================================================
+
+fn component_info_matches(query: &ComponentInfoQuery, info: &ComponentInfo) -> bool {
+    query.name.as_ref().map_or(true, |v| like(&info.name, v))
+        && query.model.as_ref().map_or(true, |v| like(&info.model, v))
+        && within(info.maintain_interval, query.maintain_interval_begin, query.maintain_interval_end)
+}
+
+fn insert_component_info(db: &mut Memory, info: &ComponentInfoInsert) -> dao::Result<i32> {
+    if db.tables.component_info.iter().any(|r| r.name == info.name && r.model == info.model) {
+        return Err(duplicate("uni_name_model"));
+    }
+    let id = db.next_id("component_info");
+    let now = now();
+    db.tables.component_info.push(ComponentInfo {
+        id,
+        name: info.name.clone(),
+        model: info.model.clone(),
+        maintain_interval: info.maintain_interval,
+        create_at: now,
+        update_at: now,
+    });
+    Ok(id)
+}
+
+pub struct ComponentInfoRepository(Rc<RefCell<Memory>>);
+
+impl ComponentInfoRepository {
+    pub fn new(db: Rc<RefCell<Memory>>) -> Self {
+        ComponentInfoRepository(db)
+    }
+
+    fn filtered(&self, query: &ComponentInfoQuery) -> Vec<ComponentInfo> {
+        self.0.borrow().tables.component_info.iter().filter(|r| component_info_matches(query, r)).cloned().collect()
+    }
+}
+
+impl ComponentInfoStorer for ComponentInfoRepository {
+    fn insert(&self, info: ComponentInfoInsert) -> dao::Result<i32> {
+        insert_component_info(&mut self.0.borrow_mut(), &info)
+    }
+
+    fn bulk_insert(&self, infos: &Vec<ComponentInfoInsert>) -> dao::Result<usize> {
+        self.0.borrow_mut().atomically(|db| {
+            for info in infos {
+                insert_component_info(db, info)?;
+            }
+            Ok(infos.len())
+        })
+    }
+
+    fn bulk_insert_ids(&self, infos: &Vec<ComponentInfoInsert>) -> dao::Result<Vec<i32>> {
+        self.0.borrow_mut().atomically(|db| infos.iter().map(|info| insert_component_info(db, info)).collect())
+    }
+
+    fn delete(&self, id: i32) -> dao::Result<usize> {
+        self.0.borrow_mut().tables.remove_component_info(id)
+    }
+
+    fn update(&self, id: i32, upd: ComponentInfoUpdate) -> dao::Result<usize> {
+        let mut db = self.0.borrow_mut();
+        let rows = &mut db.tables.component_info;
+        let i = match rows.iter().position(|r| r.id == id) {
+            Some(i) => i,
+            None => return Ok(0),
+        };
+        let name = upd.name.unwrap_or_else(|| rows[i].name.clone());
+        let model = upd.model.unwrap_or_else(|| rows[i].model.clone());
+        if rows.iter().any(|r| r.id != id && r.name == name && r.model == model) {
+            return Err(duplicate("uni_name_model"));
+        }
+        let row = &mut rows[i];
+        row.name = name;
+        row.model = model;
+        row.maintain_interval = upd.maintain_interval.unwrap_or(row.maintain_interval);
+        row.update_at = now();
+        Ok(1)
+    }
+
+    fn get(&self, id: i32) -> dao::Result<ComponentInfo> {
+        self.0.borrow().tables.component_info.iter().find(|r| r.id == id).cloned().ok_or_else(not_found)
+    }
+
+    fn find(&self, name: &str, model: &str) -> dao::Result<Option<ComponentInfo>> {
+        Ok(self.0.borrow().tables.component_info.iter().find(|r| r.name == name && r.model == model).cloned())
+    }
+
+    fn query(&self, query: &ComponentInfoQuery) -> dao::Result<(Vec<ComponentInfo>, i64)> {
+        let rows = self.filtered(query);
+        let c = rows.len() as i64;
+        Ok((paginate(rows, query.page, query.size), c))
+    }
+
+    fn query_by_subsystem_info(&self, subinfoid: i32, query: ComponentInfoQuery) -> dao::Result<(Vec<ComponentInfo>, i64)> {
+        let db = self.0.borrow();
+        let t = &db.tables;
+        let rows: Vec<ComponentInfo> = t
+            .subsysteminfo_componentinfo
+            .iter()
+            .filter(|r| r.subsystem_info_id == subinfoid)
+            .filter_map(|r| t.component_info.iter().find(|c| c.id == r.component_info_id))
+            .filter(|c| component_info_matches(&query, c))
+            .cloned()
+            .collect();
+        let c = rows.len() as i64;
+        Ok((paginate(rows, query.page, query.size), c))
+    }
+
+    fn count(&self, query: ComponentInfoQuery) -> dao::Result<i64> {
+        Ok(self.filtered(&query).len() as i64)
+    }
+
+    fn is_exist(&self, id: i32) -> dao::Result<bool> {
+        Ok(self.0.borrow().tables.has_component_info(id))
+    }
+}
+
+// =======================================================device====================================================
+
+fn device_matches(query: &DeviceQuery, dev: &Device) -> bool {
+    query.name.as_ref().map_or(true, |v| like(&dev.name, v))
+        && query.model.as_ref().map_or(true, |v| like(&dev.model, v))
+        && within(dev.maintain_interval, query.maintain_interval_begin, query.maintain_interval_end)
+        && within_at(dev.last_start_at, &query.last_start_at_begin, &query.last_start_at_end)
+        && within_at(dev.last_stop_at, &query.last_stop_at_begin, &query.last_stop_at_end)
+        && within(dev.total_duration, query.total_duration_begin, query.total_duration_end)
+        && query.status.map_or(true, |v| dev.status == v)
+        && query.device_info_id.map_or(true, |v| dev.device_info_id == Some(v))
+}
+
+fn insert_device(db: &mut Memory, dev: &DeviceInsert) -> dao::Result<i32> {
+    if let Some(v) = dev.device_info_id {
+        if !db.tables.has_device_info(v) {
+            return Err(missing_parent("fk_device_deviceinfo"));
+        }
+    }
+    let id = db.next_id("device");
+    let now = now();
+    db.tables.device.push(Device {
+        id,
+        device_info_id: dev.device_info_id,
+        name: dev.name.clone(),
+        model: dev.model.clone(),
+        maintain_interval: dev.maintain_interval,
+        unicode: dev.unicode.clone(),
+        last_start_at: dev.last_start_at,
+        last_stop_at: dev.last_stop_at,
+        total_duration: dev.total_duration,
+        status: dev.status,
+        create_at: now,
+        update_at: now,
+        last_maintain_at: None,
+        last_maintain_duration: 0,
+    });
+    Ok(id)
+}
+
+pub struct DeviceRepository(Rc<RefCell<Memory>>);
+
+impl DeviceRepository {
+    pub fn new(db: Rc<RefCell<Memory>>) -> Self {
+        DeviceRepository(db)
+    }
+}
+
+impl DeviceStorer for DeviceRepository {
+    fn insert(&self, dev: DeviceInsert) -> dao::Result<i32> {
+        insert_device(&mut self.0.borrow_mut(), &dev)
+    }
+
+    fn bulk_insert(&self, devs: &Vec<DeviceInsert>) -> dao::Result<usize> {
+        self.0.borrow_mut().atomically(|db| {
+            for dev in devs {
+                insert_device(db, dev)?;
+            }
+            Ok(devs.len())
+        })
+    }
+
+    fn bulk_insert_ids(&self, devs: &Vec<DeviceInsert>) -> dao::Result<Vec<i32>> {
+        self.0.borrow_mut().atomically(|db| devs.iter().map(|dev| insert_device(db, dev)).collect())
+    }
+
+    fn delete(&self, id: i32) -> dao::Result<usize> {
+        self.0.borrow_mut().tables.remove_device(id)
+    }
+
+    fn update(&self, id: i32, upd: DeviceUpdate) -> dao::Result<usize> {
+        let mut db = self.0.borrow_mut();
+        let row = match db.tables.device.iter_mut().find(|r| r.id == id) {
+            Some(r) => r,
+            None => return Ok(0),
+        };
+        if let Some(v) = upd.name {
+            row.name = v;
+        }
+        if let Some(v) = upd.model {
+            row.model = v;
+        }
+        if let Some(v) = upd.maintain_interval {
+            row.maintain_interval = v;
+        }
+        if let Some(v) = upd.unicode {
+            row.unicode = v;
+        }
+        if let Some(v) = upd.last_start_at {
+            row.last_start_at = Some(v);
+        }
+        if let Some(v) = upd.last_stop_at {
+            row.last_stop_at = Some(v);
+        }
+        if let Some(v) = upd.total_duration {
+            row.total_duration = v;
+        }
+        if let Some(v) = upd.status {
+            row.status = v;
+        }
+        if let Some(v) = upd.last_maintain_at {
+            row.last_maintain_at = Some(v);
+        }
+        if let Some(v) = upd.last_maintain_duration {
+            row.last_maintain_duration = v;
+        }
+        row.update_at = now();
+        Ok(1)
+    }
+
+    fn get(&self, id: i32) -> dao::Result<(Device, Vec<(Subsystem, Vec<Component>)>)> {
+        let db = self.0.borrow();
+        let dev = db.tables.device.iter().find(|r| r.id == id).ok_or_else(not_found)?;
+        Ok(db.tables.device_tree(dev))
+    }
+
+    fn query(&self, query: &DeviceQuery) -> dao::Result<(Vec<(Device, Vec<(Subsystem, Vec<Component>)>)>, i64)> {
+        let db = self.0.borrow();
+        let devs: Vec<&Device> = db.tables.device.iter().filter(|r| device_matches(query, r)).collect();
+        let c = devs.len() as i64;
+        let trees = paginate_opt(devs, query.page, query.size).into_iter().map(|d| db.tables.device_tree(d)).collect();
+        Ok((trees, c))
+    }
+}
+
+// ==================================================subsystem====================================================
+
+fn subsystem_matches(query: &SubsystemQuery, dev: &Device, sub: &Subsystem) -> bool {
+    query.device_name.as_ref().map_or(true, |v| like(&dev.name, v))
+        && query.device_model.as_ref().map_or(true, |v| like(&dev.model, v))
+        && within(dev.maintain_interval, query.device_maintain_interval_begin, query.device_maintain_interval_end)
+        && query.device_unicode.as_ref().map_or(true, |v| like(&dev.unicode, v))
+        && within_at(dev.last_start_at, &query.device_last_start_at_begin, &query.device_last_start_at_end)
+        && within_at(dev.last_stop_at, &query.device_last_stop_at_begin, &query.device_last_stop_at_end)
+        && within(dev.total_duration, query.device_total_duration_begin, query.device_total_duration_end)
+        && query.device_status.map_or(true, |v| dev.status == v)
+        && query.subsystem_name.as_ref().map_or(true, |v| like(&sub.name, v))
+        && within(sub.maintain_interval, query.maintain_interval_begin, query.maintain_interval_end)
+}
+
+fn insert_subsystem(db: &mut Memory, sub: &SubsystemInsert) -> dao::Result<i32> {
+    if !db.tables.has_device(sub.device_id) {
+        return Err(missing_parent("fk_device"));
+    }
+    if let Some(v) = sub.subsystem_info_id {
+        if !db.tables.has_subsystem_info(v) {
+            return Err(missing_parent("fk_subsystem_subsysteminfo"));
+        }
+    }
+    if db.tables.subsystem.iter().any(|r| r.device_id == sub.device_id && r.name == sub.name) {
+        return Err(duplicate("uni_deviceid_name"));
+    }
+    let id = db.next_id("subsystem");
+    let now = now();
+    db.tables.subsystem.push(Subsystem {
+        id,
+        device_id: sub.device_id,
+        subsystem_info_id: sub.subsystem_info_id,
+        name: sub.name.clone(),
+        maintain_interval: sub.maintain_interval,
+        create_at: now,
+        udpate_at: now,
+        last_maintain_at: None,
+        last_maintain_duration: 0,
+    });
+    Ok(id)
+}
+
+pub struct SubsystemRepository(Rc<RefCell<Memory>>);
+
+impl SubsystemRepository {
+    pub fn new(db: Rc<RefCell<Memory>>) -> Self {
+        SubsystemRepository(db)
+    }
+}
+
+impl SubsystemStorer for SubsystemRepository {
+    fn insert(&self, sub: SubsystemInsert) -> dao::Result<i32> {
+        insert_subsystem(&mut self.0.borrow_mut(), &sub)
+    }
+
+    fn bulk_insert(&self, subs: &Vec<SubsystemInsert>) -> dao::Result<usize> {
+        self.0.borrow_mut().atomically(|db| {
+            for sub in subs {
+                insert_subsystem(db, sub)?;
+            }
+            Ok(subs.len())
+        })
+    }
+
+    fn bulk_insert_ids(&self, subs: &Vec<SubsystemInsert>) -> dao::Result<Vec<i32>> {
+        self.0.borrow_mut().atomically(|db| subs.iter().map(|sub| insert_subsystem(db, sub)).collect())
+    }
+
+    fn delete(&self, id: i32) -> dao::Result<usize> {
+        Ok(self.0.borrow_mut().tables.remove_subsystem(id))
+    }
+
+    fn udpate(&self, id: i32, upd: SubsystemUpdate) -> dao::Result<usize> {
+        let mut db = self.0.borrow_mut();
+        let rows = &mut db.tables.subsystem;
+        let i = match rows.iter().position(|r| r.id == id) {
+            Some(i) => i,
+            None => return Ok(0),
+        };
+        let name = upd.name.unwrap_or_else(|| rows[i].name.clone());
+        let device_id = rows[i].device_id;
+        if rows.iter().any(|r| r.id != id && r.device_id == device_id && r.name == name) {
+            return Err(duplicate("uni_deviceid_name"));
+        }
+        let row = &mut rows[i];
+        row.name = name;
+        row.maintain_interval = upd.maintain_interval.unwrap_or(row.maintain_interval);
+        if let Some(v) = upd.last_maintain_at {
+            row.last_maintain_at = Some(v);
+        }
+        row.last_maintain_duration = upd.last_maintain_duration.unwrap_or(row.last_maintain_duration);
+        row.udpate_at = now();
+        Ok(1)
+    }
+
+    fn get(&self, id: i32) -> dao::Result<(Device, Subsystem, Vec<Component>)> {
+        let db = self.0.borrow();
+        let t = &db.tables;
+        let sub = t.subsystem.iter().find(|r| r.id == id).ok_or_else(not_found)?;
+        let dev = t.device.iter().find(|r| r.id == sub.device_id).ok_or_else(not_found)?;
+        let coms = t.component.iter().filter(|r| r.subsystem_id == id).cloned().collect();
+        Ok((dev.clone(), sub.clone(), coms))
+    }
+
+    fn query(&self, query: &SubsystemQuery) -> dao::Result<(Vec<(Device, Subsystem)>, i64)> {
+        let db = self.0.borrow();
+        let t = &db.tables;
+        let rows: Vec<(Device, Subsystem)> = t
+            .subsystem
+            .iter()
+            .filter_map(|sub| t.device.iter().find(|d| d.id == sub.device_id).map(|dev| (dev, sub)))
+            .filter(|(dev, sub)| subsystem_matches(query, dev, sub))
+            .map(|(dev, sub)| (dev.clone(), sub.clone()))
+            .collect();
+        let c = rows.len() as i64;
+        Ok((paginate_opt(rows, query.page, query.size), c))
+    }
+}
+
+// =================================================component=========================================================
+
+fn component_matches(query: &ComponentQuery, dev: &Device, sub: &Subsystem, com: &Component) -> bool {
+    query.device_name.as_ref().map_or(true, |v| like(&dev.name, v))
+        && query.device_model.as_ref().map_or(true, |v| like(&dev.model, v))
+        && within(dev.maintain_interval, query.device_maintain_interval_begin, query.device_maintain_interval_end)
+        && query.device_unicode.as_ref().map_or(true, |v| like(&dev.unicode, v))
+        && within_at(dev.last_start_at, &query.device_last_start_at_begin, &query.device_last_start_at_end)
+        && within_at(dev.last_stop_at, &query.device_last_stop_at_begin, &query.device_last_stop_at_end)
+        && within(dev.total_duration, query.device_total_duration_begin, query.device_total_duration_end)
+        && query.device_status.map_or(true, |v| dev.status == v)
+        && query.subsystem_name.as_ref().map_or(true, |v| like(&sub.name, v))
+        && within(sub.maintain_interval, query.subsystem_maintain_interval_begin, query.subsystem_maintain_interval_end)
+        && query.name.as_ref().map_or(true, |v| like(&com.name, v))
+        && query.model.as_ref().map_or(true, |v| like(&com.model, v))
+        && within(com.maintain_interval, query.maintain_interval_begin, query.maintain_interval_end)
+}
+
+fn insert_component(db: &mut Memory, com: &ComponentInsert) -> dao::Result<i32> {
+    if !db.tables.has_subsystem(com.subsystem_id) {
+        return Err(missing_parent("fk_subsystemid"));
+    }
+    if let Some(v) = com.component_info_id {
+        if !db.tables.has_component_info(v) {
+            return Err(missing_parent("fk_component_componentinfo"));
+        }
+    }
+    let id = db.next_id("component");
+    let now = now();
+    db.tables.component.push(Component {
+        id,
+        subsystem_id: com.subsystem_id,
+        component_info_id: com.component_info_id,
+        name: com.name.clone(),
+        model: com.model.clone(),
+        maintain_interval: com.maintain_interval,
+        position: com.position,
+        create_at: now,
+        update_at: now,
+        last_maintain_at: None,
+        last_maintain_duration: 0,
+    });
+    Ok(id)
+}
+
+pub struct ComponentRepository(Rc<RefCell<Memory>>);
+
+impl ComponentRepository {
+    pub fn new(db: Rc<RefCell<Memory>>) -> Self {
+        ComponentRepository(db)
+    }
+}
+
+impl ComponentStorer for ComponentRepository {
+    fn insert(&self, com: ComponentInsert) -> dao::Result<i32> {
+        insert_component(&mut self.0.borrow_mut(), &com)
+    }
+
+    fn bulk_insert(&self, coms: &Vec<ComponentInsert>) -> dao::Result<usize> {
+        self.0.borrow_mut().atomically(|db| {
+            for com in coms {
+                insert_component(db, com)?;
+            }
+            Ok(coms.len())
+        })
+    }
+
+    fn bulk_insert_ids(&self, coms: &Vec<ComponentInsert>) -> dao::Result<Vec<i32>> {
+        self.0.borrow_mut().atomically(|db| coms.iter().map(|com| insert_component(db, com)).collect())
+    }
+
+    fn delete(&self, id: i32) -> dao::Result<usize> {
+        Ok(self.0.borrow_mut().tables.remove_component(id))
+    }
+
+    fn update(&self, id: i32, upd: ComponentUpdate) -> dao::Result<usize> {
+        let mut db = self.0.borrow_mut();
+        let row = match db.tables.component.iter_mut().find(|r| r.id == id) {
+            Some(r) => r,
+            None => return Ok(0),
+        };
+        if let Some(v) = upd.name {
+            row.name = v;
+        }
+        if let Some(v) = upd.model {
+            row.model = v;
+        }
+        if let Some(v) = upd.maintain_interval {
+            row.maintain_interval = v;
+        }
+        if let Some(v) = upd.last_maintain_at {
+            row.last_maintain_at = Some(v);
+        }
+        if let Some(v) = upd.last_maintain_duration {
+            row.last_maintain_duration = v;
+        }
+        row.update_at = now();
+        Ok(1)
+    }
+
+    fn get(&self, id: i32) -> dao::Result<(Device, Subsystem, Component)> {
+        let db = self.0.borrow();
+        let t = &db.tables;
+        let com = t.component.iter().find(|r| r.id == id).ok_or_else(not_found)?;
+        let sub = t.subsystem.iter().find(|r| r.id == com.subsystem_id).ok_or_else(not_found)?;
+        let dev = t.device.iter().find(|r| r.id == sub.device_id).ok_or_else(not_found)?;
+        Ok((dev.clone(), sub.clone(), com.clone()))
+    }
+
+    fn query(&self, query: &ComponentQuery) -> dao::Result<(Vec<(Device, Subsystem, Component)>, i64)> {
+        let db = self.0.borrow();
+        let t = &db.tables;
+        let rows: Vec<(Device, Subsystem, Component)> = t
+            .component
+            .iter()
+            .filter_map(|com| {
+                let sub = t.subsystem.iter().find(|s| s.id == com.subsystem_id)?;
+                let dev = t.device.iter().find(|d| d.id == sub.device_id)?;
+                Some((dev, sub, com))
+            })
+            .filter(|(dev, sub, com)| component_matches(query, dev, sub, com))
+            .map(|(dev, sub, com)| (dev.clone(), sub.clone(), com.clone()))
+            .collect();
+        let c = rows.len() as i64;
+        Ok((paginate_opt(rows, query.page, query.size), c))
+    }
+}
+
+// ==============================================================relations================================================
+
+pub struct RelationRepository(Rc<RefCell<Memory>>);
+
+impl RelationRepository {
+    pub fn new(db: Rc<RefCell<Memory>>) -> RelationRepository {
+        RelationRepository(db)
+    }
+}
+
+impl RelationStorer for RelationRepository {
+    fn insert_deviceinfo_subsysteminfo(&self, rel: DevinfoSubinfoInsert) -> dao::Result<usize> {
+        let mut db = self.0.borrow_mut();
+        if !db.tables.has_device_info(rel.device_info_id) {
+            return Err(missing_parent("fk_device_info"));
+        }
+        if !db.tables.has_subsystem_info(rel.subsystem_info_id) {
+            return Err(missing_parent("fk_subsystem_info"));
+        }
+        if db
+            .tables
+            .deviceinfo_subsysteminfo
+            .iter()
+            .any(|r| r.device_info_id == rel.device_info_id && r.subsystem_info_id == rel.subsystem_info_id)
+        {
+            return Err(duplicate("uni_deviceinfo_subsysteminfo"));
+        }
+        let id = db.next_id("deviceinfo_subsysteminfo");
+        db.tables.deviceinfo_subsysteminfo.push(DeviceinfoSubsysteminfo {
+            id,
+            device_info_id: rel.device_info_id,
+            subsystem_info_id: rel.subsystem_info_id,
+        });
+        Ok(1)
+    }
+
+    fn delete_deviceinfo_subsysteminfo(&self, devinfo_id: i32, subinfo_id: i32) -> dao::Result<usize> {
+        Ok(remove(&mut self.0.borrow_mut().tables.deviceinfo_subsysteminfo, |r| {
+            r.device_info_id == devinfo_id && r.subsystem_info_id == subinfo_id
+        }))
+    }
+
+    fn bulk_delete_deviceinfo_subsysteminfo(&self, devinfo_id: i32) -> dao::Result<usize> {
+        Ok(remove(&mut self.0.borrow_mut().tables.deviceinfo_subsysteminfo, |r| r.device_info_id == devinfo_id))
+    }
+
+    fn has_deviceinfo_subsysteminfo(&self, devinfo_id: i32, subinfo_id: i32) -> dao::Result<bool> {
+        Ok(self
+            .0
+            .borrow()
+            .tables
+            .deviceinfo_subsysteminfo
+            .iter()
+            .any(|r| r.device_info_id == devinfo_id && r.subsystem_info_id == subinfo_id))
+    }
+
+    fn insert_subsysteminfo_componentinfo(&self, rel: SubinfoCominfoInsert) -> dao::Result<usize> {
+        let mut db = self.0.borrow_mut();
+        if !db.tables.has_device_info(rel.device_info_id) {
+            return Err(missing_parent("fk_deviceinfo2"));
+        }
+        if !db.tables.has_subsystem_info(rel.subsystem_info_id) {
+            return Err(missing_parent("fk_subsysteminfo2"));
+        }
+        if !db.tables.has_component_info(rel.component_info_id) {
+            return Err(missing_parent("fk_componentinfo2"));
+        }
+        if db.tables.subsysteminfo_componentinfo.iter().any(|r| {
+            r.device_info_id == rel.device_info_id && r.subsystem_info_id == rel.subsystem_info_id && r.component_info_id == rel.component_info_id
+        }) {
+            return Err(duplicate("uni_devinfo_subinfo_cominf"));
+        }
+        let id = db.next_id("subsysteminfo_componentinfo");
+        db.tables.subsysteminfo_componentinfo.push(SubsysteminfoComponentinfo {
+            id,
+            device_info_id: rel.device_info_id,
+            subsystem_info_id: rel.subsystem_info_id,
+            component_info_id: rel.component_info_id,
+            quantity: rel.quantity,
+        });
+        Ok(1)
+    }
+
+    fn delete_subsysteminfo_componentinfo(&self, devinfo_id: i32, subinfo_id: i32, cominfo_id: i32) -> dao::Result<usize> {
+        Ok(remove(&mut self.0.borrow_mut().tables.subsysteminfo_componentinfo, |r| {
+            r.device_info_id == devinfo_id && r.subsystem_info_id == subinfo_id && r.component_info_id == cominfo_id
+        }))
+    }
+
+    fn update_subsysteminfo_componentinfo(&self, devinfo_id: i32, subinfo_id: i32, cominfo_id: i32, quantity: i32) -> dao::Result<usize> {
+        let mut db = self.0.borrow_mut();
+        let mut n = 0;
+        for r in db
+            .tables
+            .subsysteminfo_componentinfo
+            .iter_mut()
+            .filter(|r| r.device_info_id == devinfo_id && r.subsystem_info_id == subinfo_id && r.component_info_id == cominfo_id)
+        {
+            r.quantity = quantity;
+            n += 1;
+        }
+        Ok(n)
+    }
+
+    fn subsysteminfo_componentinfo_quantity(&self, devinfo_id: i32, subinfo_id: i32, cominfo_id: i32) -> dao::Result<Option<i32>> {
+        Ok(self
+            .0
+            .borrow()
+            .tables
+            .subsysteminfo_componentinfo
+            .iter()
+            .find(|r| r.device_info_id == devinfo_id && r.subsystem_info_id == subinfo_id && r.component_info_id == cominfo_id)
+            .map(|r| r.quantity))
+    }
+
+    fn bulk_delete_subsysteminfo_componentinfo(&self, devinfo_id: i32, subinfo_id: i32) -> dao::Result<usize> {
+        Ok(remove(&mut self.0.borrow_mut().tables.subsysteminfo_componentinfo, |r| {
+            r.device_info_id == devinfo_id && r.subsystem_info_id == subinfo_id
+        }))
+    }
+}
+
+// ==============================================================device_event================================================
+
+pub struct DeviceEventRepository(Rc<RefCell<Memory>>);
+
+impl DeviceEventRepository {
+    pub fn new(db: Rc<RefCell<Memory>>) -> DeviceEventRepository {
+        DeviceEventRepository(db)
+    }
+}
+
+impl DeviceEventStorer for DeviceEventRepository {
+    fn insert(&self, ev: DeviceEventInsert) -> dao::Result<i32> {
+        let mut db = self.0.borrow_mut();
+        if !db.tables.has_device(ev.device_id) {
+            return Err(missing_parent("fk_event_device"));
+        }
+        let id = db.next_id("device_event");
+        db.tables.device_event.push(DeviceEvent {
+            id,
+            device_id: ev.device_id,
+            kind: ev.kind,
+            happen_at: ev.happen_at,
+            duration: ev.duration,
+            create_at: now(),
+        });
+        Ok(id)
+    }
+
+    fn query(&self, query: &DeviceEventQuery) -> dao::Result<(Vec<DeviceEvent>, i64)> {
+        let mut rows: Vec<DeviceEvent> = self
+            .0
+            .borrow()
+            .tables
+            .device_event
+            .iter()
+            .filter(|r| {
+                query.device_id.map_or(true, |v| r.device_id == v)
+                    && query.kind.map_or(true, |v| r.kind == v)
+                    && within_at(Some(r.happen_at), &query.happen_at_begin, &query.happen_at_end)
+            })
+            .cloned()
+            .collect();
+        rows.sort_by(|a, b| b.happen_at.cmp(&a.happen_at));
+        let c = rows.len() as i64;
+        Ok((paginate_opt(rows, query.page, query.size), c))
+    }
+}
+
+// ==============================================================status_history================================================
+
+pub struct StatusHistoryRepository(Rc<RefCell<Memory>>);
+
+impl StatusHistoryRepository {
+    pub fn new(db: Rc<RefCell<Memory>>) -> StatusHistoryRepository {
+        StatusHistoryRepository(db)
+    }
+}
+
+impl StatusHistoryStorer for StatusHistoryRepository {
+    fn insert(&self, his: StatusHistoryInsert) -> dao::Result<i32> {
+        let mut db = self.0.borrow_mut();
+        if !db.tables.has_device(his.device_id) {
+            return Err(missing_parent("fk_history_device"));
+        }
+        let id = db.next_id("device_status_history");
+        db.tables.device_status_history.push(StatusHistory {
+            id,
+            device_id: his.device_id,
+            from_status: his.from_status,
+            to_status: his.to_status,
+            reason: his.reason,
+            description: his.description,
+            happen_at: his.happen_at,
+            create_at: now(),
+        });
+        Ok(id)
+    }
+
+    fn query(&self, query: &StatusHistoryQuery) -> dao::Result<(Vec<StatusHistory>, i64)> {
+        let mut rows: Vec<StatusHistory> = self
+            .0
+            .borrow()
+            .tables
+            .device_status_history
+            .iter()
+            .filter(|r| {
+                query.device_id.map_or(true, |v| r.device_id == v)
+                    && query.from_status.map_or(true, |v| r.from_status == v)
+                    && query.to_status.map_or(true, |v| r.to_status == v)
+                    && query.reason.map_or(true, |v| r.reason == v)
+                    && within_at(Some(r.happen_at), &query.happen_at_begin, &query.happen_at_end)
+            })
+            .cloned()
+            .collect();
+        rows.sort_by(|a, b| b.happen_at.cmp(&a.happen_at));
+        let c = rows.len() as i64;
+        Ok((paginate_opt(rows, query.page, query.size), c))
+    }
+}
+
+// ==============================================================work_order================================================
+
+pub struct WorkOrderRepository(Rc<RefCell<Memory>>);
+
+impl WorkOrderRepository {
+    pub fn new(db: Rc<RefCell<Memory>>) -> WorkOrderRepository {
+        WorkOrderRepository(db)
+    }
+}
+
+impl WorkOrderStorer for WorkOrderRepository {
+    fn insert(&self, order: WorkOrderInsert) -> dao::Result<i32> {
+        let mut db = self.0.borrow_mut();
+        if !db.tables.has_device(order.device_id) {
+            return Err(missing_parent("fk_workorder_device"));
+        }
+        if order.subsystem_id.map_or(false, |v| !db.tables.has_subsystem(v)) {
+            return Err(missing_parent("fk_workorder_subsystem"));
+        }
+        if order.component_id.map_or(false, |v| !db.tables.has_component(v)) {
+            return Err(missing_parent("fk_workorder_component"));
+        }
+        let id = db.next_id("work_order");
+        let now = now();
+        db.tables.work_order.push(WorkOrder {
+            id,
+            device_id: order.device_id,
+            subsystem_id: order.subsystem_id,
+            component_id: order.component_id,
+            target: order.target,
+            status: order.status,
+            technician: order.technician,
+            notes: order.notes,
+            planned_at: order.planned_at,
+            started_at: None,
+            finished_at: None,
+            create_at: now,
+            update_at: now,
+        });
+        Ok(id)
+    }
+
+    fn delete(&self, id: i32) -> dao::Result<usize> {
+        Ok(remove(&mut self.0.borrow_mut().tables.work_order, |r| r.id == id))
+    }
+
+    fn update(&self, id: i32, upd: WorkOrderUpdate) -> dao::Result<usize> {
+        let mut db = self.0.borrow_mut();
+        let row = match db.tables.work_order.iter_mut().find(|r| r.id == id) {
+            Some(r) => r,
+            None => return Ok(0),
+        };
+        if let Some(v) = upd.technician {
+            row.technician = v;
+        }
+        if let Some(v) = upd.notes {
+            row.notes = Some(v);
+        }
+        if let Some(v) = upd.planned_at {
+            row.planned_at = Some(v);
+        }
+        row.update_at = now();
+        Ok(1)
+    }
+
+    fn transition(&self, id: i32, trans: WorkOrderTransition) -> dao::Result<usize> {
+        let mut db = self.0.borrow_mut();
+        let row = match db.tables.work_order.iter_mut().find(|r| r.id == id) {
+            Some(r) => r,
+            None => return Ok(0),
+        };
+        row.status = trans.status;
+        if let Some(v) = trans.started_at {
+            row.started_at = Some(v);
+        }
+        if let Some(v) = trans.finished_at {
+            row.finished_at = Some(v);
+        }
+        row.update_at = now();
+        Ok(1)
+    }
+
+    fn get(&self, id: i32) -> dao::Result<WorkOrder> {
+        self.0.borrow().tables.work_order.iter().find(|r| r.id == id).cloned().ok_or_else(not_found)
+    }
+
+    fn query(&self, query: &WorkOrderQuery) -> dao::Result<(Vec<WorkOrder>, i64)> {
+        let mut rows: Vec<WorkOrder> = self
+            .0
+            .borrow()
+            .tables
+            .work_order
+            .iter()
+            .filter(|r| {
+                query.device_id.map_or(true, |v| r.device_id == v)
+                    && query.subsystem_id.map_or(true, |v| r.subsystem_id == Some(v))
+                    && query.component_id.map_or(true, |v| r.component_id == Some(v))
+                    && query.target.map_or(true, |v| r.target == v)
+                    && query.status.map_or(true, |v| r.status == v)
+                    && query.technician.as_ref().map_or(true, |v| like(&r.technician, v))
+                    && within_at(r.planned_at, &query.planned_at_begin, &query.planned_at_end)
+                    && within_at(r.finished_at, &query.finished_at_begin, &query.finished_at_end)
+            })
+            .cloned()
+            .collect();
+        rows.sort_by(|a, b| b.id.cmp(&a.id));
+        let c = rows.len() as i64;
+        Ok((paginate_opt(rows, query.page, query.size), c))
+    }
+}
+
+// ==============================================================fault_ticket================================================
+
+pub struct FaultRepository(Rc<RefCell<Memory>>);
+
+impl FaultRepository {
+    pub fn new(db: Rc<RefCell<Memory>>) -> FaultRepository {
+        FaultRepository(db)
+    }
+}
+
+impl FaultStorer for FaultRepository {
+    fn insert(&self, ticket: FaultTicketInsert) -> dao::Result<i32> {
+        let mut db = self.0.borrow_mut();
+        if !db.tables.has_device(ticket.device_id) {
+            return Err(missing_parent("fk_fault_device"));
+        }
+        if !db.tables.has_subsystem(ticket.subsystem_id) {
+            return Err(missing_parent("fk_fault_subsystem"));
+        }
+        if ticket.component_id.map_or(false, |v| !db.tables.has_component(v)) {
+            return Err(missing_parent("fk_fault_component"));
+        }
+        let id = db.next_id("fault_ticket");
+        let now = now();
+        db.tables.fault_ticket.push(FaultTicket {
+            id,
+            device_id: ticket.device_id,
+            subsystem_id: ticket.subsystem_id,
+            component_id: ticket.component_id,
+            failure_code: ticket.failure_code,
+            description: ticket.description,
+            reported_at: ticket.reported_at,
+            repaired_at: None,
+            resolution: None,
+            create_at: now,
+            update_at: now,
+        });
+        Ok(id)
+    }
+
+    fn delete(&self, id: i32) -> dao::Result<usize> {
+        Ok(remove(&mut self.0.borrow_mut().tables.fault_ticket, |r| r.id == id))
+    }
+
+    fn update(&self, id: i32, upd: FaultTicketUpdate) -> dao::Result<usize> {
+        let mut db = self.0.borrow_mut();
+        let row = match db.tables.fault_ticket.iter_mut().find(|r| r.id == id) {
+            Some(r) => r,
+            None => return Ok(0),
+        };
+        if let Some(v) = upd.failure_code {
+            row.failure_code = v;
+        }
+        if let Some(v) = upd.description {
+            row.description = Some(v);
+        }
+        if let Some(v) = upd.reported_at {
+            row.reported_at = v;
+        }
+        if let Some(v) = upd.repaired_at {
+            row.repaired_at = Some(v);
+        }
+        if let Some(v) = upd.resolution {
+            row.resolution = Some(v);
+        }
+        row.update_at = now();
+        Ok(1)
+    }
+
+    fn get(&self, id: i32) -> dao::Result<FaultTicket> {
+        self.0.borrow().tables.fault_ticket.iter().find(|r| r.id == id).cloned().ok_or_else(not_found)
+    }
+
+    fn query(&self, query: &FaultTicketQuery) -> dao::Result<(Vec<FaultTicket>, i64)> {
+        let mut rows: Vec<FaultTicket> = self
+            .0
+            .borrow()
+            .tables
+            .fault_ticket
+            .iter()
+            .filter(|r| {
+                query.device_id.map_or(true, |v| r.device_id == v)
+                    && query.subsystem_id.map_or(true, |v| r.subsystem_id == v)
+                    && query.component_id.map_or(true, |v| r.component_id == Some(v))
+                    && query.failure_code.as_ref().map_or(true, |v| like(&r.failure_code, v))
+                    && query.open.map_or(true, |v| r.repaired_at.is_none() == v)
+                    && within_at(Some(r.reported_at), &query.reported_at_begin, &query.reported_at_end)
+                    && within_at(r.repaired_at, &query.repaired_at_begin, &query.repaired_at_end)
+            })
+            .cloned()
+            .collect();
+        rows.sort_by(|a, b| b.reported_at.cmp(&a.reported_at));
+        let c = rows.len() as i64;
+        Ok((paginate_opt(rows, query.page, query.size), c))
+    }
+}
+
+// ==============================================================inventory================================================
+
+pub struct InventoryRepository(Rc<RefCell<Memory>>);
+
+impl InventoryRepository {
+    pub fn new(db: Rc<RefCell<Memory>>) -> InventoryRepository {
+        InventoryRepository(db)
+    }
+}
+
+impl InventoryStorer for InventoryRepository {
+    fn insert_location(&self, loc: StorageLocationInsert) -> dao::Result<i32> {
+        let mut db = self.0.borrow_mut();
+        if db.tables.storage_location.iter().any(|r| r.name == loc.name) {
+            return Err(duplicate("uni_name"));
+        }
+        let id = db.next_id("storage_location");
+        let now = now();
+        db.tables.storage_location.push(StorageLocation {
+            id,
+            name: loc.name,
+            create_at: now,
+            update_at: now,
+        });
+        Ok(id)
+    }
+
+    fn delete_location(&self, id: i32) -> dao::Result<usize> {
+        let mut db = self.0.borrow_mut();
+        if db.tables.stock.iter().any(|r| r.location_id == id) {
+            return Err(referenced("fk_stock_location"));
+        }
+        if db.tables.stock_movement.iter().any(|r| r.from_location_id == Some(id) || r.to_location_id == Some(id)) {
+            return Err(referenced("fk_movement_from"));
+        }
+        Ok(remove(&mut db.tables.storage_location, |r| r.id == id))
+    }
+
+    fn locations(&self) -> dao::Result<Vec<StorageLocation>> {
+        let mut rows = self.0.borrow().tables.storage_location.clone();
+        rows.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(rows)
+    }
+
+    fn stock_quantity(&self, cominfo_id: i32, location_id: i32) -> dao::Result<i32> {
+        Ok(self
+            .0
+            .borrow()
+            .tables
+            .stock
+            .iter()
+            .find(|r| r.component_info_id == cominfo_id && r.location_id == location_id)
+            .map_or(0, |r| r.quantity))
+    }
+
+    fn change_stock(&self, cominfo_id: i32, location_id: i32, delta: i32) -> dao::Result<usize> {
+        let mut db = self.0.borrow_mut();
+        if let Some(row) = db.tables.stock.iter_mut().find(|r| r.component_info_id == cominfo_id && r.location_id == location_id) {
+            row.quantity += delta;
+            row.update_at = now();
+            return Ok(1);
+        }
+        if !db.tables.has_component_info(cominfo_id) {
+            return Err(missing_parent("fk_stock_componentinfo"));
+        }
+        if !db.tables.has_location(location_id) {
+            return Err(missing_parent("fk_stock_location"));
+        }
+        let id = db.next_id("stock");
+        db.tables.stock.push(Stock {
+            id,
+            component_info_id: cominfo_id,
+            location_id,
+            quantity: delta,
+            update_at: now(),
+        });
+        Ok(1)
+    }
+
+    fn take_stock(&self, cominfo_id: i32, location_id: i32, quantity: i32) -> dao::Result<usize> {
+        let mut db = self.0.borrow_mut();
+        match db.tables.stock.iter_mut().find(|r| r.component_info_id == cominfo_id && r.location_id == location_id && r.quantity >= quantity) {
+            Some(row) => {
+                row.quantity -= quantity;
+                row.update_at = now();
+                Ok(1)
+            }
+            None => Ok(0),
+        }
+    }
+
+    fn query_stock(&self, query: &StockQuery) -> dao::Result<(Vec<Stock>, i64)> {
+        let mut rows: Vec<Stock> = self
+            .0
+            .borrow()
+            .tables
+            .stock
+            .iter()
+            .filter(|r| query.component_info_id.map_or(true, |v| r.component_info_id == v) && query.location_id.map_or(true, |v| r.location_id == v))
+            .cloned()
+            .collect();
+        rows.sort_by_key(|r| (r.component_info_id, r.location_id));
+        let c = rows.len() as i64;
+        Ok((paginate_opt(rows, query.page, query.size), c))
+    }
+
+    fn insert_movement(&self, mov: StockMovementInsert) -> dao::Result<i32> {
+        let mut db = self.0.borrow_mut();
+        if !db.tables.has_component_info(mov.component_info_id) {
+            return Err(missing_parent("fk_movement_componentinfo"));
+        }
+        if mov.from_location_id.map_or(false, |v| !db.tables.has_location(v)) {
+            return Err(missing_parent("fk_movement_from"));
+        }
+        if mov.to_location_id.map_or(false, |v| !db.tables.has_location(v)) {
+            return Err(missing_parent("fk_movement_to"));
+        }
+        let id = db.next_id("stock_movement");
+        db.tables.stock_movement.push(StockMovement {
+            id,
+            component_info_id: mov.component_info_id,
+            kind: mov.kind,
+            from_location_id: mov.from_location_id,
+            to_location_id: mov.to_location_id,
+            quantity: mov.quantity,
+            note: mov.note,
+            happen_at: mov.happen_at,
+            create_at: now(),
+        });
+        Ok(id)
+    }
+
+    fn query_movement(&self, query: &StockMovementQuery) -> dao::Result<(Vec<StockMovement>, i64)> {
+        let mut rows: Vec<StockMovement> = self
+            .0
+            .borrow()
+            .tables
+            .stock_movement
+            .iter()
+            .filter(|r| {
+                query.component_info_id.map_or(true, |v| r.component_info_id == v)
+                    && query.location_id.map_or(true, |v| r.from_location_id == Some(v) || r.to_location_id == Some(v))
+                    && query.kind.map_or(true, |v| r.kind == v)
+                    && within_at(Some(r.happen_at), &query.happen_at_begin, &query.happen_at_end)
+            })
+            .cloned()
+            .collect();
+        rows.sort_by(|a, b| b.happen_at.cmp(&a.happen_at));
+        let c = rows.len() as i64;
+        Ok((paginate_opt(rows, query.page, query.size), c))
+    }
+
+    // REPLACE reports two affected rows when it overwrites an existing point.
+    fn set_reorder_point(&self, point: ReorderPoint) -> dao::Result<usize> {
+        let mut db = self.0.borrow_mut();
+        if !db.tables.has_component_info(point.component_info_id) {
+            return Err(missing_parent("fk_reorder_componentinfo"));
+        }
+        let replaced = remove(&mut db.tables.reorder_point, |r| r.component_info_id == point.component_info_id);
+        db.tables.reorder_point.push(point);
+        Ok(1 + replaced)
+    }
+
+    fn low_stock(&self) -> dao::Result<Vec<(ComponentInfo, i64, i32)>> {
+        let db = self.0.borrow();
+        let t = &db.tables;
+        let mut rows: Vec<(ComponentInfo, i64, i32)> = t
+            .reorder_point
+            .iter()
+            .filter_map(|p| t.component_info.iter().find(|c| c.id == p.component_info_id).map(|c| (c, p.quantity)))
+            .map(|(cominfo, point)| {
+                let total = t.stock.iter().filter(|s| s.component_info_id == cominfo.id).map(|s| s.quantity as i64).sum();
+                (cominfo.clone(), total, point)
+            })
+            .filter(|(_, total, point)| *total <= *point as i64)
+            .collect();
+        rows.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+        Ok(rows)
+    }
+}
+
+// ==============================================================transaction================================================
+
+// Each begin snapshots the tables, so nested transactions roll back to their own savepoint.
+pub struct TransactionRepository(Rc<RefCell<Memory>>);
+
+impl TransactionRepository {
+    pub fn new(db: Rc<RefCell<Memory>>) -> TransactionRepository {
+        TransactionRepository(db)
+    }
+}
+
+impl Transactor for TransactionRepository {
+    fn begin(&self) -> dao::Result<()> {
+        let mut db = self.0.borrow_mut();
+        let snapshot = db.tables.clone();
+        db.snapshots.push(snapshot);
+        Ok(())
+    }
+
+    fn commit(&self) -> dao::Result<()> {
+        match self.0.borrow_mut().snapshots.pop() {
+            Some(_) => Ok(()),
+            None => Err(dao::Error::Internal("no transaction in progress".to_owned())),
+        }
+    }
+
+    fn rollback(&self) -> dao::Result<()> {
+        let mut db = self.0.borrow_mut();
+        match db.snapshots.pop() {
+            Some(snapshot) => {
+                db.tables = snapshot;
+                Ok(())
+            }
+            None => Err(dao::Error::Internal("no transaction in progress".to_owned())),
+        }
+    }
+}
@@ -1,2 +1,34 @@
+#[cfg(test)]
+mod conformance;
 pub mod connection;
+pub mod memstorer;
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+#[macro_use]
+mod sqlstorer;
+#[cfg(feature = "mysql")]
 pub mod mysqlstorer;
+#[cfg(feature = "postgres")]
+pub mod pgstorer;
+#[cfg(feature = "sqlite")]
+pub mod sqlitestorer;
+
+use super::dao;
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+
+impl From<r2d2::Error> for dao::Error {
+    fn from(e: r2d2::Error) -> Self {
+        dao::Error::Internal(format!("{}", e))
+    }
+}
+
+// Duplicate keys and broken references come back as database errors of their own kind on every backend, they are kept apart from other failures.
+impl From<DieselError> for dao::Error {
+    fn from(e: DieselError) -> Self {
+        match e {
+            DieselError::NotFound => dao::Error::NotFound("record not found".to_owned()),
+            DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, info) => dao::Error::Conflict(info.message().to_owned()),
+            DieselError::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, info) => dao::Error::ForeignKey(info.message().to_owned()),
+            e => dao::Error::Internal(format!("{}", e)),
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use diesel::pg::Pg;
+use diesel::result::QueryResult;
+use diesel::PgConnection;
+
+macro_rules! insert_id {
+    ($conn:expr, $table:ident, $row:expr) => {
+        diesel::insert_into($table::table).values($row).returning($table::id).get_result::<i32>($conn)
+    };
+}
+
+macro_rules! insert_all {
+    ($conn:expr, $table:ident, $rows:expr) => {
+        diesel::insert_into($table::table).values($rows).execute($conn)
+    };
+}
+
+// RETURNING hands the ids back in the order of the VALUES list.
+macro_rules! insert_ids {
+    ($conn:expr, $table:ident, $rows:expr $(; $($key:ident),+)?) => {
+        diesel::insert_into($table::table).values($rows).returning($table::id).get_results::<i32>($conn)
+    };
+}
+
+fn upsert_reorder_point(conn: &PgConnection, point: ReorderPoint) -> QueryResult<usize> {
+    let quantity = point.quantity;
+    diesel::insert_into(reorder_point::table).values(point).on_conflict(reorder_point::component_info_id).do_update().set(reorder_point::quantity.eq(quantity)).execute(conn)
+}
+
+sql_storer!(PgConnection, Pg);
@@ -0,0 +1,47 @@
+use diesel::result::QueryResult;
+use diesel::select;
+use diesel::sql_types::Integer;
+use diesel::sqlite::Sqlite;
+use diesel::SqliteConnection;
+
+no_arg_sql_function!(last_insert_rowid, Integer);
+
+macro_rules! insert_id {
+    ($conn:expr, $table:ident, $row:expr) => {{
+        let conn = $conn;
+        diesel::insert_into($table::table).values($row).execute(conn).and_then(|_| select(last_insert_rowid).first::<i32>(conn))
+    }};
+}
+
+// SQLite has no DEFAULT keyword in a multi-row VALUES list, so the rows go in one by one inside a transaction.
+macro_rules! insert_all {
+    ($conn:expr, $table:ident, $rows:expr) => {{
+        let conn = $conn;
+        conn.transaction::<_, diesel::result::Error, _>(|| {
+            let mut n = 0;
+            for row in $rows {
+                n += diesel::insert_into($table::table).values(row).execute(conn)?;
+            }
+            Ok(n)
+        })
+    }};
+}
+
+macro_rules! insert_ids {
+    ($conn:expr, $table:ident, $rows:expr $(; $($key:ident),+)?) => {{
+        let conn = $conn;
+        conn.transaction::<_, diesel::result::Error, _>(|| {
+            let mut ids = Vec::new();
+            for row in $rows {
+                ids.push(insert_id!(conn, $table, row)?);
+            }
+            Ok(ids)
+        })
+    }};
+}
+
+fn upsert_reorder_point(conn: &SqliteConnection, point: ReorderPoint) -> QueryResult<usize> {
+    diesel::replace_into(reorder_point::table).values(point).execute(conn)
+}
+
+sql_storer!(SqliteConnection, Sqlite);
@@ -0,0 +1,1184 @@
+// The repositories shared by the SQL backends, expanded once per connection type.
+// A backend module defines insert_id!, insert_all! and insert_ids!, the ways it reads back generated ids, and upsert_reorder_point before expanding it.
+// insert_ids! is handed the columns of a unique key of the table, if it has one, for backends that have to look the ids up.
+macro_rules! sql_storer {
+    ($conn:ty, $backend:ty) => {
+        use super::super::dao;
+        use super::super::dao::{ComponentInfoStorer, ComponentStorer, DeviceEventStorer, DeviceInfoStorer, DeviceStorer, FaultStorer, InventoryStorer, RelationStorer, StatusHistoryStorer, SubsystemInfoStorer, SubsystemStorer, Transactor, WorkOrderStorer};
+        use super::super::model::*;
+        use super::super::schema::*;
+        use diesel::connection::TransactionManager;
+        use diesel::dsl::{InnerJoin, IntoBoxed};
+        use diesel::r2d2::{ConnectionManager, PooledConnection};
+        use diesel::{BelongingToDsl, BoolExpressionMethods, Connection, ExpressionMethods, GroupedBy, OptionalExtension, QueryDsl, RunQueryDsl, TextExpressionMethods};
+        use std::collections::HashMap;
+        use std::rc::Rc;
+
+        //===========================================================device info===================================================
+
+
+        pub struct DeviceInfoRepository(Rc<PooledConnection<ConnectionManager<$conn>>>);
+
+
+        impl DeviceInfoRepository {
+            pub fn new(conn: Rc<PooledConnection<ConnectionManager<$conn>>>) -> Self {
+                DeviceInfoRepository(conn)
+            }
+
+            fn boxed_query(&self, query: &DeviceInfoQuery) -> device_info::BoxedQuery<$backend> {
+                let mut q = device_info::table.into_boxed();
+                if let Some(v) = query.name.clone() {
+                    q = q.filter(device_info::name.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.model.clone() {
+                    q = q.filter(device_info::model.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.maintain_interval_begin {
+                    q = q.filter(device_info::maintain_interval.ge(v));
+                }
+                if let Some(v) = query.maintain_interval_end {
+                    q = q.filter(device_info::maintain_interval.lt(v));
+                }
+                q
+            }
+        }
+
+        impl DeviceInfoStorer for DeviceInfoRepository {
+            fn insert(&self, info: DeviceInfoInsert) -> dao::Result<i32> {
+                Ok(insert_id!(self.0.as_ref(), device_info, info)?)
+            }
+
+            fn bulk_insert(&self, infos: &Vec<DeviceInfoInsert>) -> dao::Result<usize> {
+                Ok(insert_all!(self.0.as_ref(), device_info, infos)?)
+            }
+
+            fn bulk_insert_ids(&self, infos: &Vec<DeviceInfoInsert>) -> dao::Result<Vec<i32>> {
+                Ok(insert_ids!(self.0.as_ref(), device_info, infos; name, model)?)
+            }
+
+            fn delete(&self, id: i32) -> dao::Result<usize> {
+                Ok(diesel::delete(device_info::table.find(id)).execute(self.0.as_ref())?)
+            }
+
+            fn bulk_delete(&self, query: DeviceInfoQuery) -> dao::Result<usize> {
+                let mut q = diesel::delete(device_info::table).into_boxed();
+                if let Some(v) = query.name {
+                    q = q.filter(device_info::name.eq(v));
+                }
+                if let Some(v) = query.model {
+                    q = q.filter(device_info::model.eq(v));
+                }
+                if let Some(v) = query.maintain_interval_begin {
+                    q = q.filter(device_info::maintain_interval.ge(v));
+                }
+                if let Some(v) = query.maintain_interval_end {
+                    q = q.filter(device_info::maintain_interval.lt(v));
+                }
+                Ok(q.execute(self.0.as_ref())?)
+            }
+
+            fn update(&self, id: i32, upd: DeviceInfoUpdate) -> dao::Result<usize> {
+                Ok(diesel::update(device_info::table).filter(device_info::id.eq(id)).set(upd).execute(self.0.as_ref())?)
+            }
+
+            fn get(&self, id: i32) -> dao::Result<DeviceInfo> {
+                Ok(device_info::table.find(id).first(self.0.as_ref())?)
+            }
+
+            fn find(&self, name: &str, model: &str) -> dao::Result<Option<DeviceInfo>> {
+                Ok(device_info::table
+                    .filter(device_info::name.eq(name).and(device_info::model.eq(model)))
+                    .first(self.0.as_ref())
+                    .optional()?)
+            }
+
+            fn query(&self, query: &DeviceInfoQuery) -> dao::Result<(Vec<DeviceInfo>, i64)> {
+                let q = self.boxed_query(query).limit(query.size).offset((query.page - 1) * query.size).load(self.0.as_ref())?;
+                let cq = self.boxed_query(query).count().first(self.0.as_ref())?;
+                Ok((q, cq))
+            }
+
+            fn query_by_subsystem_info(&self, subinfoid: i32, query: DeviceInfoQuery) -> dao::Result<(Vec<DeviceInfo>, i64)> {
+                let t = device_info::table
+                    .inner_join(deviceinfo_subsysteminfo::table.inner_join(subsystem_info::table))
+                    .filter(subsystem_info::id.eq(subinfoid));
+                let mut q = t.select(device_info::all_columns).limit(query.size).offset((query.page - 1) * query.size).into_boxed();
+                let mut cq = t.count().into_boxed();
+                if let Some(v) = query.name {
+                    q = q.filter(device_info::name.like(format!("%{}%", v)));
+                    cq = cq.filter(device_info::name.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.model {
+                    q = q.filter(device_info::model.like(format!("%{}%", v)));
+                    cq = cq.filter(device_info::model.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.maintain_interval_begin {
+                    q = q.filter(device_info::maintain_interval.ge(v));
+                    cq = cq.filter(device_info::maintain_interval.ge(v));
+                }
+                if let Some(v) = query.maintain_interval_end {
+                    q = q.filter(device_info::maintain_interval.lt(v));
+                    cq = cq.filter(device_info::maintain_interval.lt(v));
+                }
+                self.0.as_ref().transaction(|| Ok((q.load(self.0.as_ref())?, cq.first(self.0.as_ref())?)))
+            }
+
+            fn count(&self, query: DeviceInfoQuery) -> dao::Result<i64> {
+                let mut q = device_info::table.into_boxed();
+                if let Some(v) = query.name {
+                    q = q.filter(device_info::name.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.model {
+                    q = q.filter(device_info::model.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.maintain_interval_begin {
+                    q = q.filter(device_info::maintain_interval.ge(v));
+                }
+                if let Some(v) = query.maintain_interval_end {
+                    q = q.filter(device_info::maintain_interval.lt(v));
+                }
+                Ok(q.count().first(self.0.as_ref())?)
+            }
+
+            fn is_exist(&self, id: i32) -> dao::Result<bool> {
+                Ok(device_info::table.filter(device_info::id.eq(id)).count().execute(self.0.as_ref())? > 0)
+            }
+
+            fn detail(&self, id: i32) -> dao::Result<(DeviceInfo, Vec<(SubsystemInfo, Vec<(ComponentInfo, i32)>)>)> {
+                let dev: DeviceInfo = device_info::table.find(id).first(self.0.as_ref())?;
+                let subs: Vec<SubsystemInfo> = DeviceinfoSubsysteminfo::belonging_to(&dev)
+                    .inner_join(subsystem_info::table)
+                    .select(subsystem_info::all_columns)
+                    .load(self.0.as_ref())?;
+                let rows: Vec<(i32, ComponentInfo, i32)> = subsysteminfo_componentinfo::table
+                    .inner_join(component_info::table)
+                    .filter(subsysteminfo_componentinfo::device_info_id.eq(dev.id))
+                    .select((subsysteminfo_componentinfo::subsystem_info_id, component_info::all_columns, subsysteminfo_componentinfo::quantity))
+                    .load(self.0.as_ref())?;
+                let mut coms: HashMap<i32, Vec<(ComponentInfo, i32)>> = HashMap::new();
+                for (subid, com, quantity) in rows {
+                    coms.entry(subid).or_insert_with(Vec::new).push((com, quantity));
+                }
+                let subs = subs
+                    .into_iter()
+                    .map(|s| {
+                        let c = coms.remove(&s.id).unwrap_or_default();
+                        (s, c)
+                    })
+                    .collect();
+                Ok((dev, subs))
+            }
+        }
+
+        pub struct SubsystemInfoRepository(Rc<PooledConnection<ConnectionManager<$conn>>>);
+
+        impl SubsystemInfoRepository {
+            pub fn new(conn: Rc<PooledConnection<ConnectionManager<$conn>>>) -> Self {
+                SubsystemInfoRepository(conn)
+            }
+
+            fn boxed_query(&self, query: &SubsystemInfoQuery) -> subsystem_info::BoxedQuery<$backend> {
+                let mut q = subsystem_info::table.into_boxed();
+                if let Some(v) = query.name.clone() {
+                    q = q.filter(subsystem_info::name.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.maintain_interval_begin {
+                    q = q.filter(subsystem_info::maintain_interval.ge(v));
+                }
+                if let Some(v) = query.maintain_interval_end {
+                    q = q.filter(subsystem_info::maintain_interval.lt(v));
+                }
+                q
+            }
+        }
+
+
+
+        impl<'a> SubsystemInfoStorer for SubsystemInfoRepository {
+            fn insert(&self, info: SubsystemInfoInsert) -> dao::Result<i32> {
+                Ok(insert_id!(self.0.as_ref(), subsystem_info, info)?)
+            }
+
+            fn bulk_insert(&self, infos: &Vec<SubsystemInfoInsert>) -> dao::Result<usize> {
+                Ok(insert_all!(self.0.as_ref(), subsystem_info, infos)?)
+            }
+
+            fn bulk_insert_ids(&self, infos: &Vec<SubsystemInfoInsert>) -> dao::Result<Vec<i32>> {
+                Ok(insert_ids!(self.0.as_ref(), subsystem_info, infos; name)?)
+            }
+
+            fn delete(&self, id: i32) -> dao::Result<usize> {
+                Ok(diesel::delete(subsystem_info::table.find(id)).execute(self.0.as_ref())?)
+            }
+
+            fn update(&self, id: i32, upd: SubsystemInfoUpdate) -> dao::Result<usize> {
+                Ok(diesel::update(subsystem_info::table.find(id)).set(upd).execute(self.0.as_ref())?)
+            }
+
+            fn get(&self, id: i32) -> dao::Result<SubsystemInfo> {
+                Ok(subsystem_info::table.find(id).first(self.0.as_ref())?)
+            }
+
+            fn find(&self, name: &str) -> dao::Result<Option<SubsystemInfo>> {
+                Ok(subsystem_info::table.filter(subsystem_info::name.eq(name)).first(self.0.as_ref()).optional()?)
+            }
+
+            fn query(&self, query: &SubsystemInfoQuery) -> dao::Result<(Vec<SubsystemInfo>, i64)> {
+                let v = self.boxed_query(query).limit(query.size).offset((query.page - 1) * query.size).load(self.0.as_ref())?;
+                let c = self.boxed_query(query).count().first(self.0.as_ref())?;
+                Ok((v, c))
+            }
+
+            fn query_by_device_info(&self, devinfoid: i32, query: SubsystemInfoQuery) -> dao::Result<(Vec<SubsystemInfo>, i64)> {
+                let t = device_info::table
+                    .inner_join(deviceinfo_subsysteminfo::table.inner_join(subsystem_info::table))
+                    .filter(device_info::id.eq(devinfoid));
+                let mut q = t.select(subsystem_info::all_columns).limit(query.size).offset((query.page - 1) * query.size).into_boxed();
+                let mut cq = t.count().into_boxed();
+                if let Some(v) = query.name {
+                    q = q.filter(subsystem_info::name.like(format!("%{}%", v)));
+                    cq = cq.filter(subsystem_info::name.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.maintain_interval_begin {
+                    q = q.filter(subsystem_info::maintain_interval.ge(v));
+                    cq = cq.filter(subsystem_info::maintain_interval.ge(v));
+                }
+                if let Some(v) = query.maintain_interval_end {
+                    q = q.filter(subsystem_info::maintain_interval.lt(v));
+                    cq = cq.filter(subsystem_info::maintain_interval.lt(v));
+                }
+                self.0.as_ref().transaction(|| Ok((q.load(self.0.as_ref())?, cq.first(self.0.as_ref())?)))
+            }
+
+            fn query_by_component_info(&self, comid: i32, query: SubsystemInfoQuery) -> dao::Result<(Vec<SubsystemInfo>, i64)> {
+                let t = subsystem_info::table
+                    .inner_join(subsysteminfo_componentinfo::table.inner_join(component_info::table))
+                    .filter(component_info::id.eq(comid));
+                let mut q = t.select(subsystem_info::all_columns).limit(query.size).offset((query.page - 1) * query.size).into_boxed();
+                let mut cq = t.count().into_boxed();
+                if let Some(v) = query.name {
+                    q = q.filter(subsystem_info::name.like(format!("%{}%", v)));
+                    cq = cq.filter(subsystem_info::name.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.maintain_interval_begin {
+                    q = q.filter(subsystem_info::maintain_interval.ge(v));
+                    cq = cq.filter(subsystem_info::maintain_interval.ge(v));
+                }
+                if let Some(v) = query.maintain_interval_end {
+                    q = q.filter(subsystem_info::maintain_interval.lt(v));
+                    cq = cq.filter(subsystem_info::maintain_interval.lt(v));
+                }
+                self.0.as_ref().transaction(|| Ok((q.load(self.0.as_ref())?, cq.first(self.0.as_ref())?)))
+            }
+
+            fn count(&self, query: SubsystemInfoQuery) -> dao::Result<i64> {
+                let mut q = subsystem_info::table.count().into_boxed();
+                if let Some(v) = query.name {
+                    q = q.filter(subsystem_info::name.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.maintain_interval_begin {
+                    q = q.filter(subsystem_info::maintain_interval.ge(v));
+                }
+                if let Some(v) = query.maintain_interval_end {
+                    q = q.filter(subsystem_info::maintain_interval.lt(v));
+                }
+                Ok(q.first(self.0.as_ref())?)
+            }
+
+            fn is_exist(&self, id: i32) -> dao::Result<bool> {
+                Ok(subsystem_info::table.filter(subsystem_info::id.eq(id)).count().execute(self.0.as_ref())? > 0)
+            }
+        }
+
+        pub struct ComponentInfoRepository(Rc<PooledConnection<ConnectionManager<$conn>>>);
+
+        impl ComponentInfoRepository {
+            pub fn new(conn: Rc<PooledConnection<ConnectionManager<$conn>>>) -> Self {
+                ComponentInfoRepository(conn)
+            }
+
+            fn boxed_query(&self, query: &ComponentInfoQuery) -> component_info::BoxedQuery<$backend> {
+                let mut q = component_info::table.into_boxed();
+                if let Some(v) = query.name.clone() {
+                    q = q.filter(component_info::name.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.model.clone() {
+                    q = q.filter(component_info::model.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.maintain_interval_begin {
+                    q = q.filter(component_info::maintain_interval.ge(v));
+                }
+                if let Some(v) = query.maintain_interval_end {
+                    q = q.filter(component_info::maintain_interval.lt(v));
+                }
+                q
+            }
+        }
+
+        impl ComponentInfoStorer for ComponentInfoRepository {
+            fn insert(&self, info: ComponentInfoInsert) -> dao::Result<i32> {
+                Ok(insert_id!(self.0.as_ref(), component_info, info)?)
+            }
+
+            fn bulk_insert(&self, infos: &Vec<ComponentInfoInsert>) -> dao::Result<usize> {
+                Ok(insert_all!(self.0.as_ref(), component_info, infos)?)
+            }
+
+            fn bulk_insert_ids(&self, infos: &Vec<ComponentInfoInsert>) -> dao::Result<Vec<i32>> {
+                Ok(insert_ids!(self.0.as_ref(), component_info, infos; name, model)?)
+            }
+
+            fn delete(&self, id: i32) -> dao::Result<usize> {
+                Ok(diesel::delete(component_info::table.find(id)).execute(self.0.as_ref())?)
+            }
+
+            fn update(&self, id: i32, upd: ComponentInfoUpdate) -> dao::Result<usize> {
+                Ok(diesel::update(component_info::table.find(id)).set(upd).execute(self.0.as_ref())?)
+            }
+
+            fn get(&self, id: i32) -> dao::Result<ComponentInfo> {
+                Ok(component_info::table.find(id).first(self.0.as_ref())?)
+            }
+
+            fn find(&self, name: &str, model: &str) -> dao::Result<Option<ComponentInfo>> {
+                Ok(component_info::table
+                    .filter(component_info::name.eq(name).and(component_info::model.eq(model)))
+                    .first(self.0.as_ref())
+                    .optional()?)
+            }
+
+            fn query(&self, query: &ComponentInfoQuery) -> dao::Result<(Vec<ComponentInfo>, i64)> {
+                let v = self.boxed_query(query).limit(query.size).offset((query.page - 1) * query.size).load(self.0.as_ref())?;
+                let c = self.boxed_query(query).count().first(self.0.as_ref())?;
+                Ok((v, c))
+            }
+
+            fn query_by_subsystem_info(&self, subinfoid: i32, query: ComponentInfoQuery) -> dao::Result<(Vec<ComponentInfo>, i64)> {
+                let t = component_info::table
+                    .inner_join(subsysteminfo_componentinfo::table.inner_join(subsystem_info::table))
+                    .filter(subsystem_info::id.eq(subinfoid));
+                let mut q = t.select(component_info::all_columns).limit(query.size).offset((query.page - 1) * query.size).into_boxed();
+                let mut cq = t.count().into_boxed();
+                if let Some(v) = query.name {
+                    q = q.filter(component_info::name.like(format!("%{}%", v)));
+                    cq = cq.filter(component_info::name.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.model {
+                    q = q.filter(component_info::model.like(format!("%{}%", v)));
+                    cq = cq.filter(component_info::model.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.maintain_interval_begin {
+                    q = q.filter(component_info::maintain_interval.ge(v));
+                    cq = cq.filter(component_info::maintain_interval.ge(v));
+                }
+                if let Some(v) = query.maintain_interval_end {
+                    q = q.filter(component_info::maintain_interval.lt(v));
+                    cq = cq.filter(component_info::maintain_interval.lt(v));
+                }
+                self.0.as_ref().transaction(|| Ok((q.load(self.0.as_ref())?, cq.first(self.0.as_ref())?)))
+            }
+
+            fn count(&self, query: ComponentInfoQuery) -> dao::Result<i64> {
+                let mut q = component_info::table.count().into_boxed();
+                if let Some(v) = query.name {
+                    q = q.filter(component_info::name.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.model {
+                    q = q.filter(component_info::model.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.maintain_interval_begin {
+                    q = q.filter(component_info::maintain_interval.ge(v));
+                }
+                if let Some(v) = query.maintain_interval_end {
+                    q = q.filter(component_info::maintain_interval.lt(v));
+                }
+                Ok(q.first(self.0.as_ref())?)
+            }
+
+            fn is_exist(&self, id: i32) -> dao::Result<bool> {
+                Ok(component_info::table.filter(component_info::id.eq(id)).count().execute(self.0.as_ref())? > 0)
+            }
+        }
+
+        pub struct DeviceRepository(Rc<PooledConnection<ConnectionManager<$conn>>>);
+
+        impl DeviceRepository {
+            pub fn new(conn: Rc<PooledConnection<ConnectionManager<$conn>>>) -> Self {
+                DeviceRepository(conn)
+            }
+            fn boxed_query(&self, query: &DeviceQuery) -> device::BoxedQuery<$backend> {
+                let mut q = device::table.into_boxed();
+                if let Some(v) = query.name.clone() {
+                    q = q.filter(device::name.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.model.clone() {
+                    q = q.filter(device::model.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.maintain_interval_begin {
+                    q = q.filter(device::maintain_interval.ge(v));
+                }
+                if let Some(v) = query.maintain_interval_end {
+                    q = q.filter(device::maintain_interval.lt(v));
+                }
+                if let Some(v) = query.last_start_at_begin.as_ref() {
+                    q = q.filter(device::last_start_at.ge(v.0));
+                }
+                if let Some(v) = query.last_start_at_end.as_ref() {
+                    q = q.filter(device::last_start_at.lt(v.0));
+                }
+                if let Some(v) = query.last_stop_at_begin.as_ref() {
+                    q = q.filter(device::last_stop_at.ge(v.0));
+                }
+                if let Some(v) = query.last_stop_at_end.as_ref() {
+                    q = q.filter(device::last_stop_at.lt(v.0));
+                }
+                if let Some(v) = query.total_duration_begin {
+                    q = q.filter(device::total_duration.ge(v));
+                }
+                if let Some(v) = query.total_duration_end {
+                    q = q.filter(device::total_duration.lt(v));
+                }
+                if let Some(v) = query.status {
+                    q = q.filter(device::status.eq(v));
+                }
+                if let Some(v) = query.device_info_id {
+                    q = q.filter(device::device_info_id.eq(v));
+                }
+                q
+            }
+        }
+
+        impl DeviceStorer for DeviceRepository {
+            fn insert(&self, dev: DeviceInsert) -> dao::Result<i32> {
+                Ok(insert_id!(self.0.as_ref(), device, dev)?)
+            }
+
+            fn bulk_insert(&self, devs: &Vec<DeviceInsert>) -> dao::Result<usize> {
+                Ok(insert_all!(self.0.as_ref(), device, devs)?)
+            }
+
+            fn bulk_insert_ids(&self, devs: &Vec<DeviceInsert>) -> dao::Result<Vec<i32>> {
+                Ok(insert_ids!(self.0.as_ref(), device, devs)?)
+            }
+
+            fn delete(&self, id: i32) -> dao::Result<usize> {
+                Ok(diesel::delete(device::table.find(id)).execute(self.0.as_ref())?)
+            }
+
+            fn update(&self, id: i32, upd: DeviceUpdate) -> dao::Result<usize> {
+                Ok(diesel::update(device::table.find(id)).set(upd).execute(self.0.as_ref())?)
+            }
+
+            fn get(&self, id: i32) -> dao::Result<(Device, Vec<(Subsystem, Vec<Component>)>)> {
+                let dev: Device = device::table.find(id).first(self.0.as_ref())?;
+                let subs: Vec<Subsystem> = Subsystem::belonging_to(&dev).load(self.0.as_ref())?;
+                let coms: Vec<Component> = Component::belonging_to(&subs).load(self.0.as_ref())?;
+                let grouped_coms = coms.grouped_by(&subs);
+                let grouped_subs_coms = subs.into_iter().zip(grouped_coms).collect();
+                Ok((dev, grouped_subs_coms))
+            }
+
+            fn query(&self, query: &DeviceQuery) -> dao::Result<(Vec<(Device, Vec<(Subsystem, Vec<Component>)>)>, i64)> {
+                let mut q = self.boxed_query(query).order(device::id);
+                if let (Some(p), Some(s)) = (query.page, query.size) {
+                    q = q.limit(s).offset((p - 1) * s)
+                }
+                let c = self.boxed_query(query).count().first(self.0.as_ref())?;
+                let devs: Vec<Device> = q.load(self.0.as_ref())?;
+                let subs: Vec<Subsystem> = Subsystem::belonging_to(&devs).load(self.0.as_ref())?;
+                let coms: Vec<Component> = Component::belonging_to(&subs).load(self.0.as_ref())?;
+                let grouped_coms: Vec<Vec<Component>> = coms.grouped_by(&subs);
+                let grouped_subs_coms: Vec<Vec<(Subsystem, Vec<Component>)>> = subs.into_iter().zip(grouped_coms).grouped_by(&devs);
+                Ok((devs.into_iter().zip(grouped_subs_coms).collect(), c))
+            }
+        }
+
+        pub struct SubsystemRepository(Rc<PooledConnection<ConnectionManager<$conn>>>);
+
+        impl SubsystemRepository {
+            pub fn new(conn: Rc<PooledConnection<ConnectionManager<$conn>>>) -> Self {
+                SubsystemRepository(conn)
+            }
+
+            fn boxed_query(&self, query: &SubsystemQuery) -> IntoBoxed<'static, InnerJoin<device::table, subsystem::table>, $backend> {
+                let mut q = device::table.inner_join(subsystem::table).into_boxed();
+                if let Some(v) = query.device_name.clone() {
+                    q = q.filter(device::name.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.device_model.clone() {
+                    q = q.filter(device::model.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.device_maintain_interval_begin {
+                    q = q.filter(device::maintain_interval.ge(v));
+                }
+                if let Some(v) = query.device_maintain_interval_end {
+                    q = q.filter(device::maintain_interval.lt(v));
+                }
+                if let Some(v) = query.device_unicode.clone() {
+                    q = q.filter(device::unicode.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.device_last_start_at_begin.as_ref() {
+                    q = q.filter(device::last_start_at.ge(v.0));
+                }
+                if let Some(v) = query.device_last_start_at_end.as_ref() {
+                    q = q.filter(device::last_start_at.lt(v.0));
+                }
+                if let Some(v) = query.device_last_stop_at_begin.as_ref() {
+                    q = q.filter(device::last_stop_at.ge(v.0));
+                }
+                if let Some(v) = query.device_last_stop_at_end.as_ref() {
+                    q = q.filter(device::last_stop_at.lt(v.0));
+                }
+                if let Some(v) = query.device_total_duration_begin {
+                    q = q.filter(device::total_duration.ge(v));
+                }
+                if let Some(v) = query.device_total_duration_end {
+                    q = q.filter(device::total_duration.lt(v));
+                }
+                if let Some(v) = query.device_status {
+                    q = q.filter(device::status.eq(v));
+                }
+                if let Some(v) = query.subsystem_name.clone() {
+                    q = q.filter(subsystem::name.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.maintain_interval_begin {
+                    q = q.filter(subsystem::maintain_interval.ge(v));
+                }
+                if let Some(v) = query.maintain_interval_end {
+                    q = q.filter(subsystem::maintain_interval.lt(v));
+                }
+                q
+            }
+        }
+
+        impl SubsystemStorer for SubsystemRepository {
+            fn insert(&self, sub: SubsystemInsert) -> dao::Result<i32> {
+                Ok(insert_id!(self.0.as_ref(), subsystem, sub)?)
+            }
+
+            fn bulk_insert(&self, subs: &Vec<SubsystemInsert>) -> dao::Result<usize> {
+                Ok(insert_all!(self.0.as_ref(), subsystem, subs)?)
+            }
+
+            fn bulk_insert_ids(&self, subs: &Vec<SubsystemInsert>) -> dao::Result<Vec<i32>> {
+                Ok(insert_ids!(self.0.as_ref(), subsystem, subs; device_id, name)?)
+            }
+
+            fn delete(&self, id: i32) -> dao::Result<usize> {
+                Ok(diesel::delete(subsystem::table.find(id)).execute(self.0.as_ref())?)
+            }
+
+            fn udpate(&self, id: i32, upd: SubsystemUpdate) -> dao::Result<usize> {
+                Ok(diesel::update(subsystem::table.find(id)).set(upd).execute(self.0.as_ref())?)
+            }
+
+            fn get(&self, id: i32) -> dao::Result<(Device, Subsystem, Vec<Component>)> {
+                let dev_sub: (Device, Subsystem) = device::table.inner_join(subsystem::table).filter(subsystem::id.eq(id)).first(self.0.as_ref())?;
+                let coms: Vec<Component> = Component::belonging_to(&dev_sub.1).load(self.0.as_ref())?;
+                Ok((dev_sub.0, dev_sub.1, coms))
+            }
+
+            fn query(&self, query: &SubsystemQuery) -> dao::Result<(Vec<(Device, Subsystem)>, i64)> {
+                let mut q = self.boxed_query(query).order(subsystem::id);
+                if let (Some(p), Some(s)) = (query.page, query.size) {
+                    q = q.limit(s).offset((p - 1) * s)
+                }
+                let v = q.load(self.0.as_ref())?;
+                let c = self.boxed_query(query).count().first(self.0.as_ref())?;
+                Ok((v, c))
+            }
+        }
+
+        pub struct ComponentRepository(Rc<PooledConnection<ConnectionManager<$conn>>>);
+
+        impl ComponentRepository {
+            pub fn new(conn: Rc<PooledConnection<ConnectionManager<$conn>>>) -> Self {
+                ComponentRepository(conn)
+            }
+
+            fn boxed_query(&self, query: &ComponentQuery) -> IntoBoxed<'static, InnerJoin<device::table, InnerJoin<subsystem::table, component::table>>, $backend> {
+                let mut q = device::table.inner_join(subsystem::table.inner_join(component::table)).into_boxed();
+                if let Some(v) = query.device_name.clone() {
+                    q = q.filter(device::name.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.device_model.clone() {
+                    q = q.filter(device::model.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.device_maintain_interval_begin {
+                    q = q.filter(device::maintain_interval.ge(v));
+                }
+                if let Some(v) = query.device_maintain_interval_end {
+                    q = q.filter(device::maintain_interval.lt(v));
+                }
+                if let Some(v) = query.device_unicode.clone() {
+                    q = q.filter(device::unicode.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.device_last_start_at_begin.as_ref() {
+                    q = q.filter(device::last_start_at.ge(v.0));
+                }
+                if let Some(v) = query.device_last_start_at_end.as_ref() {
+                    q = q.filter(device::last_start_at.lt(v.0));
+                }
+                if let Some(v) = query.device_last_stop_at_begin.as_ref() {
+                    q = q.filter(device::last_stop_at.ge(v.0));
+                }
+                if let Some(v) = query.device_last_stop_at_end.as_ref() {
+                    q = q.filter(device::last_stop_at.lt(v.0));
+                }
+                if let Some(v) = query.device_total_duration_begin {
+                    q = q.filter(device::total_duration.ge(v));
+                }
+                if let Some(v) = query.device_total_duration_end {
+                    q = q.filter(device::total_duration.lt(v));
+                }
+                if let Some(v) = query.device_status {
+                    q = q.filter(device::status.eq(v));
+                }
+                if let Some(v) = query.subsystem_name.clone() {
+                    q = q.filter(subsystem::name.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.subsystem_maintain_interval_begin {
+                    q = q.filter(subsystem::maintain_interval.ge(v));
+                }
+                if let Some(v) = query.subsystem_maintain_interval_end {
+                    q = q.filter(subsystem::maintain_interval.lt(v));
+                }
+                if let Some(v) = query.name.clone() {
+                    q = q.filter(component::name.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.model.clone() {
+                    q = q.filter(component::model.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.maintain_interval_begin {
+                    q = q.filter(component::maintain_interval.ge(v));
+                }
+                if let Some(v) = query.maintain_interval_end {
+                    q = q.filter(component::maintain_interval.lt(v));
+                }
+                q
+            }
+        }
+
+        impl ComponentStorer for ComponentRepository {
+            fn insert(&self, com: ComponentInsert) -> dao::Result<i32> {
+                Ok(insert_id!(self.0.as_ref(), component, com)?)
+            }
+
+            fn bulk_insert(&self, coms: &Vec<ComponentInsert>) -> dao::Result<usize> {
+                Ok(insert_all!(self.0.as_ref(), component, coms)?)
+            }
+
+            fn bulk_insert_ids(&self, coms: &Vec<ComponentInsert>) -> dao::Result<Vec<i32>> {
+                Ok(insert_ids!(self.0.as_ref(), component, coms)?)
+            }
+
+            fn delete(&self, id: i32) -> dao::Result<usize> {
+                Ok(diesel::delete(component::table.find(id)).execute(self.0.as_ref())?)
+            }
+
+            fn update(&self, id: i32, upd: ComponentUpdate) -> dao::Result<usize> {
+                Ok(diesel::update(component::table.find(id)).set(upd).execute(self.0.as_ref())?)
+            }
+
+            fn get(&self, id: i32) -> dao::Result<(Device, Subsystem, Component)> {
+                let g: (Device, (Subsystem, Component)) = device::table
+                    .inner_join(subsystem::table.inner_join(component::table))
+                    .filter(component::id.eq(id))
+                    .first(self.0.as_ref())?;
+                Ok((g.0, (g.1).0, (g.1).1))
+            }
+
+            fn query(&self, query: &ComponentQuery) -> dao::Result<(Vec<(Device, Subsystem, Component)>, i64)> {
+                let mut q = self.boxed_query(query).order(component::id);
+                if let (Some(p), Some(s)) = (query.page, query.size) {
+                    q = q.limit(s).offset((p - 1) * s)
+                }
+                let v = q.load::<(Device, (Subsystem, Component))>(self.0.as_ref())?.into_iter().map(|(d, (s, c))| (d, s, c)).collect();
+                let c = self.boxed_query(query).count().first(self.0.as_ref())?;
+                Ok((v, c))
+            }
+        }
+
+        pub struct RelationRepository(Rc<PooledConnection<ConnectionManager<$conn>>>);
+
+        impl RelationRepository {
+            pub fn new(conn: Rc<PooledConnection<ConnectionManager<$conn>>>) -> RelationRepository {
+                RelationRepository(conn)
+            }
+        }
+
+        impl RelationStorer for RelationRepository {
+            fn insert_deviceinfo_subsysteminfo(&self, rel: DevinfoSubinfoInsert) -> dao::Result<usize> {
+                Ok(diesel::insert_into(deviceinfo_subsysteminfo::table).values(rel).execute(self.0.as_ref())?)
+            }
+
+            fn delete_deviceinfo_subsysteminfo(&self, devinfo_id: i32, subinfo_id: i32) -> dao::Result<usize> {
+                Ok(diesel::delete(deviceinfo_subsysteminfo::table)
+                    .filter(
+                        deviceinfo_subsysteminfo::device_info_id
+                            .eq(devinfo_id)
+                            .and(deviceinfo_subsysteminfo::subsystem_info_id.eq(subinfo_id)),
+                    )
+                    .execute(self.0.as_ref())?)
+            }
+
+            fn bulk_delete_deviceinfo_subsysteminfo(&self, devinfo_id: i32) -> dao::Result<usize> {
+                Ok(diesel::delete(deviceinfo_subsysteminfo::table)
+                    .filter(deviceinfo_subsysteminfo::device_info_id.eq(devinfo_id))
+                    .execute(self.0.as_ref())?)
+            }
+
+            fn has_deviceinfo_subsysteminfo(&self, devinfo_id: i32, subinfo_id: i32) -> dao::Result<bool> {
+                let c: i64 = deviceinfo_subsysteminfo::table
+                    .filter(
+                        deviceinfo_subsysteminfo::device_info_id
+                            .eq(devinfo_id)
+                            .and(deviceinfo_subsysteminfo::subsystem_info_id.eq(subinfo_id)),
+                    )
+                    .count()
+                    .first(self.0.as_ref())?;
+                Ok(c > 0)
+            }
+
+            fn insert_subsysteminfo_componentinfo(&self, rel: SubinfoCominfoInsert) -> dao::Result<usize> {
+                Ok(diesel::insert_into(subsysteminfo_componentinfo::table).values(rel).execute(self.0.as_ref())?)
+            }
+
+            fn delete_subsysteminfo_componentinfo(&self, devinfo_id: i32, subinfo_id: i32, cominfo_id: i32) -> dao::Result<usize> {
+                Ok(diesel::delete(subsysteminfo_componentinfo::table)
+                    .filter(
+                        subsysteminfo_componentinfo::device_info_id
+                            .eq(devinfo_id)
+                            .and(subsysteminfo_componentinfo::subsystem_info_id.eq(subinfo_id))
+                            .and(subsysteminfo_componentinfo::component_info_id.eq(cominfo_id)),
+                    )
+                    .execute(self.0.as_ref())?)
+            }
+
+            fn update_subsysteminfo_componentinfo(&self, devinfo_id: i32, subinfo_id: i32, cominfo_id: i32, quantity: i32) -> dao::Result<usize> {
+                Ok(diesel::update(subsysteminfo_componentinfo::table)
+                    .filter(
+                        subsysteminfo_componentinfo::device_info_id
+                            .eq(devinfo_id)
+                            .and(subsysteminfo_componentinfo::subsystem_info_id.eq(subinfo_id))
+                            .and(subsysteminfo_componentinfo::component_info_id.eq(cominfo_id)),
+                    )
+                    .set(subsysteminfo_componentinfo::quantity.eq(quantity))
+                    .execute(self.0.as_ref())?)
+            }
+
+            fn subsysteminfo_componentinfo_quantity(&self, devinfo_id: i32, subinfo_id: i32, cominfo_id: i32) -> dao::Result<Option<i32>> {
+                Ok(subsysteminfo_componentinfo::table
+                    .filter(
+                        subsysteminfo_componentinfo::device_info_id
+                            .eq(devinfo_id)
+                            .and(subsysteminfo_componentinfo::subsystem_info_id.eq(subinfo_id))
+                            .and(subsysteminfo_componentinfo::component_info_id.eq(cominfo_id)),
+                    )
+                    .select(subsysteminfo_componentinfo::quantity)
+                    .first(self.0.as_ref())
+                    .optional()?)
+            }
+
+            fn bulk_delete_subsysteminfo_componentinfo(&self, devinfo_id: i32, subinfo_id: i32) -> dao::Result<usize> {
+                Ok(diesel::delete(subsysteminfo_componentinfo::table)
+                    .filter(
+                        subsysteminfo_componentinfo::device_info_id
+                            .eq(devinfo_id)
+                            .and(subsysteminfo_componentinfo::subsystem_info_id.eq(subinfo_id)),
+                    )
+                    .execute(self.0.as_ref())?)
+            }
+        }
+
+        pub struct DeviceEventRepository(Rc<PooledConnection<ConnectionManager<$conn>>>);
+
+        impl DeviceEventRepository {
+            pub fn new(conn: Rc<PooledConnection<ConnectionManager<$conn>>>) -> DeviceEventRepository {
+                DeviceEventRepository(conn)
+            }
+
+            fn boxed_query(&self, query: &DeviceEventQuery) -> device_event::BoxedQuery<$backend> {
+                let mut q = device_event::table.into_boxed();
+                if let Some(v) = query.device_id {
+                    q = q.filter(device_event::device_id.eq(v));
+                }
+                if let Some(v) = query.kind {
+                    q = q.filter(device_event::kind.eq(v));
+                }
+                if let Some(v) = query.happen_at_begin.as_ref() {
+                    q = q.filter(device_event::happen_at.ge(v.0));
+                }
+                if let Some(v) = query.happen_at_end.as_ref() {
+                    q = q.filter(device_event::happen_at.lt(v.0));
+                }
+                q
+            }
+        }
+
+        impl DeviceEventStorer for DeviceEventRepository {
+            fn insert(&self, ev: DeviceEventInsert) -> dao::Result<i32> {
+                Ok(insert_id!(self.0.as_ref(), device_event, ev)?)
+            }
+
+            fn query(&self, query: &DeviceEventQuery) -> dao::Result<(Vec<DeviceEvent>, i64)> {
+                let mut q = self.boxed_query(query).order(device_event::happen_at.desc());
+                if let (Some(p), Some(s)) = (query.page, query.size) {
+                    q = q.limit(s).offset((p - 1) * s)
+                }
+                let v = q.load(self.0.as_ref())?;
+                let c = self.boxed_query(query).count().first(self.0.as_ref())?;
+                Ok((v, c))
+            }
+        }
+
+        pub struct StatusHistoryRepository(Rc<PooledConnection<ConnectionManager<$conn>>>);
+
+        impl StatusHistoryRepository {
+            pub fn new(conn: Rc<PooledConnection<ConnectionManager<$conn>>>) -> StatusHistoryRepository {
+                StatusHistoryRepository(conn)
+            }
+
+            fn boxed_query(&self, query: &StatusHistoryQuery) -> device_status_history::BoxedQuery<$backend> {
+                let mut q = device_status_history::table.into_boxed();
+                if let Some(v) = query.device_id {
+                    q = q.filter(device_status_history::device_id.eq(v));
+                }
+                if let Some(v) = query.from_status {
+                    q = q.filter(device_status_history::from_status.eq(v));
+                }
+                if let Some(v) = query.to_status {
+                    q = q.filter(device_status_history::to_status.eq(v));
+                }
+                if let Some(v) = query.reason {
+                    q = q.filter(device_status_history::reason.eq(v));
+                }
+                if let Some(v) = query.happen_at_begin.as_ref() {
+                    q = q.filter(device_status_history::happen_at.ge(v.0));
+                }
+                if let Some(v) = query.happen_at_end.as_ref() {
+                    q = q.filter(device_status_history::happen_at.lt(v.0));
+                }
+                q
+            }
+        }
+
+        impl StatusHistoryStorer for StatusHistoryRepository {
+            fn insert(&self, his: StatusHistoryInsert) -> dao::Result<i32> {
+                Ok(insert_id!(self.0.as_ref(), device_status_history, his)?)
+            }
+
+            fn query(&self, query: &StatusHistoryQuery) -> dao::Result<(Vec<StatusHistory>, i64)> {
+                let mut q = self.boxed_query(query).order(device_status_history::happen_at.desc());
+                if let (Some(p), Some(s)) = (query.page, query.size) {
+                    q = q.limit(s).offset((p - 1) * s)
+                }
+                let v = q.load(self.0.as_ref())?;
+                let c = self.boxed_query(query).count().first(self.0.as_ref())?;
+                Ok((v, c))
+            }
+        }
+
+        pub struct WorkOrderRepository(Rc<PooledConnection<ConnectionManager<$conn>>>);
+
+        impl WorkOrderRepository {
+            pub fn new(conn: Rc<PooledConnection<ConnectionManager<$conn>>>) -> WorkOrderRepository {
+                WorkOrderRepository(conn)
+            }
+
+            fn boxed_query(&self, query: &WorkOrderQuery) -> work_order::BoxedQuery<$backend> {
+                let mut q = work_order::table.into_boxed();
+                if let Some(v) = query.device_id {
+                    q = q.filter(work_order::device_id.eq(v));
+                }
+                if let Some(v) = query.subsystem_id {
+                    q = q.filter(work_order::subsystem_id.eq(v));
+                }
+                if let Some(v) = query.component_id {
+                    q = q.filter(work_order::component_id.eq(v));
+                }
+                if let Some(v) = query.target {
+                    q = q.filter(work_order::target.eq(v));
+                }
+                if let Some(v) = query.status {
+                    q = q.filter(work_order::status.eq(v));
+                }
+                if let Some(v) = query.technician.clone() {
+                    q = q.filter(work_order::technician.like(format!("%{}%", v)));
+                }
+                if let Some(v) = query.planned_at_begin.as_ref() {
+                    q = q.filter(work_order::planned_at.ge(v.0));
+                }
+                if let Some(v) = query.planned_at_end.as_ref() {
+                    q = q.filter(work_order::planned_at.lt(v.0));
+                }
+                if let Some(v) = query.finished_at_begin.as_ref() {
+                    q = q.filter(work_order::finished_at.ge(v.0));
+                }
+                if let Some(v) = query.finished_at_end.as_ref() {
+                    q = q.filter(work_order::finished_at.lt(v.0));
+                }
+                q
+            }
+        }
+
+        impl WorkOrderStorer for WorkOrderRepository {
+            fn insert(&self, order: WorkOrderInsert) -> dao::Result<i32> {
+                Ok(insert_id!(self.0.as_ref(), work_order, order)?)
+            }
+
+            fn delete(&self, id: i32) -> dao::Result<usize> {
+                Ok(diesel::delete(work_order::table.find(id)).execute(self.0.as_ref())?)
+            }
+
+            fn update(&self, id: i32, upd: WorkOrderUpdate) -> dao::Result<usize> {
+                Ok(diesel::update(work_order::table.find(id)).set(upd).execute(self.0.as_ref())?)
+            }
+
+            fn transition(&self, id: i32, trans: WorkOrderTransition) -> dao::Result<usize> {
+                Ok(diesel::update(work_order::table.find(id)).set(trans).execute(self.0.as_ref())?)
+            }
+
+            fn get(&self, id: i32) -> dao::Result<WorkOrder> {
+                Ok(work_order::table.find(id).first(self.0.as_ref())?)
+            }
+
+            fn query(&self, query: &WorkOrderQuery) -> dao::Result<(Vec<WorkOrder>, i64)> {
+                let mut q = self.boxed_query(query).order(work_order::id.desc());
+                if let (Some(p), Some(s)) = (query.page, query.size) {
+                    q = q.limit(s).offset((p - 1) * s)
+                }
+                let v = q.load(self.0.as_ref())?;
+                let c = self.boxed_query(query).count().first(self.0.as_ref())?;
+                Ok((v, c))
+            }
+        }
+
+        pub struct FaultRepository(Rc<PooledConnection<ConnectionManager<$conn>>>);
+
+        impl FaultRepository {
+            pub fn new(conn: Rc<PooledConnection<ConnectionManager<$conn>>>) -> FaultRepository {
+                FaultRepository(conn)
+            }
+
+            fn boxed_query(&self, query: &FaultTicketQuery) -> fault_ticket::BoxedQuery<$backend> {
+                let mut q = fault_ticket::table.into_boxed();
+                if let Some(v) = query.device_id {
+                    q = q.filter(fault_ticket::device_id.eq(v));
+                }
+                if let Some(v) = query.subsystem_id {
+                    q = q.filter(fault_ticket::subsystem_id.eq(v));
+                }
+                if let Some(v) = query.component_id {
+                    q = q.filter(fault_ticket::component_id.eq(v));
+                }
+                if let Some(v) = query.failure_code.clone() {
+                    q = q.filter(fault_ticket::failure_code.like(format!("%{}%", v)));
+                }
+                match query.open {
+                    Some(true) => q = q.filter(fault_ticket::repaired_at.is_null()),
+                    Some(false) => q = q.filter(fault_ticket::repaired_at.is_not_null()),
+                    None => {}
+                }
+                if let Some(v) = query.reported_at_begin.as_ref() {
+                    q = q.filter(fault_ticket::reported_at.ge(v.0));
+                }
+                if let Some(v) = query.reported_at_end.as_ref() {
+                    q = q.filter(fault_ticket::reported_at.lt(v.0));
+                }
+                if let Some(v) = query.repaired_at_begin.as_ref() {
+                    q = q.filter(fault_ticket::repaired_at.ge(v.0));
+                }
+                if let Some(v) = query.repaired_at_end.as_ref() {
+                    q = q.filter(fault_ticket::repaired_at.lt(v.0));
+                }
+                q
+            }
+        }
+
+        impl FaultStorer for FaultRepository {
+            fn insert(&self, ticket: FaultTicketInsert) -> dao::Result<i32> {
+                Ok(insert_id!(self.0.as_ref(), fault_ticket, ticket)?)
+            }
+
+            fn delete(&self, id: i32) -> dao::Result<usize> {
+                Ok(diesel::delete(fault_ticket::table.find(id)).execute(self.0.as_ref())?)
+            }
+
+            fn update(&self, id: i32, upd: FaultTicketUpdate) -> dao::Result<usize> {
+                Ok(diesel::update(fault_ticket::table.find(id)).set(upd).execute(self.0.as_ref())?)
+            }
+
+            fn get(&self, id: i32) -> dao::Result<FaultTicket> {
+                Ok(fault_ticket::table.find(id).first(self.0.as_ref())?)
+            }
+
+            fn query(&self, query: &FaultTicketQuery) -> dao::Result<(Vec<FaultTicket>, i64)> {
+                let mut q = self.boxed_query(query).order(fault_ticket::reported_at.desc());
+                if let (Some(p), Some(s)) = (query.page, query.size) {
+                    q = q.limit(s).offset((p - 1) * s)
+                }
+                let v = q.load(self.0.as_ref())?;
+                let c = self.boxed_query(query).count().first(self.0.as_ref())?;
+                Ok((v, c))
+            }
+        }
+
+        pub struct InventoryRepository(Rc<PooledConnection<ConnectionManager<$conn>>>);
+
+        impl InventoryRepository {
+            pub fn new(conn: Rc<PooledConnection<ConnectionManager<$conn>>>) -> InventoryRepository {
+                InventoryRepository(conn)
+            }
+
+            fn boxed_stock(&self, query: &StockQuery) -> stock::BoxedQuery<$backend> {
+                let mut q = stock::table.into_boxed();
+                if let Some(v) = query.component_info_id {
+                    q = q.filter(stock::component_info_id.eq(v));
+                }
+                if let Some(v) = query.location_id {
+                    q = q.filter(stock::location_id.eq(v));
+                }
+                q
+            }
+
+            fn boxed_movement(&self, query: &StockMovementQuery) -> stock_movement::BoxedQuery<$backend> {
+                let mut q = stock_movement::table.into_boxed();
+                if let Some(v) = query.component_info_id {
+                    q = q.filter(stock_movement::component_info_id.eq(v));
+                }
+                if let Some(v) = query.location_id {
+                    q = q.filter(stock_movement::from_location_id.eq(v).or(stock_movement::to_location_id.eq(v)));
+                }
+                if let Some(v) = query.kind {
+                    q = q.filter(stock_movement::kind.eq(v));
+                }
+                if let Some(v) = query.happen_at_begin.as_ref() {
+                    q = q.filter(stock_movement::happen_at.ge(v.0));
+                }
+                if let Some(v) = query.happen_at_end.as_ref() {
+                    q = q.filter(stock_movement::happen_at.lt(v.0));
+                }
+                q
+            }
+        }
+
+        impl InventoryStorer for InventoryRepository {
+            fn insert_location(&self, loc: StorageLocationInsert) -> dao::Result<i32> {
+                Ok(insert_id!(self.0.as_ref(), storage_location, loc)?)
+            }
+
+            fn delete_location(&self, id: i32) -> dao::Result<usize> {
+                Ok(diesel::delete(storage_location::table.find(id)).execute(self.0.as_ref())?)
+            }
+
+            fn locations(&self) -> dao::Result<Vec<StorageLocation>> {
+                Ok(storage_location::table.order(storage_location::name).load(self.0.as_ref())?)
+            }
+
+            fn stock_quantity(&self, cominfo_id: i32, location_id: i32) -> dao::Result<i32> {
+                let v: Option<i32> = stock::table
+                    .filter(stock::component_info_id.eq(cominfo_id).and(stock::location_id.eq(location_id)))
+                    .select(stock::quantity)
+                    .first(self.0.as_ref())
+                    .optional()?;
+                Ok(v.unwrap_or(0))
+            }
+
+            fn change_stock(&self, cominfo_id: i32, location_id: i32, delta: i32) -> dao::Result<usize> {
+                let n = diesel::update(stock::table.filter(stock::component_info_id.eq(cominfo_id).and(stock::location_id.eq(location_id))))
+                    .set(stock::quantity.eq(stock::quantity + delta))
+                    .execute(self.0.as_ref())?;
+                if n > 0 {
+                    return Ok(n);
+                }
+                Ok(diesel::insert_into(stock::table)
+                    .values(StockInsert {
+                        component_info_id: cominfo_id,
+                        location_id,
+                        quantity: delta,
+                    })
+                    .execute(self.0.as_ref())?)
+            }
+
+            fn take_stock(&self, cominfo_id: i32, location_id: i32, quantity: i32) -> dao::Result<usize> {
+                Ok(diesel::update(stock::table.filter(stock::component_info_id.eq(cominfo_id).and(stock::location_id.eq(location_id)).and(stock::quantity.ge(quantity))))
+                    .set(stock::quantity.eq(stock::quantity - quantity))
+                    .execute(self.0.as_ref())?)
+            }
+
+            fn query_stock(&self, query: &StockQuery) -> dao::Result<(Vec<Stock>, i64)> {
+                let mut q = self.boxed_stock(query).order((stock::component_info_id, stock::location_id));
+                if let (Some(p), Some(s)) = (query.page, query.size) {
+                    q = q.limit(s).offset((p - 1) * s)
+                }
+                let v = q.load(self.0.as_ref())?;
+                let c = self.boxed_stock(query).count().first(self.0.as_ref())?;
+                Ok((v, c))
+            }
+
+            fn insert_movement(&self, mov: StockMovementInsert) -> dao::Result<i32> {
+                Ok(insert_id!(self.0.as_ref(), stock_movement, mov)?)
+            }
+
+            fn query_movement(&self, query: &StockMovementQuery) -> dao::Result<(Vec<StockMovement>, i64)> {
+                let mut q = self.boxed_movement(query).order(stock_movement::happen_at.desc());
+                if let (Some(p), Some(s)) = (query.page, query.size) {
+                    q = q.limit(s).offset((p - 1) * s)
+                }
+                let v = q.load(self.0.as_ref())?;
+                let c = self.boxed_movement(query).count().first(self.0.as_ref())?;
+                Ok((v, c))
+            }
+
+            fn set_reorder_point(&self, point: ReorderPoint) -> dao::Result<usize> {
+                Ok(upsert_reorder_point(self.0.as_ref(), point)?)
+            }
+
+            fn low_stock(&self) -> dao::Result<Vec<(ComponentInfo, i64, i32)>> {
+                let points: Vec<(ComponentInfo, i32)> = reorder_point::table
+                    .inner_join(component_info::table)
+                    .select((component_info::all_columns, reorder_point::quantity))
+                    .order(component_info::name)
+                    .load(self.0.as_ref())?;
+                let stocks: Vec<(i32, i32)> = stock::table
+                    .filter(stock::component_info_id.eq_any(points.iter().map(|(c, _)| c.id).collect::<Vec<i32>>()))
+                    .select((stock::component_info_id, stock::quantity))
+                    .load(self.0.as_ref())?;
+                Ok(points
+                    .into_iter()
+                    .map(|(cominfo, point)| {
+                        let total = stocks.iter().filter(|(id, _)| *id == cominfo.id).map(|(_, q)| *q as i64).sum();
+                        (cominfo, total, point)
+                    })
+                    .filter(|(_, total, point)| *total <= *point as i64)
+                    .collect())
+            }
+        }
+
+        pub struct TransactionRepository(Rc<PooledConnection<ConnectionManager<$conn>>>);
+
+        impl TransactionRepository {
+            pub fn new(conn: Rc<PooledConnection<ConnectionManager<$conn>>>) -> TransactionRepository {
+                TransactionRepository(conn)
+            }
+        }
+
+        impl Transactor for TransactionRepository {
+            fn begin(&self) -> dao::Result<()> {
+                let conn: &$conn = self.0.as_ref();
+                Ok(conn.transaction_manager().begin_transaction(conn)?)
+            }
+
+            fn commit(&self) -> dao::Result<()> {
+                let conn: &$conn = self.0.as_ref();
+                Ok(conn.transaction_manager().commit_transaction(conn)?)
+            }
+
+            fn rollback(&self) -> dao::Result<()> {
+                let conn: &$conn = self.0.as_ref();
+                Ok(conn.transaction_manager().rollback_transaction(conn)?)
+            }
+        }
+    };
+}
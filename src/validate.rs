@@ -0,0 +1,721 @@
+use super::dao::{Error, Result};
+use super::model::*;
+
+// Column widths of the name and model columns in the schema.
+pub const NAME_MAX: usize = 64;
+pub const MODEL_MAX: usize = 255;
+pub const SIZE_MAX: i64 = 1000;
+
+pub trait Validate {
+    fn validate(&self) -> Result<()>;
+}
+
+// Collects every violation of a value instead of stopping at the first one.
+#[derive(Default)]
+pub(crate) struct Checker(Vec<FieldError>);
+
+impl Checker {
+    fn fail(&mut self, field: &'static str, message: String) {
+        self.0.push(FieldError { field, message });
+    }
+
+    fn text(&mut self, field: &'static str, v: &str, max: usize) {
+        if v.trim().is_empty() {
+            self.fail(field, "must not be empty".to_owned());
+        } else if v.chars().count() > max {
+            self.fail(field, format!("must not be longer than {} characters", max));
+        }
+    }
+
+    fn opt_text(&mut self, field: &'static str, v: &Option<String>, max: usize) {
+        if let Some(v) = v {
+            self.text(field, v, max);
+        }
+    }
+
+    // Filters may be empty, they only have to fit the column they are matched against.
+    fn filter(&mut self, field: &'static str, v: &Option<String>, max: usize) {
+        if let Some(v) = v {
+            if v.chars().count() > max {
+                self.fail(field, format!("must not be longer than {} characters", max));
+            }
+        }
+    }
+
+    fn non_negative(&mut self, field: &'static str, v: i32) {
+        if v < 0 {
+            self.fail(field, "must not be negative".to_owned());
+        }
+    }
+
+    fn opt_non_negative(&mut self, field: &'static str, v: Option<i32>) {
+        if let Some(v) = v {
+            self.non_negative(field, v);
+        }
+    }
+
+    fn positive(&mut self, field: &'static str, v: i32) {
+        if v <= 0 {
+            self.fail(field, "must be positive".to_owned());
+        }
+    }
+
+    fn opt_positive(&mut self, field: &'static str, v: Option<i32>) {
+        if let Some(v) = v {
+            self.positive(field, v);
+        }
+    }
+
+    fn range<T: PartialOrd>(&mut self, field: &'static str, begin: Option<T>, end: Option<T>) {
+        if let (Some(b), Some(e)) = (begin, end) {
+            if b > e {
+                self.fail(field, "begin must not be later than end".to_owned());
+            }
+        }
+    }
+
+    fn page(&mut self, page: i64, size: i64) {
+        if page < 1 {
+            self.fail("page", "must be at least 1".to_owned());
+        }
+        if size < 1 || size > SIZE_MAX {
+            self.fail("size", format!("must be between 1 and {}", SIZE_MAX));
+        }
+    }
+
+    fn opt_page(&mut self, page: Option<i64>, size: Option<i64>) {
+        if let Some(p) = page {
+            if p < 1 {
+                self.fail("page", "must be at least 1".to_owned());
+            }
+        }
+        if let Some(s) = size {
+            if s < 1 || s > SIZE_MAX {
+                self.fail("size", format!("must be between 1 and {}", SIZE_MAX));
+            }
+        }
+    }
+
+    // Fields the service keeps itself, such as the run bookkeeping of a device or the repair of a fault, cannot come in through a plain update.
+    pub(crate) fn forbidden(&mut self, field: &'static str, is_set: bool) {
+        if is_set {
+            self.fail(field, "is maintained by the service and cannot be set directly".to_owned());
+        }
+    }
+
+    pub(crate) fn finish(self) -> Result<()> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Invalid(self.0))
+        }
+    }
+}
+
+fn datetime(v: &Option<MyDatetime>) -> Option<&chrono::NaiveDateTime> {
+    v.as_ref().map(|v| &v.0)
+}
+
+// ==============================================================device_info================================================
+
+impl Validate for DeviceInfoInsert {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.text("name", &self.name, NAME_MAX);
+        c.text("model", &self.model, MODEL_MAX);
+        c.non_negative("maintain_interval", self.maintain_interval);
+        c.finish()
+    }
+}
+
+impl Validate for DeviceInfoUpdate {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.opt_text("name", &self.name, NAME_MAX);
+        c.opt_text("model", &self.model, MODEL_MAX);
+        c.opt_non_negative("maintain_interval", self.maintain_interval);
+        c.finish()
+    }
+}
+
+impl Validate for DeviceInfoQuery {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.filter("name", &self.name, NAME_MAX);
+        c.filter("model", &self.model, MODEL_MAX);
+        c.range("maintain_interval", self.maintain_interval_begin, self.maintain_interval_end);
+        c.page(self.page, self.size);
+        c.finish()
+    }
+}
+
+// ==============================================================subsystem_info================================================
+
+impl Validate for SubsystemInfoInsert {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.text("name", &self.name, NAME_MAX);
+        c.non_negative("maintain_interval", self.maintain_interval);
+        c.finish()
+    }
+}
+
+impl Validate for SubsystemInfoUpdate {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.opt_text("name", &self.name, NAME_MAX);
+        c.opt_non_negative("maintain_interval", self.maintain_interval);
+        c.finish()
+    }
+}
+
+impl Validate for SubsystemInfoQuery {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.filter("name", &self.name, NAME_MAX);
+        c.range("maintain_interval", self.maintain_interval_begin, self.maintain_interval_end);
+        c.page(self.page, self.size);
+        c.finish()
+    }
+}
+
+// ==============================================================component_info================================================
+
+impl Validate for ComponentInfoInsert {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.text("name", &self.name, NAME_MAX);
+        c.text("model", &self.model, MODEL_MAX);
+        c.non_negative("maintain_interval", self.maintain_interval);
+        c.finish()
+    }
+}
+
+impl Validate for ComponentInfoUpdate {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.opt_text("name", &self.name, NAME_MAX);
+        c.opt_text("model", &self.model, MODEL_MAX);
+        c.opt_non_negative("maintain_interval", self.maintain_interval);
+        c.finish()
+    }
+}
+
+impl Validate for ComponentInfoQuery {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.filter("name", &self.name, NAME_MAX);
+        c.filter("model", &self.model, MODEL_MAX);
+        c.range("maintain_interval", self.maintain_interval_begin, self.maintain_interval_end);
+        c.page(self.page, self.size);
+        c.finish()
+    }
+}
+
+// ==============================================================relations================================================
+
+impl Validate for DevinfoSubinfoInsert {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.positive("device_info_id", self.device_info_id);
+        c.positive("subsystem_info_id", self.subsystem_info_id);
+        c.finish()
+    }
+}
+
+impl Validate for SubinfoCominfoInsert {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.positive("device_info_id", self.device_info_id);
+        c.positive("subsystem_info_id", self.subsystem_info_id);
+        c.positive("component_info_id", self.component_info_id);
+        c.positive("quantity", self.quantity);
+        c.finish()
+    }
+}
+
+// ==============================================================device================================================
+
+impl Validate for DeviceInsert {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.opt_positive("device_info_id", self.device_info_id);
+        c.text("name", &self.name, NAME_MAX);
+        c.text("model", &self.model, MODEL_MAX);
+        c.non_negative("maintain_interval", self.maintain_interval);
+        c.text("unicode", &self.unicode, MODEL_MAX);
+        c.non_negative("total_duration", self.total_duration);
+        c.finish()
+    }
+}
+
+impl Validate for DeviceUpdate {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.opt_text("name", &self.name, NAME_MAX);
+        c.opt_text("model", &self.model, MODEL_MAX);
+        c.opt_non_negative("maintain_interval", self.maintain_interval);
+        c.opt_text("unicode", &self.unicode, MODEL_MAX);
+        c.opt_non_negative("total_duration", self.total_duration);
+        c.opt_non_negative("last_maintain_duration", self.last_maintain_duration);
+        c.finish()
+    }
+}
+
+impl Validate for DeviceQuery {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.opt_positive("device_info_id", self.device_info_id);
+        c.filter("name", &self.name, NAME_MAX);
+        c.filter("model", &self.model, MODEL_MAX);
+        c.range("maintain_interval", self.maintain_interval_begin, self.maintain_interval_end);
+        c.filter("unicode", &self.unicode, MODEL_MAX);
+        c.range("last_start_at", datetime(&self.last_start_at_begin), datetime(&self.last_start_at_end));
+        c.range("last_stop_at", datetime(&self.last_stop_at_begin), datetime(&self.last_stop_at_end));
+        c.range("total_duration", self.total_duration_begin, self.total_duration_end);
+        c.opt_page(self.page, self.size);
+        c.finish()
+    }
+}
+
+// ==============================================================subsystem================================================
+
+impl Validate for SubsystemInsert {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.positive("device_id", self.device_id);
+        c.opt_positive("subsystem_info_id", self.subsystem_info_id);
+        c.text("name", &self.name, NAME_MAX);
+        c.non_negative("maintain_interval", self.maintain_interval);
+        c.finish()
+    }
+}
+
+impl Validate for SubsystemUpdate {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.opt_text("name", &self.name, NAME_MAX);
+        c.opt_non_negative("maintain_interval", self.maintain_interval);
+        c.opt_non_negative("last_maintain_duration", self.last_maintain_duration);
+        c.finish()
+    }
+}
+
+impl Validate for SubsystemQuery {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.filter("device_name", &self.device_name, NAME_MAX);
+        c.filter("device_model", &self.device_model, MODEL_MAX);
+        c.range("device_maintain_interval", self.device_maintain_interval_begin, self.device_maintain_interval_end);
+        c.filter("device_unicode", &self.device_unicode, MODEL_MAX);
+        c.range("device_last_start_at", datetime(&self.device_last_start_at_begin), datetime(&self.device_last_start_at_end));
+        c.range("device_last_stop_at", datetime(&self.device_last_stop_at_begin), datetime(&self.device_last_stop_at_end));
+        c.range("device_total_duration", self.device_total_duration_begin, self.device_total_duration_end);
+        c.filter("subsystem_name", &self.subsystem_name, NAME_MAX);
+        c.range("maintain_interval", self.maintain_interval_begin, self.maintain_interval_end);
+        c.opt_page(self.page, self.size);
+        c.finish()
+    }
+}
+
+// ==============================================================component================================================
+
+impl Validate for ComponentInsert {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.positive("subsystem_id", self.subsystem_id);
+        c.opt_positive("component_info_id", self.component_info_id);
+        c.text("name", &self.name, NAME_MAX);
+        c.text("model", &self.model, MODEL_MAX);
+        c.non_negative("maintain_interval", self.maintain_interval);
+        c.non_negative("position", self.position);
+        c.finish()
+    }
+}
+
+impl Validate for ComponentUpdate {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.opt_text("name", &self.name, NAME_MAX);
+        c.opt_text("model", &self.model, MODEL_MAX);
+        c.opt_non_negative("maintain_interval", self.maintain_interval);
+        c.opt_non_negative("last_maintain_duration", self.last_maintain_duration);
+        c.finish()
+    }
+}
+
+impl Validate for ComponentQuery {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.filter("device_name", &self.device_name, NAME_MAX);
+        c.filter("device_model", &self.device_model, MODEL_MAX);
+        c.range("device_maintain_interval", self.device_maintain_interval_begin, self.device_maintain_interval_end);
+        c.filter("device_unicode", &self.device_unicode, MODEL_MAX);
+        c.range("device_last_start_at", datetime(&self.device_last_start_at_begin), datetime(&self.device_last_start_at_end));
+        c.range("device_last_stop_at", datetime(&self.device_last_stop_at_begin), datetime(&self.device_last_stop_at_end));
+        c.range("device_total_duration", self.device_total_duration_begin, self.device_total_duration_end);
+        c.filter("subsystem_name", &self.subsystem_name, NAME_MAX);
+        c.range("subsystem_maintain_interval", self.subsystem_maintain_interval_begin, self.subsystem_maintain_interval_end);
+        c.filter("name", &self.name, NAME_MAX);
+        c.filter("model", &self.model, MODEL_MAX);
+        c.range("maintain_interval", self.maintain_interval_begin, self.maintain_interval_end);
+        c.opt_page(self.page, self.size);
+        c.finish()
+    }
+}
+
+// ==============================================================work_order================================================
+
+impl Validate for WorkOrderInsert {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.positive("device_id", self.device_id);
+        c.opt_positive("subsystem_id", self.subsystem_id);
+        c.opt_positive("component_id", self.component_id);
+        c.text("technician", &self.technician, NAME_MAX);
+        c.finish()
+    }
+}
+
+impl Validate for WorkOrderUpdate {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.opt_text("technician", &self.technician, NAME_MAX);
+        c.finish()
+    }
+}
+
+impl Validate for WorkOrderQuery {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.opt_positive("device_id", self.device_id);
+        c.opt_positive("subsystem_id", self.subsystem_id);
+        c.opt_positive("component_id", self.component_id);
+        c.filter("technician", &self.technician, NAME_MAX);
+        c.range("planned_at", datetime(&self.planned_at_begin), datetime(&self.planned_at_end));
+        c.range("finished_at", datetime(&self.finished_at_begin), datetime(&self.finished_at_end));
+        c.opt_page(self.page, self.size);
+        c.finish()
+    }
+}
+
+// ==============================================================device_event================================================
+
+impl Validate for DeviceEventInsert {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.positive("device_id", self.device_id);
+        c.non_negative("duration", self.duration);
+        c.finish()
+    }
+}
+
+impl Validate for DeviceEventQuery {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.opt_positive("device_id", self.device_id);
+        c.range("happen_at", datetime(&self.happen_at_begin), datetime(&self.happen_at_end));
+        c.opt_page(self.page, self.size);
+        c.finish()
+    }
+}
+
+// ==============================================================status_history================================================
+
+impl Validate for StatusHistoryInsert {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.positive("device_id", self.device_id);
+        c.finish()
+    }
+}
+
+impl Validate for StatusHistoryQuery {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.opt_positive("device_id", self.device_id);
+        c.range("happen_at", datetime(&self.happen_at_begin), datetime(&self.happen_at_end));
+        c.opt_page(self.page, self.size);
+        c.finish()
+    }
+}
+
+// ==============================================================fault================================================
+
+impl Validate for FaultTicketInsert {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.positive("device_id", self.device_id);
+        c.positive("subsystem_id", self.subsystem_id);
+        c.opt_positive("component_id", self.component_id);
+        c.text("failure_code", &self.failure_code, NAME_MAX);
+        c.finish()
+    }
+}
+
+impl Validate for FaultTicketUpdate {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.opt_text("failure_code", &self.failure_code, NAME_MAX);
+        c.range("repaired_at", self.reported_at, self.repaired_at);
+        c.finish()
+    }
+}
+
+impl Validate for FaultTicketQuery {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.opt_positive("device_id", self.device_id);
+        c.opt_positive("subsystem_id", self.subsystem_id);
+        c.opt_positive("component_id", self.component_id);
+        c.filter("failure_code", &self.failure_code, NAME_MAX);
+        c.range("reported_at", datetime(&self.reported_at_begin), datetime(&self.reported_at_end));
+        c.range("repaired_at", datetime(&self.repaired_at_begin), datetime(&self.repaired_at_end));
+        c.opt_page(self.page, self.size);
+        c.finish()
+    }
+}
+
+// ==============================================================inventory================================================
+
+impl Validate for StorageLocationInsert {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.text("name", &self.name, NAME_MAX);
+        c.finish()
+    }
+}
+
+impl Validate for StockInsert {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.positive("component_info_id", self.component_info_id);
+        c.positive("location_id", self.location_id);
+        c.finish()
+    }
+}
+
+impl Validate for StockQuery {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.opt_positive("component_info_id", self.component_info_id);
+        c.opt_positive("location_id", self.location_id);
+        c.opt_page(self.page, self.size);
+        c.finish()
+    }
+}
+
+impl Validate for StockMovementInsert {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.positive("component_info_id", self.component_info_id);
+        c.opt_positive("from_location_id", self.from_location_id);
+        c.opt_positive("to_location_id", self.to_location_id);
+        match self.kind {
+            StockMovementKind::Adjust if self.quantity == 0 => c.fail("quantity", "must not be zero".to_owned()),
+            StockMovementKind::Adjust => {}
+            _ => c.positive("quantity", self.quantity),
+        }
+        c.finish()
+    }
+}
+
+impl Validate for StockMovementQuery {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.opt_positive("component_info_id", self.component_info_id);
+        c.opt_positive("location_id", self.location_id);
+        c.range("happen_at", datetime(&self.happen_at_begin), datetime(&self.happen_at_end));
+        c.opt_page(self.page, self.size);
+        c.finish()
+    }
+}
+
+// ==============================================================report================================================
+
+impl Validate for MaintainQuery {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.filter("device_name", &self.device_name, NAME_MAX);
+        c.filter("device_model", &self.device_model, MODEL_MAX);
+        c.opt_non_negative("due_within", self.due_within);
+        c.opt_page(self.page, self.size);
+        c.finish()
+    }
+}
+
+impl Validate for DemandQuery {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.filter("device_name", &self.device_name, NAME_MAX);
+        c.filter("device_model", &self.device_model, MODEL_MAX);
+        c.opt_positive("days", self.days);
+        c.opt_positive("hours", self.hours);
+        if self.days.is_some() == self.hours.is_some() {
+            c.fail("days", "exactly one of days or hours is required".to_owned());
+        }
+        c.finish()
+    }
+}
+
+impl Validate for ReliabilityQuery {
+    fn validate(&self) -> Result<()> {
+        let mut c = Checker::default();
+        c.range("begin", datetime(&self.begin), datetime(&self.end));
+        c.filter("device_name", &self.device_name, NAME_MAX);
+        c.filter("device_model", &self.device_model, MODEL_MAX);
+        c.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    // The fields a validation failure was reported for, in the order they were checked.
+    fn fields(r: Result<()>) -> Vec<&'static str> {
+        match r {
+            Err(Error::Invalid(errs)) => errs.iter().map(|e| e.field).collect(),
+            other => panic!("expected field errors, got {:?}", other),
+        }
+    }
+
+    fn at(day: u32) -> Option<MyDatetime> {
+        Some(MyDatetime(NaiveDate::from_ymd(2020, 1, day).and_hms(0, 0, 0)))
+    }
+
+    #[test]
+    fn accepts_a_valid_insert() {
+        let info = DeviceInfoInsert {
+            name: "pump".to_owned(),
+            model: "P-100".to_owned(),
+            maintain_interval: 0,
+        };
+        assert!(info.validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_an_empty_name() {
+        let info = DeviceInfoInsert {
+            name: "  ".to_owned(),
+            model: "P-100".to_owned(),
+            maintain_interval: 720,
+        };
+        assert_eq!(fields(info.validate()), vec!["name"]);
+    }
+
+    #[test]
+    fn rejects_a_name_over_the_column_width() {
+        let info = DeviceInfoInsert {
+            name: "x".repeat(NAME_MAX + 1),
+            model: "P-100".to_owned(),
+            maintain_interval: 720,
+        };
+        assert_eq!(fields(info.validate()), vec!["name"]);
+        let info = DeviceInfoInsert {
+            name: "x".repeat(NAME_MAX),
+            ..info
+        };
+        assert!(info.validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_a_negative_maintain_interval() {
+        let info = DeviceInfoUpdate {
+            maintain_interval: Some(-1),
+            ..Default::default()
+        };
+        assert_eq!(fields(info.validate()), vec!["maintain_interval"]);
+    }
+
+    #[test]
+    fn collects_every_violation() {
+        let info = DeviceInfoInsert {
+            name: String::new(),
+            model: "x".repeat(MODEL_MAX + 1),
+            maintain_interval: -1,
+        };
+        assert_eq!(fields(info.validate()), vec!["name", "model", "maintain_interval"]);
+    }
+
+    #[test]
+    fn rejects_page_zero_and_oversized_pages() {
+        let query = DeviceInfoQuery {
+            page: 0,
+            size: SIZE_MAX + 1,
+            ..Default::default()
+        };
+        assert_eq!(fields(query.validate()), vec!["page", "size"]);
+        let query = DeviceQuery {
+            page: Some(0),
+            size: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(fields(query.validate()), vec!["page", "size"]);
+        let query = DeviceQuery {
+            page: Some(1),
+            size: Some(SIZE_MAX),
+            ..Default::default()
+        };
+        assert!(query.validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_inverted_ranges() {
+        let query = DeviceQuery {
+            maintain_interval_begin: Some(10),
+            maintain_interval_end: Some(5),
+            last_start_at_begin: at(2),
+            last_start_at_end: at(1),
+            total_duration_begin: Some(3),
+            total_duration_end: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(fields(query.validate()), vec!["maintain_interval", "last_start_at", "total_duration"]);
+        let query = ComponentQuery {
+            device_last_stop_at_begin: at(3),
+            device_last_stop_at_end: at(2),
+            ..Default::default()
+        };
+        assert_eq!(fields(query.validate()), vec!["device_last_stop_at"]);
+    }
+
+    #[test]
+    fn accepts_open_and_equal_ranges() {
+        let query = DeviceQuery {
+            maintain_interval_begin: Some(5),
+            maintain_interval_end: Some(5),
+            last_start_at_begin: at(1),
+            ..Default::default()
+        };
+        assert!(query.validate().is_ok());
+    }
+
+    #[test]
+    fn forbidden_fields_fail_only_when_set() {
+        let mut c = Checker::default();
+        c.forbidden("total_duration", false);
+        assert!(c.finish().is_ok());
+
+        let mut c = Checker::default();
+        c.forbidden("last_start_at", true);
+        c.forbidden("total_duration", false);
+        c.forbidden("last_stop_at", true);
+        assert_eq!(fields(c.finish()), vec!["last_start_at", "last_stop_at"]);
+    }
+
+    #[test]
+    fn rejects_a_negative_due_window() {
+        let query = MaintainQuery {
+            due_within: Some(-1),
+            ..Default::default()
+        };
+        assert_eq!(fields(query.validate()), vec!["due_within"]);
+        let query = MaintainQuery {
+            due_within: Some(0),
+            ..Default::default()
+        };
+        assert!(query.validate().is_ok());
+    }
+}